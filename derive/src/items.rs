@@ -16,8 +16,100 @@ pub struct Event {
 	signature: NamedSignature,
 }
 
+/// Dispatch options set with `#[abi(..)]` on a method
+#[derive(Default)]
+pub struct MethodAttrs {
+	/// `#[abi(nonreentrant, storage = "pwasm_ethereum")]`: dispatch reverts if the contract is entered
+	/// again while the method runs
+	pub nonreentrant: bool,
+	/// Path of the module with the host `read(&H256) -> [u8; 32]` and `write(&H256, &[u8; 32])`
+	/// storage functions, which keep the flag of the `nonreentrant` guard
+	pub storage: Option<syn::Path>,
+	/// `#[constructor]` (or the `ctor` name): the method is dispatched as the table fallback
+	pub constructor: bool,
+	/// `#[fallback]`: the method is invoked for payloads without a known selector
+	pub fallback: bool,
+	/// `#[abi(signature = 0x12345678)]`: selector dispatched instead of the one computed from the
+	/// name and params, for selectors dictated by an external interface
+	pub selector: Option<u32>,
+	/// `#[abi(name = "transferFrom")]`: name of the method in the ABI, for interfaces whose names
+	/// don't follow the Rust method names
+	pub name: Option<String>,
+}
+
+impl MethodAttrs {
+	pub fn from_attrs(attrs: &[syn::Attribute]) -> Result<Self, String> {
+		let mut method_attrs = MethodAttrs::default();
+		for attr in attrs {
+			let nested = match attr.value {
+				syn::MetaItem::List(ref ident, ref nested) if ident.as_ref() == "abi" => nested,
+				_ => continue,
+			};
+			for item in nested {
+				match *item {
+					syn::NestedMetaItem::MetaItem(syn::MetaItem::Word(ref ident)) if ident.as_ref() == "nonreentrant" => {
+						method_attrs.nonreentrant = true;
+					},
+					syn::NestedMetaItem::MetaItem(syn::MetaItem::NameValue(ref ident, syn::Lit::Int(selector, _))) if ident.as_ref() == "signature" => {
+						if selector > u64::from(u32::MAX) {
+							return Err(format!("selector 0x{:x} does not fit in 4 bytes", selector));
+						}
+						method_attrs.selector = Some(selector as u32);
+					},
+					syn::NestedMetaItem::MetaItem(syn::MetaItem::NameValue(ref ident, syn::Lit::Str(ref path, _))) if ident.as_ref() == "storage" => {
+						let path = syn::parse_path(path).map_err(|_| format!("`{}` is not a path to the storage functions", path))?;
+						method_attrs.storage = Some(path);
+					},
+					syn::NestedMetaItem::MetaItem(syn::MetaItem::NameValue(ref ident, syn::Lit::Str(ref name, _))) if ident.as_ref() == "name" => {
+						if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+							return Err(format!("`{}` is not a valid ABI method name", name));
+						}
+						method_attrs.name = Some(name.clone());
+					},
+					_ => return Err(format!(
+						"unknown abi attribute `{}`, only `nonreentrant`, `storage = \"..\"`, `name = \"..\"` and `signature = 0x12345678` are supported",
+						quote! { #item },
+					)),
+				}
+			}
+		}
+		match (method_attrs.nonreentrant, method_attrs.storage.is_some()) {
+			(true, false) => Err("`nonreentrant` needs the module of the host storage functions, e.g. `#[abi(nonreentrant, storage = \"pwasm_ethereum\")]`".to_owned()),
+			(false, true) => Err("`storage` is only used by `nonreentrant`".to_owned()),
+			_ => Ok(method_attrs),
+		}
+	}
+
+	/// Name of the method in the ABI, the Rust name unless overridden with `name = ".."`
+	pub fn abi_name<'a>(&'a self, ident: &'a syn::Ident) -> &'a str {
+		self.name.as_ref().map_or(ident.as_ref(), |name| name.as_str())
+	}
+}
+
+fn has_word_attr(attrs: &[syn::Attribute], word: &str) -> bool {
+	attrs.iter().any(|a| match a.value {
+		syn::MetaItem::Word(ref ident) => ident.as_ref() == word,
+		_ => false
+	})
+}
+
+/// Whether the method is marked `#[event]`
+pub fn is_event(attrs: &[syn::Attribute]) -> bool {
+	has_word_attr(attrs, "event")
+}
+
+/// Whether the method is marked `#[fallback]`
+pub fn is_fallback(attrs: &[syn::Attribute]) -> bool {
+	has_word_attr(attrs, "fallback")
+}
+
+/// Whether the method is the constructor, either named `ctor` or marked `#[constructor]`
+pub fn is_constructor(ident: &syn::Ident, attrs: &[syn::Attribute]) -> bool {
+	ident.as_ref() == "ctor" || has_word_attr(attrs, "constructor")
+}
+
 pub enum Item {
-	Signature(syn::Ident, syn::MethodSig),
+	Signature(syn::Ident, syn::MethodSig, MethodAttrs),
 	Event(Event),
 	Other(syn::TraitItem),
 }
@@ -71,10 +163,7 @@ impl Item {
 		let attrs = source.attrs;
 		match node {
 			syn::TraitItemKind::Method(method_sig, None) => {
-				if attrs.iter().any(|a| match a.value {
-					syn::MetaItem::Word(ref ident) => ident.as_ref() == "event" ,
-					_ => false
-				}) {
+				if is_event(&attrs) {
 					let (indexed, non_indexed) = utils::iter_signature(&method_sig)
 						.partition(|&(ref pat, _)| quote! { #pat }.to_string().starts_with("indexed_"));
 
@@ -93,7 +182,10 @@ impl Item {
 					Item::Event(event)
 				} else {
 
-					Item::Signature(ident, method_sig)
+					let mut method_attrs = MethodAttrs::from_attrs(&attrs).expect("attributes are checked before generation");
+					method_attrs.constructor = is_constructor(&ident, &attrs);
+					method_attrs.fallback = is_fallback(&attrs);
+					Item::Signature(ident, method_sig, method_attrs)
 				}
 			},
 			_ => {
@@ -142,7 +234,7 @@ impl quote::ToTokens for Item {
 					)
 				]);
 			},
-			Item::Signature(ref name, ref method_sig) => {
+			Item::Signature(ref name, ref method_sig, _) => {
 				tokens.append_all(&[syn::TraitItem {
 					ident: name.clone(),
 					attrs: Vec::new(),
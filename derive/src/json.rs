@@ -0,0 +1,217 @@
+//! ABI JSON generation at macro expansion time
+
+use {abi, quote, syn, utils};
+use items::{Interface, Item};
+
+/// Builds the ABI JSON (as understood by solidity tooling) describing the interface
+pub fn abi_json(intf: &Interface) -> Json {
+	let entries = intf.items().iter().filter_map(|item| {
+		let mut json = Json::default();
+		match *item {
+			Item::Signature(_, _, ref attrs) if attrs.fallback => json.push_str(r#"{"type":"fallback","stateMutability":"nonpayable"}"#),
+			Item::Signature(_, ref method_sig, ref attrs) if attrs.constructor => {
+				json.push_str(r#"{"type":"constructor","inputs":["#);
+				json.append(inputs(method_sig, false));
+				json.push_str(r#"],"stateMutability":"nonpayable"}"#);
+			},
+			Item::Signature(ref ident, ref method_sig, ref attrs) => {
+				json.push_str(&format!(r#"{{"type":"function","name":"{}","inputs":["#, attrs.abi_name(ident)));
+				json.append(inputs(method_sig, false));
+				json.push_str(r#"],"outputs":["#);
+				json.append(outputs(method_sig));
+				json.push_str(&format!(r#"],"stateMutability":"{}""#, state_mutability(method_sig)));
+				// not derived from the name, so tooling needs it spelled out (`Abi::from_json` reads it back)
+				if let Some(selector) = attrs.selector {
+					json.push_str(&format!(r#","selector":"0x{:08x}""#, selector));
+				}
+				json.push_str("}");
+			},
+			Item::Event(ref event) => {
+				json.push_str(&format!(r#"{{"type":"event","name":"{}","inputs":["#, event.name.as_ref()));
+				json.append(inputs(&event.method_sig, true));
+				json.push_str(r#"],"anonymous":false}"#);
+			},
+			Item::Other(_) => return None,
+		}
+		Some(json)
+	});
+
+	let mut json = Json::default();
+	json.push_str("[");
+	json.append(Json::join(entries));
+	json.push_str("]");
+	json
+}
+
+/// ABI JSON as literal parts and `COMPONENTS_JSON` of structs deriving `AbiType`, which are only
+/// known once the contract is compiled
+#[derive(Default)]
+pub struct Json {
+	parts: Vec<Part>,
+}
+
+enum Part {
+	Literal(String),
+	Components(syn::Ty),
+}
+
+impl Json {
+	fn push_str(&mut self, s: &str) {
+		if let Some(&mut Part::Literal(ref mut literal)) = self.parts.last_mut() {
+			literal.push_str(s);
+			return;
+		}
+		self.parts.push(Part::Literal(s.to_owned()));
+	}
+
+	fn append(&mut self, other: Json) {
+		for part in other.parts {
+			match part {
+				Part::Literal(literal) => self.push_str(&literal),
+				components => self.parts.push(components),
+			}
+		}
+	}
+
+	fn join<I: IntoIterator<Item = Json>>(items: I) -> Json {
+		let mut json = Json::default();
+		for (i, item) in items.into_iter().enumerate() {
+			if i != 0 {
+				json.push_str(",");
+			}
+			json.append(item);
+		}
+		json
+	}
+}
+
+impl quote::ToTokens for Json {
+	/// A string literal, or a block joining the parts in a constant if there are structs
+	fn to_tokens(&self, tokens: &mut quote::Tokens) {
+		match self.parts.as_slice() {
+			[] => tokens.append_all(&[quote! { "" }]),
+			[Part::Literal(ref literal)] => tokens.append_all(&[quote! { #literal }]),
+			parts => {
+				let pieces = parts.iter().map(|part| match *part {
+					Part::Literal(ref literal) => quote! { #literal },
+					Part::Components(ref ty) => quote! { <#ty as ::pwasm_abi::eth::AbiType>::COMPONENTS_JSON },
+				});
+				tokens.append_all(&[quote! {
+					{
+						const PIECES: &'static [&'static str] = &[#(#pieces),*];
+						const BYTES: [u8; ::pwasm_abi::eth::const_concat_len(PIECES)] = ::pwasm_abi::eth::const_concat(PIECES);
+						::pwasm_abi::eth::const_str(&BYTES)
+					}
+				}]);
+			},
+		}
+	}
+}
+
+/// ABI JSON of a param, with the `components` of tuples (or arrays of them)
+fn param(name: &str, ty: &syn::Ty, indexed: Option<bool>) -> Json {
+	let (type_name, components) = type_json(ty);
+
+	let mut json = Json::default();
+	json.push_str(&format!(r#"{{"name":"{}","type":"{}""#, name, type_name));
+	if let Some(components) = components {
+		json.push_str(r#","components":["#);
+		json.append(components);
+		json.push_str("]");
+	}
+	if let Some(indexed) = indexed {
+		json.push_str(&format!(r#","indexed":{}"#, indexed));
+	}
+	json.push_str("}");
+	json
+}
+
+/// ABI JSON of the fields of a struct deriving `AbiType`, its `COMPONENTS_JSON`
+pub fn components<'a, I: IntoIterator<Item = (String, &'a syn::Ty)>>(fields: I) -> Json {
+	Json::join(fields.into_iter().map(|(name, ty)| param(&name, ty, None)))
+}
+
+/// `type` of the param, with its `components` if it's `tuple` based
+fn type_json(ty: &syn::Ty) -> (String, Option<Json>) {
+	if !utils::has_abi_struct(ty) {
+		return param_type_json(&utils::ty_to_param_type(ty));
+	}
+
+	match *ty {
+		syn::Ty::Array(ref elem, syn::ConstExpr::Lit(syn::Lit::Int(len, _))) => {
+			let (type_name, components) = type_json(elem);
+			(format!("{}[{}]", type_name, len), components)
+		},
+		syn::Ty::Tup(ref members) => ("tuple".to_owned(), Some(Json::join(members.iter().map(|ty| param("", ty, None))))),
+		_ => match utils::vec_element(ty) {
+			Some(elem) => {
+				let (type_name, components) = type_json(elem);
+				(format!("{}[]", type_name), components)
+			},
+			None => ("tuple".to_owned(), Some(Json { parts: vec![Part::Components(ty.clone())] })),
+		},
+	}
+}
+
+fn param_type_json(param_type: &abi::eth::ParamType) -> (String, Option<Json>) {
+	use abi::eth::ParamType;
+
+	match *param_type {
+		ParamType::Array(ref elem) => {
+			let (type_name, components) = param_type_json(elem.as_ref());
+			(format!("{}[]", type_name), components)
+		},
+		ParamType::FixedArray(ref elem, len) => {
+			let (type_name, components) = param_type_json(elem.as_ref());
+			(format!("{}[{}]", type_name, len), components)
+		},
+		ParamType::Tuple(ref members) => {
+			let members = members.iter().map(|member| {
+				let (type_name, components) = param_type_json(member);
+				let mut json = Json::default();
+				json.push_str(&format!(r#"{{"name":"","type":"{}""#, type_name));
+				if let Some(components) = components {
+					json.push_str(r#","components":["#);
+					json.append(components);
+					json.push_str("]");
+				}
+				json.push_str("}");
+				json
+			});
+			("tuple".to_owned(), Some(Json::join(members)))
+		},
+		_ => {
+			let mut name = String::new();
+			param_type.to_member(&mut name);
+			(name, None)
+		},
+	}
+}
+
+fn inputs(method_sig: &syn::MethodSig, event: bool) -> Json {
+	Json::join(utils::iter_signature(method_sig).map(|(pat, ty)| {
+		let name = quote! { #pat }.to_string();
+		match event {
+			// indexed event params are marked by the name prefix, which is not part of the abi name
+			true => param(name.trim_start_matches("indexed_"), &ty, Some(name.starts_with("indexed_"))),
+			false => param(&name, &ty, None),
+		}
+	}))
+}
+
+fn outputs(method_sig: &syn::MethodSig) -> Json {
+	match utils::result_ty(method_sig) {
+		None => Json::default(),
+		// tuple stands for multiple return values, listed one by one
+		Some(&syn::Ty::Tup(ref members)) => Json::join(members.iter().map(|ty| param("", ty, None))),
+		Some(ty) => param("", ty, None),
+	}
+}
+
+/// `view` for methods taking `&self`, which can't modify the contract state, `nonpayable` otherwise
+fn state_mutability(method_sig: &syn::MethodSig) -> &'static str {
+	match method_sig.decl.inputs.first() {
+		Some(&syn::FnArg::SelfRef(_, syn::Mutability::Immutable)) => "view",
+		_ => "nonpayable",
+	}
+}
@@ -12,6 +12,7 @@ extern crate quote;
 extern crate alloc;
 
 mod items;
+mod json;
 mod utils;
 
 use alloc::vec::Vec;
@@ -19,37 +20,363 @@ use proc_macro::TokenStream;
 
 use items::Item;
 
+/// Generates the endpoint and the client of the trait, named by the first two args
+///
+/// The endpoint dispatches call payloads to an implementation of the trait, the client encodes
+/// calls of its methods for a contract at some address. Optional flags follow the names:
+///
+/// - `trace(path::to::hook)`: every call is passed to `hook(method_id: u32, args: &[ValueType])`
+///   before the method is invoked
+/// - `strict`: the endpoint also gets `try_dispatch`, which returns errors instead of panicking
+/// - `structs(Order, Fill)`: structs deriving `AbiType` the methods take or return
+///
+/// The client makes calls through `call(&Address, U256, &[u8], &mut [u8]) -> Result<usize, _>` in
+/// scope, returning the length of the output. Besides the trait methods, which panic when the output
+/// fails to decode, it has `try_` prefixed ones returning the error.
 #[proc_macro_attribute]
 pub fn eth_abi(args: TokenStream, input: TokenStream) -> TokenStream {
 	let args_str = args.to_string();
-	let mut args: Vec<String> = args_str
-		.split(',')
-		.map(|w| w.trim_matches(&['(', ')', '"', ' '][..]).to_string())
+	let args: Vec<String> = split_args(&args_str)
+		.into_iter()
+		.map(|w| w.trim_matches(&['"', ' '][..]).to_string())
 		.collect();
 
-	let client_arg = args.pop().expect("Should be 2 elements in attribute");
-	let endpoint_arg = args.pop().expect("Should be 2 elements in attribute");
+	let mut args = args.into_iter();
+	let endpoint_arg = args.next().expect("Should be at least 2 elements in attribute");
+	let client_arg = args.next().expect("Should be at least 2 elements in attribute");
+	// optional flags follow the endpoint and client names
+	let flags: Vec<String> = args.collect();
+	let strict = flags.iter().any(|flag| flag == "strict");
+	let structs = struct_list(&flags);
 
 	let source = input.to_string();
 	let ast = syn::parse_item(&source).expect("Failed to parse derive input");
 
-	let generated = impl_eth_dispatch(ast, endpoint_arg, client_arg);
+	// reported as a compile error rather than a panic of the macro
+	let trace = match trace_hook(&flags).and_then(|trace| check_trait(&ast, &structs).map(|_| trace)) {
+		Ok(trace) => trace,
+		Err(message) => return quote! { compile_error!(#message); }.parse().expect("Failed to parse generated input"),
+	};
+
+	let generated = impl_eth_dispatch(ast, endpoint_arg, client_arg, trace, strict);
 
 	generated.parse().expect("Failed to parse generated input")
 }
 
-fn item_to_signature(item: &Item) -> Option<abi::eth::NamedSignature> {
-	match *item {
-		Item::Signature(ref ident, ref method_sig) => {
-			let name = ident.as_ref().to_string();
-			Some(
-				abi::eth::NamedSignature::new(
-					name,
-					utils::parse_rust_signature(method_sig),
-				)
-			)
+/// Splits attribute args at the commas between them, the ones of `structs(..)` lists are kept
+fn split_args(args: &str) -> Vec<String> {
+	let mut split = vec![String::new()];
+	let mut depth = 0;
+	for c in args.chars() {
+		match c {
+			',' if depth == 0 => {
+				split.push(String::new());
+				continue;
+			},
+			'(' => depth += 1,
+			')' => depth -= 1,
+			_ => {},
+		}
+		split.last_mut().expect("split starts with an arg").push(c);
+	}
+	split
+}
+
+/// Path of the hook given with `trace(path::to::hook)` among the flags
+fn trace_hook(flags: &[String]) -> Result<Option<syn::Path>, String> {
+	let hook = match flags.iter().find(|flag| flag.starts_with("trace")) {
+		Some(flag) => flag["trace".len()..].trim(),
+		None => return Ok(None),
+	};
+	hook.strip_prefix('(')
+		.and_then(|hook| hook.strip_suffix(')'))
+		.and_then(|hook| syn::parse_path(hook).ok())
+		.map(Some)
+		.ok_or_else(|| "`trace` needs the path of the hook function, e.g. `trace(hooks::trace)`".to_owned())
+}
+
+/// Structs deriving `AbiType` listed with `structs(Order, Fill)` among the flags
+fn struct_list(flags: &[String]) -> Vec<String> {
+	flags.iter()
+		.filter_map(|flag| flag.strip_prefix("structs").map(str::trim))
+		.filter_map(|list| list.strip_prefix('(').and_then(|list| list.strip_suffix(')')))
+		.flat_map(|list| list.split(','))
+		.map(|name| name.trim().to_owned())
+		.filter(|name| !name.is_empty())
+		.collect()
+}
+
+/// Implements `AbiType` for a struct, passed as the tuple of its fields
+///
+/// Fields of other structs deriving `AbiType` are listed with `#[abi(structs(Order))]` on the struct.
+#[proc_macro_derive(AbiType, attributes(abi))]
+pub fn abi_type(input: TokenStream) -> TokenStream {
+	let source = input.to_string();
+	let ast = syn::parse_derive_input(&source).expect("Failed to parse derive input");
+
+	let generated = match impl_abi_type(&ast) {
+		Ok(generated) => generated,
+		Err(message) => quote! { compile_error!(#message); },
+	};
+
+	generated.parse().expect("Failed to parse generated input")
+}
+
+fn impl_abi_type(ast: &syn::DeriveInput) -> Result<quote::Tokens, String> {
+	let ident = &ast.ident;
+	if !ast.generics.lifetimes.is_empty() || !ast.generics.ty_params.is_empty() {
+		return Err(format!("`AbiType` can't be derived for `{}`, generic structs are not supported", ident));
+	}
+	let fields = match ast.body {
+		syn::Body::Struct(syn::VariantData::Struct(ref fields)) |
+		syn::Body::Struct(syn::VariantData::Tuple(ref fields)) if !fields.is_empty() => fields,
+		_ => return Err(format!("`AbiType` can only be derived for structs with fields, `{}` isn't one", ident)),
+	};
+
+	let structs = struct_attrs(&ast.attrs)?;
+	let field_types = fields.iter().enumerate().map(|(i, field)| {
+		utils::param_type_tokens(&field.ty)
+			.and_then(|tokens| match utils::unlisted_struct(&field.ty, &structs) {
+				Some(name) => Err(format!(
+					"unknown type `{}`, structs deriving `AbiType` should be listed with `#[abi(structs({}))]`",
+					name, name,
+				)),
+				None => Ok(tokens),
+			})
+			.map_err(|e| match field.ident {
+				Some(ref name) => format!("{} (field `{}` of `{}`)", e, name, ident),
+				None => format!("{} (field {} of `{}`)", e, i, ident),
+			})
+	}).collect::<Result<Vec<_>, _>>()?;
+	let components_json = json::components(fields.iter().map(|field| {
+		(field.ident.as_ref().map_or(String::new(), |name| name.to_string()), &field.ty)
+	}));
+
+	// tuple struct fields are accessed by index
+	let members: Vec<syn::Ident> = fields.iter().enumerate()
+		.map(|(i, field)| field.ident.clone().unwrap_or_else(|| i.to_string().into()))
+		.collect();
+	let values = members.iter().map(|_| quote! {
+		values.next().expect("tuple should hold a value for every field").into()
+	});
+	let checked_values = members.iter().map(|_| quote! {
+		::pwasm_abi::eth::TryFromValue::try_from_value(values.next().expect("tuple length is checked above"))?
+	});
+	let (from_values, try_from_values) = match fields[0].ident {
+		Some(_) => {
+			let names = &members;
+			(quote! { #ident { #(#names: #values),* } }, quote! { #ident { #(#names: #checked_values),* } })
 		},
-		_ => None,
+		None => (quote! { #ident(#(#values),*) }, quote! { #ident(#(#checked_values),*) }),
+	};
+	let len = fields.len();
+	let pointer_width_check = utils::pointer_width_check(fields.iter().map(|field| &field.ty), &format!("`{}`", ident));
+
+	Ok(quote! {
+		#pointer_width_check
+
+		impl ::pwasm_abi::eth::AbiType for #ident {
+			const PARAM_TYPE: ::pwasm_abi::eth::ParamType =
+				::pwasm_abi::eth::ParamType::Tuple(Cow::Borrowed(&[#(#field_types),*]));
+			const COMPONENTS_JSON: &'static str = #components_json;
+		}
+
+		impl From<#ident> for ::pwasm_abi::eth::ValueType {
+			fn from(value: #ident) -> Self {
+				::pwasm_abi::eth::ValueType::Tuple(vec![#(value.#members.into()),*])
+			}
+		}
+
+		impl From<::pwasm_abi::eth::ValueType> for #ident {
+			fn from(value: ::pwasm_abi::eth::ValueType) -> Self {
+				match value {
+					::pwasm_abi::eth::ValueType::Tuple(values) => {
+						let mut values = values.into_iter();
+						#from_values
+					},
+					_ => panic!("struct should be converted from a tuple"),
+				}
+			}
+		}
+
+		impl ::pwasm_abi::eth::TryFromValue for #ident {
+			fn try_from_value(value: ::pwasm_abi::eth::ValueType) -> Result<Self, ::pwasm_abi::eth::ValueError> {
+				match value {
+					::pwasm_abi::eth::ValueType::Tuple(values) => {
+						if values.len() != #len {
+							return Err(::pwasm_abi::eth::ValueError::LengthMismatch { expected: #len, got: values.len() });
+						}
+						let mut values = values.into_iter();
+						Ok(#try_from_values)
+					},
+					_ => Err(::pwasm_abi::eth::ValueError::TypeMismatch),
+				}
+			}
+		}
+	})
+}
+
+/// Structs listed with `#[abi(structs(..))]` on a struct deriving `AbiType`
+fn struct_attrs(attrs: &[syn::Attribute]) -> Result<Vec<String>, String> {
+	let mut structs = Vec::new();
+	for attr in attrs {
+		let nested = match attr.value {
+			syn::MetaItem::List(ref ident, ref nested) if ident.as_ref() == "abi" => nested,
+			_ => continue,
+		};
+		for item in nested {
+			match *item {
+				syn::NestedMetaItem::MetaItem(syn::MetaItem::List(ref ident, ref names)) if ident.as_ref() == "structs" => {
+					structs.extend(names.iter().map(|name| quote! { #name }.to_string()));
+				},
+				_ => return Err(format!("unknown abi attribute `{}`, only `structs(..)` is supported on structs", quote! { #item })),
+			}
+		}
+	}
+	Ok(structs)
+}
+
+fn check_trait(item: &syn::Item, structs: &[String]) -> Result<(), String> {
+	if let syn::ItemKind::Trait(_, _, _, ref items) = item.node {
+		let mut constructor: Option<&syn::Ident> = None;
+		let mut fallback: Option<&syn::Ident> = None;
+		let mut selectors: Vec<(u32, &syn::Ident)> = Vec::new();
+		for trait_item in items {
+			if let syn::TraitItemKind::Method(ref method_sig, None) = trait_item.node {
+				utils::check_signature(&trait_item.ident, method_sig, items::is_event(&trait_item.attrs), structs)?;
+
+				if items::is_fallback(&trait_item.attrs) {
+					if utils::iter_signature(method_sig).next().is_some() || method_sig.decl.output != syn::FunctionRetTy::Default {
+						return Err(format!("fallback `{}` should take no arguments and return nothing", trait_item.ident));
+					}
+					if let Some(first) = fallback {
+						return Err(format!("`{}` and `{}` are both marked #[fallback], only one is allowed", first, trait_item.ident));
+					}
+					fallback = Some(&trait_item.ident);
+				}
+
+				if items::is_constructor(&trait_item.ident, &trait_item.attrs) {
+					if let Some(first) = constructor {
+						return Err(format!("`{}` and `{}` both claim to be the constructor, only one is allowed", first, trait_item.ident));
+					}
+					constructor = Some(&trait_item.ident);
+				}
+
+				if items::is_event(&trait_item.attrs) {
+					continue;
+				}
+				let attrs = items::MethodAttrs::from_attrs(&trait_item.attrs)
+					.map_err(|e| format!("{} (method `{}`)", e, trait_item.ident))?;
+				if !items::is_fallback(&trait_item.attrs) {
+					// selectors depending on structs are only known after compilation, the table checks them
+					let params: Option<Vec<abi::eth::ParamType>> = utils::iter_signature(method_sig)
+						.map(|(_, ty)| utils::try_ty_to_param_type(&ty).ok())
+						.collect();
+					let selector = attrs.selector
+						.or_else(|| params.map(|params| method_selector(attrs.abi_name(&trait_item.ident), &params)));
+					if let Some(selector) = selector {
+						if let Some(&(_, first)) = selectors.iter().find(|&&(s, _)| s == selector) {
+							return Err(format!("`{}` and `{}` have the same selector 0x{:08x}", first, trait_item.ident, selector));
+						}
+						selectors.push((selector, &trait_item.ident));
+					}
+				}
+			}
+		}
+	}
+	Ok(())
+}
+
+/// Selector computed from the ABI name of the method and params
+fn method_selector(name: &str, params: &[abi::eth::ParamType]) -> u32 {
+	u32::from_be_bytes(abi::eth::method_selector(name, params))
+}
+
+/// Method of the interface, as listed in the dispatch table
+struct Method<'a> {
+	name: &'a str,
+	/// Name of the method in the ABI, which the selector is computed from
+	abi_name: &'a str,
+	method_sig: &'a syn::MethodSig,
+	constructor: bool,
+	/// Forced with `#[abi(signature = ..)]` or computed, `None` if computing it depends on structs
+	/// deriving `AbiType`, the selector is computed in a constant then
+	hash: Option<u32>,
+}
+
+impl<'a> Method<'a> {
+	fn from_item(item: &'a Item) -> Option<Self> {
+		match *item {
+			// invoked for unknown selectors only, so it has no entry in the table
+			Item::Signature(_, _, ref attrs) if attrs.fallback => None,
+			Item::Signature(ref ident, ref method_sig, ref attrs) => {
+				let params: Option<Vec<abi::eth::ParamType>> = utils::iter_signature(method_sig)
+					.map(|(_, ty)| utils::try_ty_to_param_type(&ty).ok())
+					.collect();
+				Some(Method {
+					name: ident.as_ref(),
+					abi_name: attrs.abi_name(ident),
+					method_sig: method_sig,
+					constructor: attrs.constructor,
+					hash: attrs.selector.or_else(|| params.map(|params| method_selector(attrs.abi_name(ident), &params))),
+				})
+			},
+			_ => None,
+		}
+	}
+
+	/// Selector as a literal, or as a block evaluating `const_selector` in a constant
+	fn selector(&self) -> quote::Tokens {
+		match self.hash {
+			Some(hash) => {
+				let hash_literal = syn::Lit::Int(hash as u64, syn::IntTy::U32);
+				quote! { #hash_literal }
+			},
+			None => {
+				let name = self.abi_name;
+				let param_types = self.param_types();
+				// params are a constant on their own, temporaries of a `const_selector` call can't be dropped
+				quote! {
+					{
+						const PARAMS: &'static [::pwasm_abi::eth::ParamType] = &[#(#param_types),*];
+						const SELECTOR: u32 = ::pwasm_abi::eth::const_selector(#name, PARAMS);
+						SELECTOR
+					}
+				}
+			},
+		}
+	}
+
+	fn param_types(&self) -> Vec<quote::Tokens> {
+		utils::iter_signature(self.method_sig)
+			.map(|(_, ty)| utils::param_type_tokens(&ty).expect("signatures are checked before generation"))
+			.collect()
+	}
+
+	fn result_type(&self) -> Option<quote::Tokens> {
+		utils::result_ty(self.method_sig)
+			.map(|ty| utils::param_type_tokens(ty).expect("signatures are checked before generation"))
+	}
+
+	/// Whether the result depends on structs deriving `AbiType`, so its size is only known after compilation
+	fn has_struct_result(&self) -> bool {
+		match utils::result_ty(self.method_sig) {
+			Some(ty) => utils::has_abi_struct(ty),
+			None => false,
+		}
+	}
+
+	/// Whether the method returns a Rust tuple, which stands for multiple return values
+	fn has_multiple_results(&self) -> bool {
+		matches!(utils::result_ty(self.method_sig), Some(&syn::Ty::Tup(_)))
+	}
+
+	/// Whether the method returns `Option<T>`, which is `None` when there is no output
+	fn has_optional_result(&self) -> bool {
+		match self.method_sig.decl.output {
+			syn::FunctionRetTy::Default => false,
+			syn::FunctionRetTy::Ty(ref ty) => utils::option_inner(ty).is_some(),
+		}
 	}
 }
 
@@ -57,11 +384,13 @@ fn param_type_to_ident(param_type: &abi::eth::ParamType) -> quote::Tokens {
 	use abi::eth::ParamType;
 	match *param_type {
 		ParamType::U32 => quote! { ::pwasm_abi::eth::ParamType::U32 },
-		ParamType::I32 => quote! { ::pwasm_abi::eth::ParamType::U32 },
-		ParamType::U64 => quote! { ::pwasm_abi::eth::ParamType::U32 },
-		ParamType::I64 => quote! { ::pwasm_abi::eth::ParamType::U32 },
+		ParamType::I32 => quote! { ::pwasm_abi::eth::ParamType::I32 },
+		ParamType::U64 => quote! { ::pwasm_abi::eth::ParamType::U64 },
+		ParamType::I64 => quote! { ::pwasm_abi::eth::ParamType::I64 },
 		ParamType::Bool => quote! { ::pwasm_abi::eth::ParamType::Bool },
 		ParamType::U256 => quote! { ::pwasm_abi::eth::ParamType::U256 },
+		ParamType::Uint(bits) => quote! { ::pwasm_abi::eth::ParamType::Uint(#bits) },
+		ParamType::Int(bits) => quote! { ::pwasm_abi::eth::ParamType::Int(#bits) },
 		ParamType::H256 => quote! { ::pwasm_abi::eth::ParamType::H256 },
 		ParamType::Address => quote! { ::pwasm_abi::eth::ParamType::Address },
 		ParamType::Bytes => quote! { ::pwasm_abi::eth::ParamType::Bytes },
@@ -72,104 +401,144 @@ fn param_type_to_ident(param_type: &abi::eth::ParamType) -> quote::Tokens {
 			}
 		},
 		ParamType::String => quote! { ::pwasm_abi::eth::ParamType::String },
+		ParamType::FixedBytes(len) => quote! { ::pwasm_abi::eth::ParamType::FixedBytes(#len) },
+		ParamType::FixedArray(ref t, len) => {
+			let nested = param_type_to_ident(t.as_ref());
+			quote! {
+				::pwasm_abi::eth::ParamType::FixedArray(::pwasm_abi::eth::ArrayRef::Static(&#nested), #len)
+			}
+		},
+		ParamType::Tuple(ref members) => {
+			let members = members.iter().map(param_type_to_ident);
+			quote! {
+				::pwasm_abi::eth::ParamType::Tuple(Cow::Borrowed(&[#(#members),*]))
+			}
+		},
 	}
 }
 
 
 
+/// Storage key of the flag set while a `#[abi(nonreentrant)]` method runs,
+/// `keccak256("pwasm_abi.nonreentrant")`
+const REENTRANCY_GUARD_KEY: [u8; 32] = [
+	0x26, 0x5d, 0x84, 0x26, 0x35, 0xf1, 0x6d, 0xec, 0xb1, 0x05, 0xbd, 0xa6, 0xe4, 0x7a, 0xfd, 0x9d,
+	0x0b, 0xd4, 0x64, 0x5d, 0x3c, 0x6a, 0x2c, 0xbe, 0xfc, 0x00, 0xdb, 0x85, 0xdf, 0xc6, 0x75, 0x2f,
+];
+
+/// Default size of the buffer the client receives dynamic results in, see `result_capacity`
+const DEFAULT_RESULT_CAPACITY: usize = 1024;
+
+/// Wraps method `call` in a reentrancy guard, using the `read`/`write` functions of `storage`
+fn guard_reentrancy(name: &str, storage: &syn::Path, call: quote::Tokens) -> quote::Tokens {
+	let key_bytes = REENTRANCY_GUARD_KEY.iter().map(|b| syn::Lit::Int(*b as u64, syn::IntTy::U8));
+	let message = format!("reentrant call to `{}`", name);
+
+	// the flag is not cleared if the method panics, but the panic reverts the whole call anyway
+	quote! {
+		{
+			let guard_key = [#(#key_bytes),*].into();
+			if #storage::read(&guard_key) != [0u8; 32] {
+				panic!(#message);
+			}
+			let mut flag = [0u8; 32];
+			flag[31] = 1;
+			#storage::write(&guard_key, &flag);
+			let result = #call;
+			#storage::write(&guard_key, &[0u8; 32]);
+			result
+		}
+	}
+}
+
 fn impl_eth_dispatch(
 	item: syn::Item,
 	endpoint_name: String,
 	client_name: String,
+	trace: Option<syn::Path>,
+	strict: bool,
 ) -> quote::Tokens {
 
 	let intf = items::Interface::from_item(item)
 		.client(client_name)
 		.endpoint(endpoint_name);
 
-	let signatures: Vec<abi::eth::NamedSignature> =
-		intf.items().iter().filter_map(item_to_signature).collect();
+	let methods: Vec<Method> = intf.items().iter().filter_map(Method::from_item).collect();
 
 	let (ctor_branch, ctor_signature) = {
 
-		let ctor_signature = signatures.iter().find(|ns| ns.name() == "ctor");
+		let ctor_method = methods.iter().find(|m| m.constructor);
 
-		let ctor_branch = ctor_signature.map(|ns| {
-			let args_line = std::iter::repeat(
-				quote! { args.next().expect("Failed to fetch next argument").into() }
-			).take(ns.signature().params().len());
+		let ctor_branch = ctor_method.map(|m| {
+			let ident: syn::Ident = m.name.into();
+			let args_line = utils::iter_signature(m.method_sig)
+				.map(|(_, ty)| utils::arg_from_value(&ty));
 
 			quote! {
-				inner.ctor(
+				inner.#ident(
 					#(#args_line),*
 				);
 			}
 		});
 
-		let ctor_dispatch_effective = ctor_signature.map(|ns|
+		let ctor_dispatch_effective = ctor_method.map(|m|
 			{
-				let param_types = ns.signature().params().iter().map(|p| {
-					let ident = param_type_to_ident(&p);
-					quote! { #ident }
-				});
+				let param_types = m.param_types();
 
 				quote! {
-					Some(::pwasm_abi::eth::Signature {
+					::pwasm_abi::eth::Signature {
 						params: Cow::Borrowed(&[#(#param_types),*]),
 						result: None,
-					})
+						multiple_results: false,
+					}
 				}
 			}
-		).unwrap_or(quote! { None });
+		);
 
 		(ctor_branch, ctor_dispatch_effective)
 	};
 
-	let hashed_signatures: Vec<abi::eth::HashSignature> =
-		signatures.clone().into_iter()
-			.map(From::from)
-			.collect();
-
-	let table_signatures = hashed_signatures.clone().into_iter().map(|hs| {
-		let hash_literal = syn::Lit::Int(hs.hash() as u64, syn::IntTy::U32);
+	// emitted ordered by hash, so that the endpoint can look methods up with binary search;
+	// selectors computed in constants go last, the table is only searched linearly if there are any
+	let mut sorted_methods: Vec<&Method> = methods.iter().collect();
+	sorted_methods.sort_by_key(|m| (m.hash.is_none(), m.hash));
 
-		let param_types = hs.signature().params().iter().map(|p| {
-			let ident = param_type_to_ident(&p);
-			quote! {
-				#ident
-			}
-		});
+	let table_signatures = sorted_methods.into_iter().map(|m| {
+		let selector = m.selector();
+		let param_types = m.param_types();
 
-		if let Some(result_type) = hs.signature().result() {
-			let return_type = param_type_to_ident(result_type);
+		if let Some(return_type) = m.result_type() {
+			let multiple_results = m.has_multiple_results();
 			quote! {
 				::pwasm_abi::eth::HashSignature {
-					hash: #hash_literal,
+					hash: #selector,
 					signature: ::pwasm_abi::eth::Signature {
 						params: Cow::Borrowed(&[#(#param_types),*]),
 						result: Some(#return_type),
+						multiple_results: #multiple_results,
 					}
 				}
 			}
 		} else {
 			quote! {
 				::pwasm_abi::eth::HashSignature {
-					hash: #hash_literal,
+					hash: #selector,
 					signature: ::pwasm_abi::eth::Signature {
 						params: Cow::Borrowed(&[#(#param_types),*]),
 						result: None,
+						multiple_results: false,
 					}
 				}
 			}
 		}
 	});
 
-	let calls: Vec<quote::Tokens> = intf.items().iter().filter_map(|item| {
+	let try_calls: Vec<quote::Tokens> = intf.items().iter().filter_map(|item| {
 		match *item {
-			Item::Signature(ref ident, ref method_sig)  => {
-				let signature_index = signatures.iter().position(|s| s.name() == ident.as_ref()).expect("signature with this name known to exist");
-				let hash = *&hashed_signatures[signature_index].hash();
-				let hash_literal = syn::Lit::Int(hash as u64, syn::IntTy::U32);
+			Item::Signature(_, _, ref attrs) if attrs.fallback => None,
+			Item::Signature(ref ident, ref method_sig, _)  => {
+				let method = methods.iter().find(|m| m.name == ident.as_ref()).expect("method with this name known to exist");
+				let selector = method.selector();
 
 				let args = method_sig.decl.inputs.iter().filter_map(|arg| {
 					match *arg {
@@ -178,26 +547,89 @@ fn impl_eth_dispatch(
 					}
 				});
 
-				let body_appendix = match method_sig.decl.output {
-					syn::FunctionRetTy::Default => quote!{;},
-					syn::FunctionRetTy::Ty(_) => quote!{.expect("abi should return value").into()},
+				let result_conversion = match method_sig.decl.output {
+					syn::FunctionRetTy::Default => quote!{.map(|_| ())},
+					_ if method.has_optional_result() => quote!{.map(|result| result.map(Into::into))},
+					syn::FunctionRetTy::Ty(_) => quote!{.map(|result| result.expect("abi should return value").into())},
+				};
+				// the buffer is cut to the length of the output the callee returned, so no output is
+				// `None` for optional results and fails to decode (with `UnexpectedEnd`) for the rest
+				let output = match method.has_optional_result() {
+					true => quote! {
+						match result.is_empty() {
+							true => None,
+							false => Some(result),
+						}
+					},
+					false => quote! { Some(result) },
 				};
 
-				Some(utils::produce_signature(
+				// dynamic results (`None`) can't be sized upfront and get a buffer of `result_capacity`
+				// bytes, longer outputs are truncated and fail to decode
+				let result_len = match method.has_struct_result() {
+					true => quote! {
+						self.table.hash_signature(#selector).ok()
+							.and_then(|hs| hs.signature().return_size_hint())
+					},
+					false => {
+						let result = utils::result_ty(method_sig).map(utils::ty_to_param_type);
+						match abi::eth::Signature::new(Vec::new(), result).return_size_hint() {
+							Some(result_len) => quote! { Some(#result_len) },
+							None => quote! { None },
+						}
+					},
+				};
+
+				Some(utils::produce_try_signature(
 					ident,
 					method_sig,
 					quote!{
 						let values: &[::pwasm_abi::eth::ValueType] = &[
 							#(#args.into()),*
 						];
+						let result_len: Option<usize> = #result_len;
 						self.table
-							.call(#hash_literal, values, |payload| {
-								call(&self.address, self.value.clone().unwrap_or(U256::zero()), &payload, &mut[])
+							.call(#selector, values, |payload| {
+								let mut result = vec![0u8; result_len.unwrap_or(self.result_capacity)];
+								let output_len = call(&self.address, self.value.clone().unwrap_or(U256::zero()), &payload, &mut result)
 									.expect("call failed");
-								None
+								result.truncate(output_len);
+								#output
 							})
-							.expect("abi dispatch failed")
-							#body_appendix
+							#result_conversion
+					}
+				))
+			},
+			_ => None,
+		}
+	}).collect();
+
+	let calls: Vec<quote::Tokens> = intf.items().iter().filter_map(|item| {
+		match *item {
+			Item::Signature(ref ident, ref method_sig, ref attrs) if attrs.fallback => {
+				Some(utils::produce_signature(
+					ident,
+					method_sig,
+					quote!{
+						call(&self.address, self.value.clone().unwrap_or(U256::zero()), &[], &mut [])
+							.expect("call failed");
+					}
+				))
+			},
+			Item::Signature(ref ident, ref method_sig, _)  => {
+				let try_ident = utils::try_ident(ident);
+				let args = method_sig.decl.inputs.iter().filter_map(|arg| {
+					match *arg {
+						syn::FnArg::Captured(ref pat, _) => Some(pat),
+						_ => None,
+					}
+				});
+
+				Some(utils::produce_signature(
+					ident,
+					method_sig,
+					quote!{
+						self.#try_ident(#(#args),*).expect("abi dispatch failed")
 					}
 				))
 			},
@@ -215,64 +647,241 @@ fn impl_eth_dispatch(
 		}
 	}).collect();
 
-	let branches = hashed_signatures.into_iter()
-		.zip(signatures.into_iter())
-		.filter_map(|(hs, ns)| {
-			if ns.name() == "ctor" {
+	let nonreentrant: Vec<(&str, &syn::Path)> = intf.items().iter().filter_map(|item| {
+		match *item {
+			Item::Signature(ref ident, _, ref attrs) => attrs.storage.as_ref().map(|storage| (ident.as_ref(), storage)),
+			_ => None,
+		}
+	}).collect();
+
+	// with `checked`, arguments are converted before the call, failing dispatch of other types
+	let branches = |checked: bool| -> Vec<quote::Tokens> {
+		methods.iter().filter_map(|m| {
+			if m.constructor {
 				return None;
 			}
 
-			let hash_literal = syn::Lit::Int(hs.hash() as u64, syn::IntTy::U32);
-			let ident: syn::Ident = ns.name().into();
+			// selectors computed in constants can't be patterns, they are compared in a guard
+			let pattern = match m.hash {
+				Some(_) => m.selector(),
+				None => {
+					let selector = m.selector();
+					quote! { id if id == #selector }
+				},
+			};
+			let ident: syn::Ident = m.name.into();
+
+			let mut conversions = Vec::new();
+			let args_line: Vec<quote::Tokens> = utils::iter_signature(m.method_sig).enumerate()
+				.map(|(i, (_, ty))| match checked {
+					true => {
+						let (statement, pass) = utils::checked_arg_from_value(&ty, &format!("arg{}", i).into());
+						conversions.push(statement);
+						pass
+					},
+					false => utils::arg_from_value(&ty),
+				})
+				.collect();
 
-			let args_line = std::iter::repeat(
-				quote! { args.next().expect("Failed to fetch next argument").into() }
-			).take(hs.signature().params().len());
+			let mut call = quote! {
+				inner.#ident(
+					#(#args_line),*
+				)
+			};
+			if let Some(&(_, storage)) = nonreentrant.iter().find(|&&(name, _)| name == m.name) {
+				call = guard_reentrancy(m.name, storage, call);
+			}
 
-			if let Some(_) = hs.signature().result() {
+			if m.has_optional_result() {
+				// `None` is dispatched as no output, which the signature only allows for methods without result
+				Some(quote! {
+					#pattern => {
+						#(#conversions)*
+						match #call {
+							Some(result) => Some(result.into()),
+							None => {
+								no_output = true;
+								None
+							},
+						}
+					}
+				})
+			} else if let syn::FunctionRetTy::Ty(_) = m.method_sig.decl.output {
 				Some(quote! {
-					#hash_literal => {
+					#pattern => {
+						#(#conversions)*
 						Some(
-							inner.#ident(
-								#(#args_line),*
-							).into()
+							#call.into()
 						)
 					}
 				})
 			} else {
 				Some(quote! {
-					#hash_literal => {
-						inner.#ident(
-							#(#args_line),*
-						);
+					#pattern => {
+						#(#conversions)*
+						#call;
 						None
 					}
 				})
 			}
-		}
-	);
+		}).collect()
+	};
 
 
+	// built at compile time, so creating an endpoint or client neither hashes nor allocates;
+	// signatures are constants on their own, as they may refer to constants of `AbiType` structs
+	let table = match ctor_signature {
+		Some(ctor_signature) => quote! {
+			const FALLBACK: ::pwasm_abi::eth::Signature = #ctor_signature;
+			const TABLE: &'static ::pwasm_abi::eth::Table = &::pwasm_abi::eth::Table::from_static_with_fallback(SIGNATURES, FALLBACK);
+		},
+		None => quote! {
+			const TABLE: &'static ::pwasm_abi::eth::Table = &::pwasm_abi::eth::Table::from_static(SIGNATURES);
+		},
+	};
 	let dispatch_table = quote! {
 		{
-			const TABLE: &'static ::pwasm_abi::eth::Table = &::pwasm_abi::eth::Table {
-				inner: Cow::Borrowed(&[#(#table_signatures),*]),
-				fallback: #ctor_signature,
-			};
+			const SIGNATURES: &'static [::pwasm_abi::eth::HashSignature] = &[#(#table_signatures),*];
+			#table
 			TABLE
 		}
 	};
 
+	// payloads without a known selector go to the `#[fallback]` method, or revert
+	let fallback_ident = intf.items().iter().filter_map(|item| match *item {
+		Item::Signature(ref ident, _, ref attrs) if attrs.fallback => Some(ident),
+		_ => None,
+	}).next();
+	let unknown_selector = fallback_ident.map(|ident| quote! {
+		{
+			inner.#ident();
+			Vec::new()
+		}
+	}).unwrap_or_else(|| quote! { panic!("unknown method selector") });
+
+	// with the `trace` flag, the hook sees every call before the method is invoked
+	let trace_call = trace.map(|hook| quote! { #hook(method_id, &args); });
+
+	// with the `strict` flag, `try_dispatch` reports what `dispatch` panics on
+	let try_dispatch = match strict {
+		true => {
+			let checked_branches = branches(true);
+			let unknown_selector = fallback_ident.map(|ident| quote! {
+				{
+					inner.#ident();
+					Ok(Vec::new())
+				}
+			}).unwrap_or_else(|| quote! { result });
+
+			Some(quote! {
+				/// Dispatches the call `payload` like `dispatch`, failing instead of panicking if it
+				/// can't be handed over to a method
+				///
+				/// Arguments are converted with `TryFromValue` rather than `From`, which panics on a
+				/// value of another type. The decoder checks values against the signature of the
+				/// selector, so this only happens when the payload was meant for another method
+				/// sharing it. The method is not invoked then and `InvalidConversion` is returned;
+				/// arguments failing to decode are reported with the decoding error, an unknown
+				/// selector (without a `#[fallback]` method) as `UnknownSignature`. The caller
+				/// should revert on an error.
+				pub fn try_dispatch(&mut self, payload: &[u8]) -> Result<Vec<u8>, ::pwasm_abi::eth::Error> {
+					let inner = &mut self.inner;
+					#[allow(unused_mut)]
+					let mut conversion: Result<(), ::pwasm_abi::eth::Error> = Ok(());
+					#[allow(unused_mut)]
+					let mut no_output = false;
+					let result = self.table.dispatch(payload, |method_id, args| {
+						#trace_call
+						let mut args = args.into_iter();
+						match method_id {
+							#(#checked_branches),*,
+							_ => panic!("Invalid method signature"),
+						}
+					});
+					conversion?;
+					match result {
+						Err(::pwasm_abi::eth::Error::ResultCantFit) if no_output => Ok(Vec::new()),
+						Err(::pwasm_abi::eth::Error::UnknownSignature) |
+						Err(::pwasm_abi::eth::Error::NoLengthForSignature) => #unknown_selector,
+						result => result,
+					}
+				}
+			})
+		},
+		false => None,
+	};
+	let branches = branches(false);
+
+	// `None` of optional results fails to encode with `ResultCantFit`, it is caught as no output
+	let dispatch = quote! {
+		/// Dispatches the call `payload` to its method, returning the encoded result
+		///
+		/// A payload with an unknown selector (or too short to hold one) invokes the `#[fallback]`
+		/// method and returns no output; without such method it panics with `unknown method selector`,
+		/// which reverts the call. Arguments that fail to decode panic as well.
+		pub fn dispatch(&mut self, payload: &[u8]) -> Vec<u8> {
+			let inner = &mut self.inner;
+			#[allow(unused_mut)]
+			let mut no_output = false;
+			let result = self.table.dispatch(payload, |method_id, args| {
+				#trace_call
+				let mut args = args.into_iter();
+				match method_id {
+					#(#branches),*,
+					_ => panic!("Invalid method signature"),
+				}
+			});
+			match result {
+				Ok(output) => output,
+				Err(::pwasm_abi::eth::Error::ResultCantFit) if no_output => Vec::new(),
+				Err(::pwasm_abi::eth::Error::UnknownSignature) |
+				Err(::pwasm_abi::eth::Error::NoLengthForSignature) => #unknown_selector,
+				Err(e) => panic!("Failed abi dispatch: {:?}", e),
+			}
+		}
+	};
+
+	let abi_json = json::abi_json(&intf);
+
 	let endpoint_ident: syn::Ident = intf.endpoint_name().clone().into();
 	let client_ident: syn::Ident = intf.client_name().clone().into();
+	let result_capacity = DEFAULT_RESULT_CAPACITY;
+	let result_capacity_doc = format!(
+		"Size of the buffer receiving dynamic results (strings, bytes, arrays), rounded up to whole words \
+		(down past the last one `usize` holds), {} bytes by default\n\nOutputs longer than that are truncated and fail to decode.",
+		DEFAULT_RESULT_CAPACITY,
+	);
 	let name_ident: syn::Ident = intf.name().clone().into();
 
+	let pointer_width_checks: Vec<quote::Tokens> = intf.items().iter().filter_map(|item| {
+		let (ident, method_sig) = match *item {
+			Item::Signature(ref ident, ref method_sig, _) => (ident, method_sig),
+			Item::Event(ref event) => (&event.name, &event.method_sig),
+			Item::Other(_) => return None,
+		};
+		let output = match method_sig.decl.output {
+			syn::FunctionRetTy::Ty(ref ty) => Some(ty),
+			syn::FunctionRetTy::Default => None,
+		};
+		let tys: Vec<&syn::Ty> = method_sig.decl.inputs.iter()
+			.filter_map(|arg| match *arg {
+				syn::FnArg::Captured(_, ref ty) => Some(ty),
+				_ => None,
+			})
+			.chain(output)
+			.collect();
+		utils::pointer_width_check(tys, &format!("`{}`", ident))
+	}).collect();
+
 	quote! {
+		#(#pointer_width_checks)*
+
 		#intf
 
 		pub struct #client_ident {
 			address: Address,
 			value: Option<U256>,
+			result_capacity: usize,
 			table: &'static ::pwasm_abi::eth::Table,
 		}
 
@@ -287,6 +896,7 @@ fn impl_eth_dispatch(
 					address: address,
 					table: #dispatch_table,
 					value: None,
+					result_capacity: #result_capacity,
 				}
 			}
 
@@ -294,6 +904,14 @@ fn impl_eth_dispatch(
 				self.value = Some(val);
 				self
 			}
+
+			#[doc = #result_capacity_doc]
+			pub fn result_capacity(mut self, len: usize) -> Self {
+				self.result_capacity = len.checked_next_multiple_of(32).unwrap_or(usize::MAX / 32 * 32);
+				self
+			}
+
+			#(#try_calls)*
 		}
 
 		impl #name_ident for #client_ident {
@@ -301,6 +919,9 @@ fn impl_eth_dispatch(
 		}
 
 		impl<T: #name_ident> #endpoint_ident<T> {
+			/// ABI JSON of the contract, generated together with the dispatch code
+			pub const ABI_JSON: &'static str = #abi_json;
+
 			pub fn new(inner: T) -> Self {
 				#endpoint_ident {
 					inner: inner,
@@ -308,16 +929,9 @@ fn impl_eth_dispatch(
 				}
 			}
 
-			pub fn dispatch(&mut self, payload: &[u8]) -> Vec<u8> {
-				let inner = &mut self.inner;
-				self.table.dispatch(payload, |method_id, args| {
-					let mut args = args.into_iter();
-					match method_id {
-				 		#(#branches),*,
-						_ => panic!("Invalid method signature"),
-					}
-				}).expect("Failed abi dispatch")
-			}
+			#dispatch
+
+			#try_dispatch
 
 			#[allow(unused_variables)]
 			pub fn dispatch_ctor(&mut self, payload: &[u8]) {
@@ -331,6 +945,10 @@ fn impl_eth_dispatch(
 			pub fn instance(&self) -> &T {
 				&self.inner
 			}
+
+			pub fn table(&self) -> &'static ::pwasm_abi::eth::Table {
+				self.table
+			}
 		}
 	}
 }
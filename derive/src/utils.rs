@@ -34,23 +34,18 @@ pub fn produce_signature<T: quote::ToTokens>(
 	t: T,
 ) -> quote::Tokens
 {
-	let args = method_sig.decl.inputs.iter().filter_map(|arg| {
-		match *arg {
-			syn::FnArg::Captured(ref pat, ref ty) => Some(quote!{#pat: #ty}),
-			_ => None,
-		}
-	});
+	let (receiver, args) = signature_inputs(method_sig);
 	match method_sig.decl.output {
 		syn::FunctionRetTy::Ty(ref output) => {
 			quote!{
-				fn #ident(&mut self, #(#args),*) -> #output {
+				fn #ident(#receiver, #(#args),*) -> #output {
 					#t
 				}
 			}
 		},
 		syn::FunctionRetTy::Default => {
 			quote!{
-				fn #ident(&mut self, #(#args),*) {
+				fn #ident(#receiver, #(#args),*) {
 					#t
 				}
 			}
@@ -58,8 +53,58 @@ pub fn produce_signature<T: quote::ToTokens>(
 	}
 }
 
+/// Same as `produce_signature`, for a `try_` prefixed method returning the result of `t` as it is
+pub fn produce_try_signature<T: quote::ToTokens>(
+	ident: &syn::Ident,
+	method_sig: &syn::MethodSig,
+	t: T,
+) -> quote::Tokens
+{
+	let (receiver, args) = signature_inputs(method_sig);
+	let try_ident = try_ident(ident);
+	let output = match method_sig.decl.output {
+		syn::FunctionRetTy::Ty(ref output) => quote!{#output},
+		syn::FunctionRetTy::Default => quote!{()},
+	};
+	let doc = format!("Calls `{}`, returning the error its output fails to decode with instead of panicking", ident);
+	quote!{
+		#[doc = #doc]
+		pub fn #try_ident(#receiver, #(#args),*) -> Result<#output, ::pwasm_abi::eth::Error> {
+			#t
+		}
+	}
+}
+
+pub fn try_ident(ident: &syn::Ident) -> syn::Ident {
+	format!("try_{}", ident).into()
+}
+
+fn signature_inputs(method_sig: &syn::MethodSig) -> (quote::Tokens, Vec<quote::Tokens>) {
+	let args = method_sig.decl.inputs.iter().filter_map(|arg| {
+		match *arg {
+			syn::FnArg::Captured(ref pat, ref ty) => Some(quote!{#pat: #ty}),
+			_ => None,
+		}
+	}).collect();
+	// receiver as declared, `&self` methods end up as `view` functions in the ABI JSON
+	let receiver = match method_sig.decl.inputs.first() {
+		Some(&syn::FnArg::SelfRef(_, syn::Mutability::Immutable)) => quote!{&self},
+		_ => quote!{&mut self},
+	};
+	(receiver, args)
+}
+
 pub fn ty_to_param_type(ty: &syn::Ty) -> abi::eth::ParamType {
-	match *ty {
+	try_ty_to_param_type(ty).unwrap_or_else(|e| panic!("{}", e))
+}
+
+fn unsupported(ty: &syn::Ty, reason: &str) -> String {
+	format!("type `{}` is not supported by the Ethereum ABI{}", quote! { #ty }, reason)
+}
+
+/// Same as `ty_to_param_type`, with the message for types that can't be mapped as the error
+pub fn try_ty_to_param_type(ty: &syn::Ty) -> Result<abi::eth::ParamType, String> {
+	Ok(match *ty {
 		syn::Ty::Path(None, ref path) => {
 			let last_path = path.segments.last().unwrap();
 			match last_path.ident.to_string().as_ref() {
@@ -67,32 +112,312 @@ pub fn ty_to_param_type(ty: &syn::Ty) -> abi::eth::ParamType {
 				"i32" => abi::eth::ParamType::I32,
 				"u64" => abi::eth::ParamType::U64,
 				"i64" => abi::eth::ParamType::I64,
+				// pointer-sized integers are 32-bit on wasm32, the only target contracts are built for;
+				// generated code fails to compile for other targets (see `pointer_width_check`)
+				"usize" => abi::eth::ParamType::U32,
+				"isize" => abi::eth::ParamType::I32,
 				"U256" => abi::eth::ParamType::U256,
 				"H256" => abi::eth::ParamType::H256,
 				"Address" => abi::eth::ParamType::Address,
 				"Vec" => {
 					match last_path.parameters {
-						syn::PathParameters::AngleBracketed(ref param_data) => {
-							let vec_arg = param_data.types.last().unwrap();
-							if let syn::Ty::Path(None, ref nested_path) = *vec_arg {
-								if "u8" == nested_path.segments.last().unwrap().ident.to_string() {
-									return abi::eth::ParamType::Bytes;
-								}
+						syn::PathParameters::AngleBracketed(ref param_data) if param_data.types.len() == 1 => {
+							let vec_arg = &param_data.types[0];
+							if is_path_to(vec_arg, "u8") {
+								return Ok(abi::eth::ParamType::Bytes);
 							}
-							abi::eth::ParamType::Array(ty_to_param_type(vec_arg).into())
+							abi::eth::ParamType::Array(try_ty_to_param_type(vec_arg)?.into())
 						},
-						_ => panic!("Unsupported vec arguments"),
+						_ => return Err(unsupported(ty, ", vectors should have a single element type")),
 					}
 				},
-				"String" => abi::eth::ParamType::String,
+				"String" | "str" => abi::eth::ParamType::String,
 				"bool" => abi::eth::ParamType::Bool,
-				ref val @ _ => panic!("Unable to handle param of type {}: not supported by abi", val)
+				_ => return Err(unsupported(ty, "")),
 			}
 		},
-		ref val @ _ => panic!("Unable to handle param of type {:?}: not supported by abi", val),
+		syn::Ty::Array(ref elem, ref len) => {
+			let len = match *len {
+				syn::ConstExpr::Lit(syn::Lit::Int(len, _)) => len as usize,
+				_ => return Err(unsupported(ty, ", array length should be a literal")),
+			};
+			match (is_path_to(elem, "u8"), len) {
+				(true, 1..=32) => abi::eth::ParamType::FixedBytes(len),
+				(true, _) => return Err(unsupported(ty, ", byte arrays should be 1 to 32 bytes long")),
+				_ => abi::eth::ParamType::FixedArray(try_ty_to_param_type(elem)?.into(), len),
+			}
+		},
+		syn::Ty::Rptr(_, ref target) => match borrowed_owner(&target.ty) {
+			Some(_) => try_ty_to_param_type(&target.ty)?,
+			None => return Err(unsupported(ty, ", only &[u8] and &str references are")),
+		},
+		syn::Ty::Slice(ref elem) if is_path_to(elem, "u8") => abi::eth::ParamType::Bytes,
+		// only valid as a return type, mapped to multiple return values
+		syn::Ty::Tup(ref members) if !members.is_empty() => {
+			let members: Result<Vec<_>, _> = members.iter().map(try_ty_to_param_type).collect();
+			abi::eth::ParamType::Tuple(members?.into())
+		},
+		_ => return Err(unsupported(ty, "")),
+	})
+}
+
+/// Whether `ty` names a struct deriving `AbiType`, which the ABI type can't be resolved for here
+///
+/// Those are capitalized paths without generic params, other than the types mapped above; they
+/// should be listed as such (see `unlisted_struct`), which the macros check before generation.
+fn is_abi_struct(ty: &syn::Ty) -> bool {
+	match *ty {
+		syn::Ty::Path(None, ref path) => {
+			let last_path = path.segments.last().unwrap();
+			last_path.parameters.is_empty()
+				&& last_path.ident.as_ref().starts_with(|c: char| c.is_ascii_uppercase())
+				&& try_ty_to_param_type(ty).is_err()
+		},
+		_ => false,
+	}
+}
+
+/// Element type of `Vec<T>`
+pub fn vec_element(ty: &syn::Ty) -> Option<&syn::Ty> {
+	match *ty {
+		syn::Ty::Path(None, ref path) => {
+			let last_path = path.segments.last().unwrap();
+			match last_path.parameters {
+				syn::PathParameters::AngleBracketed(ref param_data)
+					if last_path.ident.as_ref() == "Vec" && param_data.types.len() == 1 => Some(&param_data.types[0]),
+				_ => None,
+			}
+		},
+		_ => None,
+	}
+}
+
+/// Type wrapped in `Option<T>`
+pub fn option_inner(ty: &syn::Ty) -> Option<&syn::Ty> {
+	match *ty {
+		syn::Ty::Path(None, ref path) => {
+			let last_path = path.segments.last().unwrap();
+			match last_path.parameters {
+				syn::PathParameters::AngleBracketed(ref param_data)
+					if last_path.ident.as_ref() == "Option" && param_data.types.len() == 1 => Some(&param_data.types[0]),
+				_ => None,
+			}
+		},
+		_ => None,
+	}
+}
+
+/// Return type of the method as it's mapped to the ABI
+///
+/// `Option<T>` results are encoded as `T`, with `None` standing for no output at all.
+pub fn result_ty(method_sig: &syn::MethodSig) -> Option<&syn::Ty> {
+	match method_sig.decl.output {
+		syn::FunctionRetTy::Default => None,
+		syn::FunctionRetTy::Ty(ref ty) => Some(option_inner(ty).unwrap_or(ty)),
+	}
+}
+
+/// Whether the ABI type of `ty` depends on structs deriving `AbiType`
+pub fn has_abi_struct(ty: &syn::Ty) -> bool {
+	match *ty {
+		syn::Ty::Array(ref elem, _) => has_abi_struct(elem),
+		syn::Ty::Tup(ref members) => members.iter().any(has_abi_struct),
+		_ => match vec_element(ty) {
+			Some(elem) => has_abi_struct(elem),
+			None => is_abi_struct(ty),
+		},
 	}
 }
 
+/// First struct `ty` refers to that is not one of the `structs` marked as deriving `AbiType`
+pub fn unlisted_struct(ty: &syn::Ty, structs: &[String]) -> Option<String> {
+	match *ty {
+		syn::Ty::Array(ref elem, _) => unlisted_struct(elem, structs),
+		syn::Ty::Tup(ref members) => members.iter().filter_map(|ty| unlisted_struct(ty, structs)).next(),
+		syn::Ty::Path(None, ref path) => match vec_element(ty) {
+			Some(elem) => unlisted_struct(elem, structs),
+			None => {
+				let name = path.segments.last().unwrap().ident.as_ref();
+				match is_abi_struct(ty) && !structs.iter().any(|s| s == name) {
+					true => Some(name.to_owned()),
+					false => None,
+				}
+			},
+		},
+		_ => None,
+	}
+}
+
+/// Expression of the `ParamType` of `ty`, usable in constants
+///
+/// Types built of structs deriving `AbiType` refer to the `PARAM_TYPE` of the structs.
+pub fn param_type_tokens(ty: &syn::Ty) -> Result<quote::Tokens, String> {
+	if !has_abi_struct(ty) {
+		return try_ty_to_param_type(ty).map(|p| ::param_type_to_ident(&p));
+	}
+
+	Ok(match *ty {
+		syn::Ty::Array(ref elem, ref len) => {
+			let nested = param_type_tokens(elem)?;
+			let len = match *len {
+				syn::ConstExpr::Lit(syn::Lit::Int(len, _)) => len as usize,
+				_ => return Err(unsupported(ty, ", array length should be a literal")),
+			};
+			quote! {
+				::pwasm_abi::eth::ParamType::FixedArray(::pwasm_abi::eth::ArrayRef::Static(&#nested), #len)
+			}
+		},
+		syn::Ty::Tup(ref members) => {
+			let members: Result<Vec<_>, _> = members.iter().map(param_type_tokens).collect();
+			let members = members?;
+			quote! {
+				::pwasm_abi::eth::ParamType::Tuple(Cow::Borrowed(&[#(#members),*]))
+			}
+		},
+		_ => match vec_element(ty) {
+			Some(elem) => {
+				let nested = param_type_tokens(elem)?;
+				quote! {
+					::pwasm_abi::eth::ParamType::Array(::pwasm_abi::eth::ArrayRef::Static(&#nested))
+				}
+			},
+			None => quote! { <#ty as ::pwasm_abi::eth::AbiType>::PARAM_TYPE },
+		},
+	})
+}
+
+/// `usize` or `isize` if `ty` refers to one, they are only mapped on 32-bit targets
+pub fn pointer_sized(ty: &syn::Ty) -> Option<&'static str> {
+	match *ty {
+		syn::Ty::Slice(ref elem) | syn::Ty::Array(ref elem, _) => pointer_sized(elem),
+		syn::Ty::Rptr(_, ref target) => pointer_sized(&target.ty),
+		syn::Ty::Tup(ref members) => members.iter().filter_map(pointer_sized).next(),
+		syn::Ty::Path(None, ref path) => {
+			let last_path = path.segments.last().unwrap();
+			match last_path.ident.as_ref() {
+				"usize" => Some("usize"),
+				"isize" => Some("isize"),
+				_ => match last_path.parameters {
+					syn::PathParameters::AngleBracketed(ref param_data) => param_data.types.iter().filter_map(pointer_sized).next(),
+					_ => None,
+				},
+			}
+		},
+		_ => None,
+	}
+}
+
+/// Fails compilation for targets other than 32-bit ones if `tys` refer to `usize` or `isize`,
+/// which are passed as `uint32`/`int32` there; the macro runs on the host, so that's left to `cfg`
+pub fn pointer_width_check<'a, I: IntoIterator<Item = &'a syn::Ty>>(tys: I, context: &str) -> Option<quote::Tokens> {
+	tys.into_iter().filter_map(pointer_sized).next().map(|name| {
+		let message = format!(
+			"{} uses `{}`, which is passed as `{}` and only supported on 32-bit targets (like wasm32)",
+			context, name, if name == "usize" { "uint32" } else { "int32" },
+		);
+		quote! {
+			#[cfg(not(target_pointer_width = "32"))]
+			compile_error!(#message);
+		}
+	})
+}
+
+fn is_path_to(ty: &syn::Ty, name: &str) -> bool {
+	match *ty {
+		syn::Ty::Path(None, ref path) => path.segments.last().unwrap().ident.as_ref() == name,
+		_ => false,
+	}
+}
+
+/// Owned type the argument borrowed as `ty` (`[u8]` or `str`) is decoded into
+fn borrowed_owner(ty: &syn::Ty) -> Option<quote::Tokens> {
+	match *ty {
+		syn::Ty::Slice(ref elem) if is_path_to(elem, "u8") => Some(quote! { Vec<u8> }),
+		_ if is_path_to(ty, "str") => Some(quote! { String }),
+		_ => None,
+	}
+}
+
+/// Expression converting the next decoded argument into `ty`
+///
+/// References borrow a temporary owned value, which lives until the end of the call statement.
+pub fn arg_from_value(ty: &syn::Ty) -> quote::Tokens {
+	let value = quote! { args.next().expect("Failed to fetch next argument") };
+	match *ty {
+		syn::Ty::Rptr(_, ref target) => {
+			let owner = borrowed_owner(&target.ty).expect("only supported references reach dispatch");
+			quote! { &{ let owned: #owner = #value.into(); owned } }
+		},
+		_ => quote! { #value.into() },
+	}
+}
+
+/// Statement binding the next decoded argument, converted into `ty`, to `binding`, with the
+/// expression passing it to the method
+///
+/// Used by `strict` dispatch: a value of another type stores the error in `conversion`
+/// and ends dispatch of the call without invoking the method.
+pub fn checked_arg_from_value(ty: &syn::Ty, binding: &syn::Ident) -> (quote::Tokens, quote::Tokens) {
+	let (owned, pass) = match *ty {
+		syn::Ty::Rptr(_, ref target) => {
+			let owner = borrowed_owner(&target.ty).expect("only supported references reach dispatch");
+			(owner, quote! { &#binding })
+		},
+		_ => (quote! { #ty }, quote! { #binding }),
+	};
+	let statement = quote! {
+		let #binding: #owned = match ::pwasm_abi::eth::TryFromValue::try_from_value(args.next().expect("Failed to fetch next argument")) {
+			Ok(value) => value,
+			Err(e) => {
+				conversion = Err(e.into());
+				return None;
+			},
+		};
+	};
+	(statement, pass)
+}
+
+/// Fails with a message naming the method and argument if the method can't be mapped to the ABI
+///
+/// Run over every method before generating code, so that the macro reports a compile error
+/// instead of panicking.
+///
+/// Structs deriving `AbiType` are allowed unless `event` is set, as topics are computed here.
+pub fn check_signature(ident: &syn::Ident, method_sig: &syn::MethodSig, event: bool, structs: &[String]) -> Result<(), String> {
+	let check_ty = |ty: &syn::Ty| match event {
+		true => try_ty_to_param_type(ty).map(|_| ()),
+		false => {
+			param_type_tokens(ty)?;
+			match unlisted_struct(ty, structs) {
+				Some(name) => Err(format!(
+					"unknown type `{}`, structs deriving `AbiType` should be listed with `structs({})` in `#[eth_abi]`",
+					name, name,
+				)),
+				None => Ok(()),
+			}
+		},
+	};
+
+	for fn_arg in method_sig.decl.inputs.iter() {
+		match *fn_arg {
+			syn::FnArg::Captured(ref pat, ref ty) => {
+				check_ty(ty).map_err(|e| format!("{} (argument `{}` of `{}`)", e, quote! { #pat }, ident))?;
+			},
+			syn::FnArg::SelfValue(_) => return Err(format!("`{}` cannot take self by value", ident)),
+			_ => {},
+		}
+	}
+	if let Some(ty) = result_ty(method_sig) {
+		check_ty(ty).map_err(|e| format!("{} (return type of `{}`)", e, ident))?;
+	}
+	if let syn::FunctionRetTy::Ty(ref ty) = method_sig.decl.output {
+		if option_inner(ty).is_some() && event {
+			return Err(format!("event `{}` cannot return an `Option`", ident));
+		}
+	}
+	Ok(())
+}
+
 pub fn parse_rust_signature(method_sig: &syn::MethodSig) -> abi::eth::Signature {
 	let mut params = Vec::new();
 
@@ -108,9 +433,6 @@ pub fn parse_rust_signature(method_sig: &syn::MethodSig) -> abi::eth::Signature
 
 	abi::eth::Signature::new(
 		params,
-		match method_sig.decl.output {
-			syn::FunctionRetTy::Default => None,
-			syn::FunctionRetTy::Ty(ref ty) => Some(ty_to_param_type(ty)),
-		}
+		result_ty(method_sig).map(ty_to_param_type)
 	)
 }
\ No newline at end of file
@@ -0,0 +1,263 @@
+//! Runtime-loaded contract ABI
+
+use std::collections::BTreeMap;
+use byteorder::{BigEndian, ByteOrder};
+use serde_json::{self, Value};
+
+use super::{ParamType, ValueType, Signature, NamedSignature, HashSignature, Table, Error};
+use super::decode::decode;
+
+/// Contract functions loaded from ABI JSON, keyed by selector
+#[derive(Clone)]
+pub struct Abi {
+	functions: BTreeMap<u32, NamedSignature>,
+}
+
+impl Abi {
+	/// Loads functions from ABI JSON (constructor, fallback and event entries are skipped)
+	///
+	/// Functions are keyed by the `selector` of their entry if it has one (as generated for
+	/// selectors forced with `#[abi(signature = ..)]`), by the one computed from the name otherwise.
+	pub fn from_json(s: &str) -> Result<Abi, Error> {
+		let (functions, _) = entries_from_json(s)?;
+		let functions = functions.into_iter()
+			.map(|(selector, signature)| (selector.unwrap_or_else(|| HashSignature::from(signature.clone()).hash()), signature))
+			.collect();
+
+		Ok(Abi { functions: functions })
+	}
+
+	/// Function with the given selector
+	pub fn function(&self, selector: u32) -> Option<&NamedSignature> {
+		self.functions.get(&selector)
+	}
+
+	/// All loaded functions, ordered by selector
+	pub fn functions(&self) -> impl Iterator<Item = (u32, &NamedSignature)> {
+		self.functions.iter().map(|(selector, signature)| (*selector, signature))
+	}
+
+	/// Decodes transaction input (selector followed by args), returning the called function name with args
+	pub fn decode_input(&self, calldata: &[u8]) -> Result<(String, Vec<ValueType>), Error> {
+		if calldata.len() < 4 {
+			return Err(Error::NoLengthForSignature);
+		}
+
+		let selector = BigEndian::read_u32(&calldata[0..4]);
+		let function = self.function(selector).ok_or(Error::UnknownSignature)?;
+		let values = decode(function.signature().params(), &calldata[4..])?;
+
+		Ok((function.name().to_owned(), values))
+	}
+}
+
+impl Table {
+	/// Dispatch table of the functions in ABI JSON, with the constructor params (if there is
+	/// a constructor entry) as the fallback signature
+	pub fn from_json(s: &str) -> Result<Table, Error> {
+		let (functions, constructor) = entries_from_json(s)?;
+
+		let mut table = Table::default();
+		for (selector, signature) in functions {
+			match selector {
+				Some(selector) => table.push(HashSignature::new(selector, signature.signature().clone())),
+				None => table.push(signature),
+			}
+		}
+		table.fallback = constructor;
+		Ok(table)
+	}
+}
+
+/// Functions, with their `selector` if set, and the constructor signature
+type Entries = (Vec<(Option<u32>, NamedSignature)>, Option<Signature>);
+
+/// Functions and constructor signature of ABI JSON, other entries are skipped
+fn entries_from_json(s: &str) -> Result<Entries, Error> {
+	let json: Value = serde_json::from_str(s).map_err(|_| Error::InvalidAbiJson)?;
+	let entries = json.as_array().ok_or(Error::InvalidAbiJson)?;
+
+	let mut functions = Vec::new();
+	let mut constructor = None;
+	for entry in entries {
+		// `type` can be omitted for functions
+		match entry.get("type").and_then(Value::as_str) {
+			None | Some("function") => {},
+			Some("constructor") => {
+				constructor = Some(Signature::new_void(params_from_json(entry.get("inputs"))?));
+				continue;
+			},
+			Some(_) => continue,
+		}
+
+		let name = entry.get("name").and_then(Value::as_str).ok_or(Error::InvalidAbiJson)?;
+		let params = params_from_json(entry.get("inputs"))?;
+		let mut outputs = params_from_json(entry.get("outputs"))?;
+		let signature = match outputs.len() {
+			1 => Signature::new(params, outputs.pop()),
+			_ => Signature::new_multi(params, outputs),
+		};
+
+		let selector = match entry.get("selector") {
+			Some(selector) => Some(selector_from_json(selector)?),
+			None => None,
+		};
+
+		functions.push((selector, NamedSignature::new(name.to_owned(), signature)));
+	}
+
+	Ok((functions, constructor))
+}
+
+/// Selector written as `0x` followed by 8 hex digits
+fn selector_from_json(selector: &Value) -> Result<u32, Error> {
+	let digits = selector.as_str()
+		.and_then(|s| s.strip_prefix("0x"))
+		.filter(|digits| digits.len() == 8 && digits.bytes().all(|b| b.is_ascii_hexdigit()))
+		.ok_or(Error::InvalidAbiJson)?;
+	u32::from_str_radix(digits, 16).map_err(|_| Error::InvalidAbiJson)
+}
+
+fn params_from_json(params: Option<&Value>) -> Result<Vec<ParamType>, Error> {
+	let params = match params {
+		Some(params) => params.as_array().ok_or(Error::InvalidAbiJson)?,
+		None => return Ok(Vec::new()),
+	};
+
+	params.iter().map(param_from_json).collect()
+}
+
+fn param_from_json(param: &Value) -> Result<ParamType, Error> {
+	let ty = param.get("type").and_then(Value::as_str).ok_or(Error::InvalidAbiJson)?;
+
+	// tuple members are listed in `components`, the type itself is `tuple` with optional array suffixes
+	if let Some(suffix) = ty.strip_prefix("tuple") {
+		let components = params_from_json(param.get("components"))?;
+		let mut member = ParamType::Tuple(components.into()).to_string_type();
+		member.push_str(suffix);
+		return member.parse();
+	}
+
+	ty.parse()
+}
+
+#[cfg(test)]
+mod tests {
+	extern crate rustc_hex as hex;
+
+	use self::hex::FromHex;
+	use super::Abi;
+	use super::super::{ParamType, ValueType, Table, Error};
+
+	const ERC20: &'static str = r#"[
+		{"constant":true,"inputs":[],"name":"totalSupply","outputs":[{"name":"","type":"uint256"}],"type":"function"},
+		{"constant":false,"inputs":[{"name":"_to","type":"address"},{"name":"_value","type":"uint256"}],"name":"transfer","outputs":[{"name":"","type":"bool"}],"type":"function"},
+		{"constant":true,"inputs":[{"name":"_owner","type":"address"}],"name":"balanceOf","outputs":[{"name":"balance","type":"uint256"}],"type":"function"},
+		{"inputs":[{"name":"_supply","type":"uint256"}],"type":"constructor"},
+		{"anonymous":false,"inputs":[{"indexed":true,"name":"from","type":"address"},{"indexed":true,"name":"to","type":"address"},{"indexed":false,"name":"value","type":"uint256"}],"name":"Transfer","type":"event"}
+	]"#;
+
+	#[test]
+	fn decode_erc20_transfer() {
+		let abi = Abi::from_json(ERC20).unwrap();
+		assert_eq!(abi.functions().count(), 3);
+
+		let calldata = ("".to_owned() +
+			"a9059cbb" +
+			"0000000000000000000000001111111111111111111111111111111111111111" +
+			"00000000000000000000000000000000000000000000000000000000000003e8").from_hex().unwrap();
+
+		let (name, values) = abi.decode_input(&calldata).unwrap();
+
+		let mut amount = [0u8; 32];
+		amount[30] = 0x03;
+		amount[31] = 0xe8;
+		assert_eq!(name, "transfer");
+		assert_eq!(values, vec![ValueType::Address([0x11u8; 20]), ValueType::U256(amount)]);
+		assert_eq!(abi.function(0xa9059cbb).unwrap().signature().result(), Some(&ParamType::Bool));
+	}
+
+	#[test]
+	fn decode_unknown_selector() {
+		let abi = Abi::from_json(ERC20).unwrap();
+
+		assert_eq!(abi.decode_input(&[0x12, 0x34, 0x56, 0x78]).err(), Some(Error::UnknownSignature));
+		assert_eq!(abi.decode_input(&[0x12, 0x34]).err(), Some(Error::NoLengthForSignature));
+	}
+
+	#[test]
+	fn erc20_table() {
+		let table = Table::from_json(ERC20).unwrap();
+		assert_eq!(table.inner.len(), 3);
+		assert!(table.is_sorted());
+		assert_eq!(table.fallback.as_ref().unwrap().params(), &[ParamType::U256]);
+
+		let mut payload = vec![0x70, 0xa0, 0x82, 0x31]; // balanceOf(address)
+		payload.extend_from_slice(&[0u8; 32]);
+		let result = table.dispatch(&payload, |method_id, args| {
+			assert_eq!(method_id, 0x70a08231);
+			assert_eq!(args, vec![ValueType::Address([0u8; 20])]);
+			Some(ValueType::U256([0x11u8; 32]))
+		});
+		assert_eq!(result, Ok(vec![0x11u8; 32]));
+	}
+
+	#[test]
+	fn tuple_components() {
+		let abi = Abi::from_json(r#"[
+			{"type":"function","name":"submit","inputs":[
+				{"name":"orders","type":"tuple[2]","components":[{"name":"amount","type":"uint256"},{"name":"buy","type":"bool"}]}
+			],"outputs":[]}
+		]"#).unwrap();
+
+		let function = abi.function(0xa2a27881).expect("selector of submit((uint256,bool)[2])");
+		assert_eq!(function.signature().params(), &[
+			ParamType::FixedArray(ParamType::Tuple(vec![ParamType::U256, ParamType::Bool].into()).into(), 2),
+		]);
+	}
+
+	#[test]
+	fn tuple_output() {
+		let abi = Abi::from_json(r#"[
+			{"type":"function","name":"receipt","inputs":[],"outputs":[
+				{"name":"","type":"tuple","components":[{"name":"ok","type":"bool"},{"name":"data","type":"bytes"}]}
+			]},
+			{"type":"function","name":"pair","inputs":[],"outputs":[{"name":"","type":"bool"},{"name":"","type":"bytes"}]}
+		]"#).unwrap();
+
+		// a single tuple output is one value, several outputs are multiple return values
+		let (_, receipt) = abi.functions().find(|&(_, f)| f.name() == "receipt").unwrap();
+		let (_, pair) = abi.functions().find(|&(_, f)| f.name() == "pair").unwrap();
+		assert_eq!(receipt.signature().result(), pair.signature().result());
+		assert!(!receipt.signature().multiple_results);
+		assert!(pair.signature().multiple_results);
+	}
+
+	#[test]
+	fn forced_selector() {
+		let json = r#"[
+			{"type":"function","name":"send","inputs":[{"name":"to","type":"address"}],"outputs":[],"selector":"0x12345678"}
+		]"#;
+		let abi = Abi::from_json(json).unwrap();
+		assert_eq!(abi.function(0x12345678).unwrap().name(), "send");
+		assert_eq!(abi.functions().count(), 1);
+
+		let table = Table::from_json(json).unwrap();
+		assert!(table.hash_signature(0x12345678).is_ok());
+
+		assert_eq!(Abi::from_json(&json.replace("0x12345678", "12345678")).err(), Some(Error::InvalidAbiJson));
+		assert_eq!(Abi::from_json(&json.replace("0x12345678", "0x123456789")).err(), Some(Error::InvalidAbiJson));
+	}
+
+	#[test]
+	fn invalid_json() {
+		assert_eq!(Abi::from_json("{").err(), Some(Error::InvalidAbiJson));
+		assert_eq!(Abi::from_json(r#"[{"type":"function","name":"f","inputs":[{"type":"fixed128x18"}]}]"#).err(), Some(Error::InvalidParamType));
+	}
+
+	#[test]
+	fn abi_is_sync() {
+		fn assert_sync<T: Sync>() {}
+		assert_sync::<Abi>();
+	}
+}
@@ -2,20 +2,208 @@
 //! Original code is mostly by debris in ethabi
 
 use lib::*;
-use super::util::{as_bool, as_i32, as_u32, as_u64, as_i64, Error, Hash};
+use super::util::{as_bool, as_i32, as_u32, as_u64, as_i64, check_uint, check_int, Error, Hash};
 use super::{ValueType, ParamType};
+use super::encode::encode;
+
+/// Default bound on the number of top-level params, see `DecodeConfig::max_params`
+pub const DEFAULT_MAX_PARAMS: usize = 256;
+
+/// Decoder strictness options
+#[derive(Debug, Clone)]
+pub struct DecodeConfig {
+	/// Reject payloads where the tail regions of two dynamic values overlap
+	pub reject_overlapping: bool,
+	/// Reject payloads with bytes left over after the last word referenced by any param
+	pub reject_trailing: bool,
+	/// Decode strings with invalid UTF-8 by replacing bad sequences instead of failing
+	pub string_lossy: bool,
+	/// Maximum number of top-level params, guards against corrupted runtime-loaded signatures
+	pub max_params: usize,
+	/// Accept address words with non-zero high 12 bytes, ignoring them
+	pub lenient_address: bool,
+}
+
+impl Default for DecodeConfig {
+	fn default() -> Self {
+		DecodeConfig {
+			reject_overlapping: false,
+			reject_trailing: false,
+			string_lossy: false,
+			max_params: DEFAULT_MAX_PARAMS,
+			lenient_address: false,
+		}
+	}
+}
 
 /// Decodes ABI compliant vector of bytes into vector of runtime values
 pub fn decode(types: &[ParamType], data: &[u8]) -> Result<Vec<ValueType>, Error> {
+	decode_with_config(types, data, &DecodeConfig::default())
+}
+
+/// Decodes ABI compliant vector of bytes into vector of runtime values with the given strictness options
+pub fn decode_with_config(types: &[ParamType], data: &[u8], config: &DecodeConfig) -> Result<Vec<ValueType>, Error> {
+	let mut tokens = Vec::with_capacity(types.len());
+	let consumed = decode_all(types, data, config, &mut tokens)?;
+	if config.reject_trailing && consumed != data.len() {
+		return Err(Error::TrailingData { remaining: data.len() - consumed });
+	}
+	Ok(tokens)
+}
+
+/// Decodes ABI compliant vector of bytes into vector of runtime values, failing with
+/// `Error::TrailingData` if any bytes are left after the heads and the tails they reference
+pub fn decode_strict(types: &[ParamType], data: &[u8]) -> Result<Vec<ValueType>, Error> {
+	decode_with_config(types, data, &DecodeConfig { reject_trailing: true, ..DecodeConfig::default() })
+}
+
+/// Decodes ABI compliant vector of bytes into vector of runtime values, also returning
+/// the number of bytes consumed (heads and all referenced tails)
+pub fn decode_with_len(types: &[ParamType], data: &[u8]) -> Result<(Vec<ValueType>, usize), Error> {
+	let mut tokens = Vec::with_capacity(types.len());
+	let consumed = decode_all(types, data, &DecodeConfig::default(), &mut tokens)?;
+	Ok((tokens, consumed))
+}
+
+/// Decodes ABI compliant vector of bytes, failing with `Error::NonCanonical` unless `data` is the
+/// unique canonical encoding of the decoded values
+///
+/// Canonical encoding has tails laid out in param order right after the heads, zero padding
+/// and nothing trailing, so two payloads decoding to the same values are byte-equal (which
+/// matters for contracts hashing their own calldata).
+pub fn decode_canonical(types: &[ParamType], data: &[u8]) -> Result<Vec<ValueType>, Error> {
+	let values = decode(types, data)?;
+	if encode(&values) != data {
+		return Err(Error::NonCanonical);
+	}
+	Ok(values)
+}
+
+/// Decodes ABI compliant vector of bytes into the provided vector, replacing its contents
+///
+/// Allocations of `out` and of the arrays previously stored in it are reused, which
+/// avoids reallocating when decoding many payloads of the same shape. On error `out` is cleared.
+pub fn decode_reuse(types: &[ParamType], data: &[u8], out: &mut Vec<ValueType>) -> Result<(), Error> {
+	match decode_all(types, data, &DecodeConfig::default(), out) {
+		Ok(_) => Ok(()),
+		Err(e) => {
+			out.clear();
+			Err(e)
+		},
+	}
+}
+
+/// Decoder pulling top-level values one at a time
+///
+/// Lets callers decode a prefix of the params and stop early, without the work (and allocations)
+/// for the rest of the payload.
+pub struct Decoder {
+	slices: Vec<Hash>,
+	offset: usize,
+}
+
+impl Decoder {
+	/// Decoder of the ABI compliant `data`, failing if its length is not a multiple of 32
+	pub fn new(data: &[u8]) -> Result<Self, Error> {
+		Ok(Decoder {
+			slices: slice_data(data)?,
+			offset: 0,
+		})
+	}
+
+	/// Decodes the next top-level value as `param`
+	pub fn next(&mut self, param: &ParamType) -> Result<ValueType, Error> {
+		let config = DecodeConfig::default();
+		let mut context = Context::new(&self.slices, &config);
+		let res = context.decode_param(param, self.offset, None)?;
+		self.offset = res.new_offset;
+		Ok(res.token)
+	}
+}
+
+/// Location a single top-level value was decoded from
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct TraceEntry {
+	/// Index of the param in the signature
+	pub param_index: usize,
+	/// Type the value was decoded as
+	pub ty: ParamType,
+	/// Index of the 32-byte word holding the value (static) or its offset (dynamic)
+	pub head_word_index: usize,
+	/// Byte offset of the tail, as read from the head, for dynamic params
+	pub tail_offset: Option<usize>,
+}
+
+/// Decodes ABI compliant vector of bytes into vector of runtime values, also returning
+/// where each of them was read from (for diagnosing layout mismatches)
+#[cfg(feature = "std")]
+pub fn decode_traced(types: &[ParamType], data: &[u8]) -> Result<(Vec<ValueType>, Vec<TraceEntry>), Error> {
+	let config = DecodeConfig::default();
+	check_params_count(types, &config)?;
+	let slices = slice_data(data)?;
+	let mut context = Context::new(&slices, &config);
+	let mut tokens = Vec::with_capacity(types.len());
+	let mut trace = Vec::with_capacity(types.len());
+	let mut offset = 0;
+	for (i, param) in types.iter().enumerate() {
+		let tail_offset = match param.is_dynamic() {
+			true => Some(as_u32(context.peek(offset)?)? as usize),
+			false => None,
+		};
+		trace.push(TraceEntry {
+			param_index: i,
+			ty: param.clone(),
+			head_word_index: offset,
+			tail_offset: tail_offset,
+		});
+		offset = context.decode_into(param, offset, &mut tokens, i)?;
+	}
+	Ok((tokens, trace))
+}
+
+/// Decodes ABI compliant vector of bytes into vector of runtime values, pairing each of them with
+/// the raw words it was read from, in payload order
+///
+/// Words of a dynamic value include its head (the offset) and the length prefix of its tail.
+#[cfg(feature = "std")]
+pub fn decode_with_words(types: &[ParamType], data: &[u8]) -> Result<Vec<(ValueType, Vec<Hash>)>, Error> {
+	let config = DecodeConfig::default();
+	check_params_count(types, &config)?;
 	let slices = slice_data(data)?;
-	let mut tokens = vec![];
+	let mut context = Context::new(&slices, &config);
+	let mut result = Vec::with_capacity(types.len());
 	let mut offset = 0;
 	for param in types {
-		let res = decode_param(param, &slices, offset)?;
+		context.read_words = Some(Vec::new());
+		let res = context.decode_param(param, offset, None)?;
 		offset = res.new_offset;
-		tokens.push(res.token);
+
+		let mut positions = context.read_words.take().expect("set above");
+		positions.sort();
+		positions.dedup();
+		result.push((res.token, positions.into_iter().map(|position| slices[position]).collect()));
 	}
-	Ok(tokens)
+	Ok(result)
+}
+
+fn decode_all(types: &[ParamType], data: &[u8], config: &DecodeConfig, tokens: &mut Vec<ValueType>) -> Result<usize, Error> {
+	check_params_count(types, config)?;
+	let slices = slice_data(data)?;
+	let mut context = Context::new(&slices, config);
+	tokens.truncate(types.len());
+	let mut offset = 0;
+	for (i, param) in types.iter().enumerate() {
+		offset = context.decode_into(param, offset, tokens, i)?;
+	}
+	Ok(context.extent * 32)
+}
+
+fn check_params_count(types: &[ParamType], config: &DecodeConfig) -> Result<(), Error> {
+	if types.len() > config.max_params {
+		return Err(Error::TooManyParams { count: types.len(), max: config.max_params });
+	}
+	Ok(())
 }
 
 struct DecodeResult {
@@ -25,14 +213,13 @@ struct DecodeResult {
 
 struct BytesTaken {
 	bytes: Vec<u8>,
-	#[allow(dead_code)] // will be used later probably
 	new_offset: usize,
 }
 
 /// Convers vector of bytes with len equal n * 32, to a vector of slices.
 fn slice_data(data: &[u8]) -> Result<Vec<Hash>, Error> {
 	if data.len() % 32 != 0 {
-		return Err(Error::InvalidPadding);
+		return Err(Error::LengthMismatch { expected: (data.len() / 32 + 1) * 32, got: data.len() });
 	}
 
 	let times = data.len() / 32;
@@ -46,173 +233,373 @@ fn slice_data(data: &[u8]) -> Result<Vec<Hash>, Error> {
 	Ok(result)
 }
 
-fn peek(slices: &[Hash], position: usize) -> Result<&Hash, Error> {
-	slices.get(position).ok_or(Error::UnexpectedEnd)
+/// Decoding state shared by all params of a single payload
+struct Context<'a> {
+	slices: &'a [Hash],
+	config: &'a DecodeConfig,
+	// tail regions claimed so far, as [start, end) slice ranges
+	regions: Vec<(usize, usize)>,
+	// number of leading slices covered by everything read so far
+	extent: usize,
+	// slice the offsets of the tuple (or payload) being decoded are relative to
+	base: usize,
+	// positions of the slices read, recorded if set
+	read_words: Option<Vec<usize>>,
 }
 
-fn take_bytes(slices: &[Hash], position: usize, len: usize) -> Result<BytesTaken, Error> {
-	let slices_len = (len + 31) / 32;
+impl<'a> Context<'a> {
+	fn new(slices: &'a [Hash], config: &'a DecodeConfig) -> Self {
+		Context {
+			slices: slices,
+			config: config,
+			regions: Vec::new(),
+			extent: 0,
+			base: 0,
+			read_words: None,
+		}
+	}
 
-	let mut bytes_slices = vec![];
-	for i in 0..slices_len {
-		let slice = try!(peek(slices, position + i)).clone();
-		bytes_slices.push(slice);
+	fn peek(&mut self, position: usize) -> Result<&'a Hash, Error> {
+		let slice = self.slices.get(position).ok_or(Error::UnexpectedEnd { position: 32 * (position + 1) })?;
+		self.extent = cmp::max(self.extent, position + 1);
+		if let Some(ref mut read_words) = self.read_words {
+			read_words.push(position);
+		}
+		Ok(slice)
 	}
 
-	let bytes = bytes_slices.into_iter()
-		.flat_map(|slice| slice.to_vec())
-		.take(len)
-		.collect();
+	/// Reads the offset stored in the head at `position`, returning slice of the tail it points to
+	fn tail_position(&mut self, position: usize) -> Result<usize, Error> {
+		let offset_slice = self.peek(position)?;
+		if !offset_slice[..28].iter().all(|x| *x == 0) {
+			return Err(Error::OffsetTooLarge);
+		}
+		Ok(self.base + (as_u32(offset_slice)? / 32) as usize)
+	}
 
-	let taken = BytesTaken {
-		bytes: bytes,
-		new_offset: position + slices_len,
-	};
+	/// Fails if fewer than `words` slices follow the length stored at `len_offset`
+	///
+	/// Checked before reading the data, so that a bogus length can't make the decoder loop
+	/// (or allocate) far beyond the end of the payload.
+	fn check_len(&self, len_offset: usize, words: usize) -> Result<(), Error> {
+		match self.slices.len().saturating_sub(len_offset + 1) < words {
+			true => Err(Error::UnexpectedEnd { position: (len_offset + 1).saturating_add(words).saturating_mul(32) }),
+			false => Ok(()),
+		}
+	}
 
-	Ok(taken)
-}
+	/// Records the tail region `[start, end)`, failing if strict mode is on and it overlaps a known one
+	fn claim(&mut self, start: usize, end: usize) -> Result<(), Error> {
+		if !self.config.reject_overlapping || start == end {
+			return Ok(());
+		}
 
-fn decode_param(param: &ParamType, slices: &[Hash], offset: usize) -> Result<DecodeResult, Error> {
-	match *param {
-		ParamType::Address => {
-			let slice = try!(peek(slices, offset));
-			let mut address = [0u8; 20];
-			address.copy_from_slice(&slice[12..]);
+		if self.regions.iter().any(|&(s, e)| start < e && s < end) {
+			return Err(Error::OverlappingRegions);
+		}
 
-			let result = DecodeResult {
-				token: ValueType::Address(address),
-				new_offset: offset + 1,
-			};
+		self.regions.push((start, end));
+		Ok(())
+	}
 
-			Ok(result)
-		},
-		ParamType::U32 => {
-			let slice = try!(peek(slices, offset));
+	fn take_bytes(&mut self, position: usize, len: usize) -> Result<BytesTaken, Error> {
+		let slices_len = len.div_ceil(32);
 
-			let result = DecodeResult {
-				token: ValueType::U32(as_u32(slice)?),
-				new_offset: offset + 1,
-			};
+		let mut bytes_slices = vec![];
+		for i in 0..slices_len {
+			let slice = try!(self.peek(position + i)).clone();
+			bytes_slices.push(slice);
+		}
 
-			Ok(result)
-		},
-		ParamType::U64 => {
-			let slice = peek(slices, offset)?;
+		let bytes = bytes_slices.into_iter()
+			.flat_map(|slice| slice.to_vec())
+			.take(len)
+			.collect();
 
-			let result = DecodeResult {
-				token: ValueType::U64(as_u64(slice)?),
-				new_offset: offset + 1,
-			};
+		let taken = BytesTaken {
+			bytes: bytes,
+			new_offset: position + slices_len,
+		};
 
-			Ok(result)
-		},
-		ParamType::I32 => {
-			let slice = peek(slices, offset)?;
+		Ok(taken)
+	}
 
-			let result = DecodeResult {
-				token: ValueType::I32(as_i32(slice)?),
-				new_offset: offset + 1,
-			};
+	/// Decodes `param` into `tokens[index]` (or pushes it, if `index` is past the end), reusing
+	/// the value previously stored there. Returns offset of the next head.
+	fn decode_into(&mut self, param: &ParamType, offset: usize, tokens: &mut Vec<ValueType>, index: usize)
+		-> Result<usize, Error>
+	{
+		let previous = tokens.get_mut(index).map(|token| mem::replace(token, ValueType::Unit));
+		let res = self.decode_param(param, offset, previous)?;
+		match tokens.get_mut(index) {
+			Some(token) => *token = res.token,
+			None => tokens.push(res.token),
+		}
+		Ok(res.new_offset)
+	}
 
-			Ok(result)
-		},
-		ParamType::I64 => {
-			let slice = peek(slices, offset)?;
+	fn decode_param(&mut self, param: &ParamType, offset: usize, previous: Option<ValueType>)
+		-> Result<DecodeResult, Error>
+	{
+		match *param {
+			ParamType::Address => {
+				let slice = try!(self.peek(offset));
+				if !self.config.lenient_address && !slice[..12].iter().all(|x| *x == 0) {
+					return Err(Error::InvalidPadding);
+				}
+				let mut address = [0u8; 20];
+				address.copy_from_slice(&slice[12..]);
 
-			let result = DecodeResult {
-				token: ValueType::I64(as_i64(slice)?),
-				new_offset: offset + 1,
-			};
+				let result = DecodeResult {
+					token: ValueType::Address(address),
+					new_offset: offset + 1,
+				};
 
-			Ok(result)
-		},
-		ParamType::U256 => {
-			let slice = peek(slices, offset)?;
+				Ok(result)
+			},
+			ParamType::U32 => {
+				let slice = try!(self.peek(offset));
 
-			let result = DecodeResult {
-				token: ValueType::U256(slice.clone()),
-				new_offset: offset + 1,
-			};
+				let result = DecodeResult {
+					token: ValueType::U32(as_u32(slice)?),
+					new_offset: offset + 1,
+				};
 
-			Ok(result)
-		},
-		ParamType::H256 => {
-			let slice = peek(slices, offset)?;
+				Ok(result)
+			},
+			ParamType::U64 => {
+				let slice = self.peek(offset)?;
 
-			let result = DecodeResult {
-				token: ValueType::U256(slice.clone()),
-				new_offset: offset + 1,
-			};
+				let result = DecodeResult {
+					token: ValueType::U64(as_u64(slice)?),
+					new_offset: offset + 1,
+				};
 
-			Ok(result)
-		},
-		ParamType::Bool => {
-			let slice = peek(slices, offset)?;
+				Ok(result)
+			},
+			ParamType::I32 => {
+				let slice = self.peek(offset)?;
 
-			let b = as_bool(slice)?;
+				let result = DecodeResult {
+					token: ValueType::I32(as_i32(slice)?),
+					new_offset: offset + 1,
+				};
 
-			let result = DecodeResult {
-				token: ValueType::Bool(b),
-				new_offset: offset + 1,
-			};
+				Ok(result)
+			},
+			ParamType::I64 => {
+				let slice = self.peek(offset)?;
 
-			Ok(result)
-		},
-		ParamType::Bytes => {
-			let offset_slice = peek(slices, offset)?;
-			let len_offset = (try!(as_u32(offset_slice)) / 32) as usize;
+				let result = DecodeResult {
+					token: ValueType::I64(as_i64(slice)?),
+					new_offset: offset + 1,
+				};
 
-			let len_slice = try!(peek(slices, len_offset));
-			let len = try!(as_u32(len_slice)) as usize;
+				Ok(result)
+			},
+			ParamType::U256 => {
+				let slice = self.peek(offset)?;
 
-			let taken = try!(take_bytes(slices, len_offset + 1, len));
+				let result = DecodeResult {
+					token: ValueType::U256(slice.clone()),
+					new_offset: offset + 1,
+				};
 
-			let result = DecodeResult {
-				token: ValueType::Bytes(taken.bytes),
-				new_offset: offset + 1,
-			};
+				Ok(result)
+			},
+			ParamType::H256 => {
+				let slice = self.peek(offset)?;
 
-			Ok(result)
-		},
-		ParamType::String => {
-			let offset_slice = try!(peek(slices, offset));
-			let len_offset = (try!(as_u32(offset_slice)) / 32) as usize;
+				let result = DecodeResult {
+					token: ValueType::H256(slice.clone()),
+					new_offset: offset + 1,
+				};
 
-			let len_slice = try!(peek(slices, len_offset));
-			let len = try!(as_u32(len_slice)) as usize;
+				Ok(result)
+			},
+			ParamType::Uint(bits) => {
+				let slice = self.peek(offset)?;
+				check_uint(slice, bits)?;
 
-			let taken = try!(take_bytes(slices, len_offset + 1, len));
+				let result = DecodeResult {
+					token: ValueType::Uint(slice.clone(), bits),
+					new_offset: offset + 1,
+				};
 
-			let result = DecodeResult {
-				token: ValueType::String(String::from_utf8(taken.bytes).map_err(|_| Error::InvalidUtf8)?),
-				new_offset: offset + 1,
-			};
+				Ok(result)
+			},
+			ParamType::Int(bits) => {
+				let slice = self.peek(offset)?;
+				check_int(slice, bits)?;
 
-			Ok(result)
-		},
-		ParamType::Array(ref t) => {
-			let offset_slice = try!(peek(slices, offset));
-			let len_offset = (try!(as_u32(offset_slice)) / 32) as usize;
+				let result = DecodeResult {
+					token: ValueType::Int(slice.clone(), bits),
+					new_offset: offset + 1,
+				};
 
-			let len_slice = try!(peek(slices, len_offset));
-			let len = try!(as_u32(len_slice)) as usize;
+				Ok(result)
+			},
+			ParamType::FixedBytes(len) => {
+				if len == 0 || len > 32 {
+					return Err(Error::InvalidParamType);
+				}
+				let slice = self.peek(offset)?;
 
-			let mut tokens = vec![];
-			let mut new_offset = len_offset + 1;
+				// left aligned, the padding that follows is ignored
+				let result = DecodeResult {
+					token: ValueType::FixedBytes(slice[..len].to_vec()),
+					new_offset: offset + 1,
+				};
 
-			for _ in 0..len {
-				let res = try!(decode_param(t.as_ref(), &slices, new_offset));
-				new_offset = res.new_offset;
-				tokens.push(res.token);
-			}
+				Ok(result)
+			},
+			ParamType::Bool => {
+				let slice = self.peek(offset)?;
 
-			let result = DecodeResult {
-				token: ValueType::Array(tokens),
-				new_offset: offset + 1,
-			};
+				let b = as_bool(slice)?;
 
-			Ok(result)
-		},
+				let result = DecodeResult {
+					token: ValueType::Bool(b),
+					new_offset: offset + 1,
+				};
+
+				Ok(result)
+			},
+			ParamType::Bytes => {
+				let len_offset = self.tail_position(offset)?;
+
+				let len_slice = try!(self.peek(len_offset));
+				let len = try!(as_u32(len_slice)) as usize;
+				self.check_len(len_offset, len.div_ceil(32))?;
+
+				let taken = try!(self.take_bytes(len_offset + 1, len));
+				self.claim(len_offset, taken.new_offset)?;
+
+				let result = DecodeResult {
+					token: ValueType::Bytes(taken.bytes),
+					new_offset: offset + 1,
+				};
+
+				Ok(result)
+			},
+			ParamType::String => {
+				let len_offset = self.tail_position(offset)?;
+
+				let len_slice = try!(self.peek(len_offset));
+				let len = try!(as_u32(len_slice)) as usize;
+				self.check_len(len_offset, len.div_ceil(32))?;
+
+				let taken = try!(self.take_bytes(len_offset + 1, len));
+				self.claim(len_offset, taken.new_offset)?;
+
+				let result = DecodeResult {
+					token: ValueType::String(match self.config.string_lossy {
+						true => String::from_utf8_lossy(&taken.bytes).into_owned(),
+						false => String::from_utf8(taken.bytes).map_err(|_| Error::InvalidUtf8)?,
+					}),
+					new_offset: offset + 1,
+				};
+
+				Ok(result)
+			},
+			ParamType::Array(ref t) => {
+				let len_offset = self.tail_position(offset)?;
+
+				let len_slice = try!(self.peek(len_offset));
+				let len = try!(as_u32(len_slice)) as usize;
+				// every element takes at least one head word
+				self.check_len(len_offset, len)?;
+
+				let mut tokens = match previous {
+					Some(ValueType::Array(tokens)) => tokens,
+					_ => vec![],
+				};
+				tokens.truncate(len);
+				let mut new_offset = len_offset + 1;
+
+				// offsets of dynamic elements are relative to the word following the length
+				let outer_base = self.base;
+				self.base = len_offset + 1;
+				for i in 0..len {
+					new_offset = try!(self.decode_into(t.as_ref(), new_offset, &mut tokens, i));
+				}
+				self.base = outer_base;
+				// only the length and element heads are claimed, nested tails claim their own regions
+				self.claim(len_offset, new_offset)?;
+
+				let result = DecodeResult {
+					token: ValueType::Array(tokens),
+					new_offset: offset + 1,
+				};
+
+				Ok(result)
+			},
+			ParamType::FixedArray(ref t, len) => {
+				let previous = match previous {
+					Some(ValueType::FixedArray(tokens)) => Some(tokens),
+					_ => None,
+				};
+				let (tokens, new_offset) = self.decode_composite(param, iter::repeat(t.as_ref()).take(len), offset, previous)?;
+
+				let result = DecodeResult {
+					token: ValueType::FixedArray(tokens),
+					new_offset: new_offset,
+				};
+
+				Ok(result)
+			},
+			ParamType::Tuple(ref types) => {
+				let previous = match previous {
+					Some(ValueType::Tuple(tokens)) => Some(tokens),
+					_ => None,
+				};
+				let (tokens, new_offset) = self.decode_composite(param, types.iter(), offset, previous)?;
+
+				let result = DecodeResult {
+					token: ValueType::Tuple(tokens),
+					new_offset: new_offset,
+				};
+
+				Ok(result)
+			},
+		}
+	}
+
+	/// Decodes members of a tuple or fixed array, returning them with offset of the next head
+	///
+	/// Static composites are laid out in place; dynamic ones are encoded at the tail and
+	/// offsets of their members are relative to the start of that tail.
+	fn decode_composite<'p, I>(&mut self, param: &ParamType, types: I, offset: usize, previous: Option<Vec<ValueType>>)
+		-> Result<(Vec<ValueType>, usize), Error>
+		where I: Iterator<Item = &'p ParamType>
+	{
+		let dynamic = param.is_dynamic();
+		let start = match dynamic {
+			true => self.tail_position(offset)?,
+			false => offset,
+		};
+		let outer_base = self.base;
+		if dynamic {
+			self.base = start;
+		}
+
+		let mut tokens = previous.unwrap_or_default();
+		let mut new_offset = start;
+		let mut count = 0;
+		for (i, t) in types.enumerate() {
+			new_offset = self.decode_into(t, new_offset, &mut tokens, i)?;
+			count = i + 1;
+		}
+		tokens.truncate(count);
+		self.base = outer_base;
+
+		if !dynamic {
+			return Ok((tokens, new_offset));
+		}
+
+		// only member heads are claimed, nested tails claim their own regions
+		self.claim(start, new_offset)?;
+		Ok((tokens, offset + 1))
 	}
 }
 
@@ -221,8 +608,9 @@ mod tests {
 	extern crate rustc_hex as hex;
 
 	use self::hex::FromHex;
-	use super::decode;
-    use super::super::{ValueType, ParamType};
+	use super::{decode, decode_with_config, decode_strict, decode_with_len, decode_reuse, decode_traced, decode_with_words, decode_canonical, DecodeConfig, Decoder};
+    use super::super::{ValueType, ParamType, Error};
+    use super::super::encode::encode as encode_values;
 
 	#[test]
 	fn decode_address() {
@@ -231,6 +619,7 @@ mod tests {
 		let expected = vec![address];
 		let decoded = decode(&[ParamType::Address], &encoded).unwrap();
 		assert_eq!(decoded, expected);
+		assert_eq!(encode_values(&decoded), encoded);
 	}
 
 	#[test]
@@ -243,6 +632,7 @@ mod tests {
 		let expected = vec![address1, address2];
 		let decoded = decode(&[ParamType::Address, ParamType::Address], &encoded).unwrap();
 		assert_eq!(decoded, expected);
+		assert_eq!(encode_values(&decoded), encoded);
 	}
 
 	#[test]
@@ -252,6 +642,22 @@ mod tests {
 		let expected = vec![uint];
 		let decoded = decode(&[ParamType::U256], &encoded).unwrap();
 		assert_eq!(decoded, expected);
+		assert_eq!(encode_values(&decoded), encoded);
+	}
+
+	#[test]
+	fn decode_hash() {
+		use parity_hash::H256;
+
+		let encoded = "0102030405060708091011121314151617181920212223242526272829303132".from_hex().unwrap();
+		let mut word = [0u8; 32];
+		word.copy_from_slice(&encoded);
+		let mut decoded = decode(&[ParamType::H256], &encoded).unwrap();
+		assert_eq!(decoded, vec![ValueType::H256(word)]);
+
+		// converting used to panic, the hash was decoded as `ValueType::U256`
+		let hash = H256::from(decoded.pop().unwrap());
+		assert_eq!(&hash[..], &encoded[..]);
 	}
 
 	#[test]
@@ -267,6 +673,7 @@ mod tests {
 		let expected = vec![addresses];
 		let decoded = decode(&[ParamType::Array(ParamType::Address.into())], &encoded).unwrap();
 		assert_eq!(decoded, expected);
+		assert_eq!(encode_values(&decoded), encoded);
 	}
 
 
@@ -275,8 +682,8 @@ mod tests {
 		let encoded  = ("".to_owned() +
 			"0000000000000000000000000000000000000000000000000000000000000020" +
 			"0000000000000000000000000000000000000000000000000000000000000002" +
+			"0000000000000000000000000000000000000000000000000000000000000040" +
 			"0000000000000000000000000000000000000000000000000000000000000080" +
-			"00000000000000000000000000000000000000000000000000000000000000c0" +
 			"0000000000000000000000000000000000000000000000000000000000000001" +
 			"0000000000000000000000001111111111111111111111111111111111111111" +
 			"0000000000000000000000000000000000000000000000000000000000000001" +
@@ -294,6 +701,7 @@ mod tests {
 			)
 		], &encoded).unwrap();
 		assert_eq!(decoded, expected);
+		assert_eq!(encode_values(&decoded), encoded);
 	}
 
 	#[test]
@@ -301,8 +709,8 @@ mod tests {
 		let encoded = ("".to_owned() +
 			"0000000000000000000000000000000000000000000000000000000000000020" +
 			"0000000000000000000000000000000000000000000000000000000000000002" +
-			"0000000000000000000000000000000000000000000000000000000000000080" +
-			"00000000000000000000000000000000000000000000000000000000000000e0" +
+			"0000000000000000000000000000000000000000000000000000000000000040" +
+			"00000000000000000000000000000000000000000000000000000000000000a0" +
 			"0000000000000000000000000000000000000000000000000000000000000002" +
 			"0000000000000000000000001111111111111111111111111111111111111111" +
 			"0000000000000000000000002222222222222222222222222222222222222222" +
@@ -324,6 +732,7 @@ mod tests {
 			)
 		], &encoded).unwrap();
 		assert_eq!(decoded, expected);
+		assert_eq!(encode_values(&decoded), encoded);
 	}
 
 	#[test]
@@ -336,6 +745,7 @@ mod tests {
 		let expected = vec![bytes];
 		let decoded = decode(&[ParamType::Bytes], &encoded).unwrap();
 		assert_eq!(decoded, expected);
+		assert_eq!(encode_values(&decoded), encoded);
 	}
 
 	#[test]
@@ -351,6 +761,7 @@ mod tests {
 		let expected = vec![bytes];
 		let decoded = decode(&[ParamType::Bytes], &encoded).unwrap();
 		assert_eq!(decoded, expected);
+		assert_eq!(encode_values(&decoded), encoded);
 	}
 
 	#[test]
@@ -367,6 +778,7 @@ mod tests {
 		let expected = vec![bytes1, bytes2];
 		let decoded = decode(&[ParamType::Bytes, ParamType::Bytes], &encoded).unwrap();
 		assert_eq!(decoded, expected);
+		assert_eq!(encode_values(&decoded), encoded);
 	}
 
 	#[test]
@@ -379,6 +791,561 @@ mod tests {
 		let expected = vec![s];
 		let decoded = decode(&[ParamType::String], &encoded).unwrap();
 		assert_eq!(decoded, expected);
+		assert_eq!(encode_values(&decoded), encoded);
+	}
+
+	#[test]
+	fn decode_overlapping_bytes() {
+		let encoded = ("".to_owned() +
+			"0000000000000000000000000000000000000000000000000000000000000040" +
+			"0000000000000000000000000000000000000000000000000000000000000040" +
+			"0000000000000000000000000000000000000000000000000000000000000002" +
+			"1234000000000000000000000000000000000000000000000000000000000000").from_hex().unwrap();
+		let types = [ParamType::Bytes, ParamType::Bytes];
+
+		let decoded = decode(&types, &encoded).unwrap();
+		assert_eq!(decoded, vec![ValueType::Bytes(vec![0x12, 0x34]), ValueType::Bytes(vec![0x12, 0x34])]);
+
+		let mut config = DecodeConfig::default();
+		config.reject_overlapping = true;
+		assert_eq!(decode_with_config(&types, &encoded, &config), Err(Error::OverlappingRegions));
+	}
+
+	#[test]
+	fn decode_partially_overlapping_bytes() {
+		// second offset points into the data of the first value
+		let encoded = ("".to_owned() +
+			"0000000000000000000000000000000000000000000000000000000000000040" +
+			"0000000000000000000000000000000000000000000000000000000000000060" +
+			"0000000000000000000000000000000000000000000000000000000000000040" +
+			"0000000000000000000000000000000000000000000000000000000000000001" +
+			"1200000000000000000000000000000000000000000000000000000000000000").from_hex().unwrap();
+		let types = [ParamType::Bytes, ParamType::Bytes];
+
+		assert!(decode(&types, &encoded).is_ok());
+
+		let mut config = DecodeConfig::default();
+		config.reject_overlapping = true;
+		assert_eq!(decode_with_config(&types, &encoded, &config), Err(Error::OverlappingRegions));
+	}
+
+	#[test]
+	fn decode_nested_arrays_strict() {
+		let encoded  = ("".to_owned() +
+			"0000000000000000000000000000000000000000000000000000000000000020" +
+			"0000000000000000000000000000000000000000000000000000000000000002" +
+			"0000000000000000000000000000000000000000000000000000000000000040" +
+			"0000000000000000000000000000000000000000000000000000000000000080" +
+			"0000000000000000000000000000000000000000000000000000000000000001" +
+			"0000000000000000000000001111111111111111111111111111111111111111" +
+			"0000000000000000000000000000000000000000000000000000000000000001" +
+			"0000000000000000000000002222222222222222222222222222222222222222").from_hex().unwrap();
+		let types = [ParamType::Array(ParamType::Array(ParamType::Address.into()).into())];
+
+		let mut config = DecodeConfig::default();
+		config.reject_overlapping = true;
+		assert_eq!(decode_with_config(&types, &encoded, &config), decode(&types, &encoded));
+	}
+
+	#[test]
+	fn decode_offset_too_large() {
+		let encoded = ("".to_owned() +
+			"0000000000000000000000000000000000000000000000000000000100000000" +
+			"0000000000000000000000000000000000000000000000000000000000000002" +
+			"1234000000000000000000000000000000000000000000000000000000000000").from_hex().unwrap();
+
+		assert_eq!(decode(&[ParamType::Bytes], &encoded), Err(Error::OffsetTooLarge));
+		assert_eq!(decode(&[ParamType::Array(ParamType::U32.into())], &encoded), Err(Error::OffsetTooLarge));
+	}
+
+	#[test]
+	fn decode_enormous_length() {
+		// truncated payload claiming 0xffffffff bytes / elements
+		let encoded = ("".to_owned() +
+			"0000000000000000000000000000000000000000000000000000000000000020" +
+			"00000000000000000000000000000000000000000000000000000000ffffffff" +
+			"1234000000000000000000000000000000000000000000000000000000000000").from_hex().unwrap();
+
+		// the position past the data doesn't fit in a 32-bit usize, it is clamped there
+		let end = |words: u64| (64 + 32 * words).min(usize::MAX as u64) as usize;
+		let bytes_end = end(0xffffffffu64.div_ceil(32));
+		assert_eq!(decode(&[ParamType::Bytes], &encoded), Err(Error::UnexpectedEnd { position: bytes_end }));
+		assert_eq!(decode(&[ParamType::String], &encoded), Err(Error::UnexpectedEnd { position: bytes_end }));
+		assert_eq!(
+			decode(&[ParamType::Array(ParamType::U256.into())], &encoded),
+			Err(Error::UnexpectedEnd { position: end(0xffffffff) })
+		);
+	}
+
+	#[test]
+	fn decode_canonical_rejects_reencoded() {
+		let types = [ParamType::Bytes, ParamType::String];
+		let canonical = ("".to_owned() +
+			"0000000000000000000000000000000000000000000000000000000000000040" +
+			"0000000000000000000000000000000000000000000000000000000000000080" +
+			"0000000000000000000000000000000000000000000000000000000000000002" +
+			"1234000000000000000000000000000000000000000000000000000000000000" +
+			"0000000000000000000000000000000000000000000000000000000000000003" +
+			"6162630000000000000000000000000000000000000000000000000000000000").from_hex().unwrap();
+		// the same values with tails swapped
+		let reordered = ("".to_owned() +
+			"00000000000000000000000000000000000000000000000000000000000000c0" +
+			"0000000000000000000000000000000000000000000000000000000000000040" +
+			"0000000000000000000000000000000000000000000000000000000000000003" +
+			"6162630000000000000000000000000000000000000000000000000000000000" +
+			"0000000000000000000000000000000000000000000000000000000000000000" +
+			"0000000000000000000000000000000000000000000000000000000000000000" +
+			"0000000000000000000000000000000000000000000000000000000000000002" +
+			"1234000000000000000000000000000000000000000000000000000000000000").from_hex().unwrap();
+		// junk in the padding of the bytes
+		let mut dirty = canonical.clone();
+		dirty[127] = 0xff;
+
+		let values = decode_canonical(&types, &canonical).unwrap();
+		assert_eq!(values, vec![ValueType::Bytes(vec![0x12, 0x34]), ValueType::String("abc".to_owned())]);
+
+		assert_eq!(decode(&types, &reordered).unwrap(), values);
+		assert_eq!(decode_canonical(&types, &reordered), Err(Error::NonCanonical));
+		assert_eq!(decode(&types, &dirty).unwrap(), values);
+		assert_eq!(decode_canonical(&types, &dirty), Err(Error::NonCanonical));
+		assert_eq!(decode_canonical(&types, &[&canonical[..], &[0u8; 32][..]].concat()), Err(Error::NonCanonical));
+	}
+
+	#[test]
+	fn decode_len_static() {
+		// transfer(address,uint256) arguments
+		let encoded = ("".to_owned() +
+			"0000000000000000000000001111111111111111111111111111111111111111" +
+			"0000000000000000000000000000000000000000000000000000000000000045").from_hex().unwrap();
+		let (decoded, consumed) = decode_with_len(&[ParamType::Address, ParamType::U256], &encoded).unwrap();
+		assert_eq!(decoded.len(), 2);
+		assert_eq!(consumed, 64);
 	}
-}
 
+	#[test]
+	fn decode_len_bytes() {
+		let encoded = ("".to_owned() +
+			"0000000000000000000000000000000000000000000000000000000000000020" +
+			"0000000000000000000000000000000000000000000000000000000000000002" +
+			"1234000000000000000000000000000000000000000000000000000000000000").from_hex().unwrap();
+		let (decoded, consumed) = decode_with_len(&[ParamType::Bytes], &encoded).unwrap();
+		assert_eq!(decoded, vec![ValueType::Bytes(vec![0x12, 0x34])]);
+		assert_eq!(consumed, 96);
+	}
+
+	#[test]
+	fn decode_trailing_data() {
+		let encoded = ("".to_owned() +
+			"0000000000000000000000001111111111111111111111111111111111111111" +
+			"0000000000000000000000000000000000000000000000000000000000000000").from_hex().unwrap();
+		let (_, consumed) = decode_with_len(&[ParamType::Address], &encoded).unwrap();
+		assert_eq!(consumed, 32);
+		assert!(decode(&[ParamType::Address], &encoded).is_ok());
+
+		let mut config = DecodeConfig::default();
+		config.reject_trailing = true;
+		assert_eq!(
+			decode_with_config(&[ParamType::Address], &encoded, &config),
+			Err(Error::TrailingData { remaining: 32 })
+		);
+	}
+
+	#[test]
+	fn decode_strict_trailing_word() {
+		let encoded = ("".to_owned() +
+			"0000000000000000000000001111111111111111111111111111111111111111" +
+			"0000000000000000000000000000000000000000000000000000000000000040" +
+			"0000000000000000000000000000000000000000000000000000000000000002" +
+			"1234000000000000000000000000000000000000000000000000000000000000").from_hex().unwrap();
+		let types = [ParamType::Address, ParamType::Bytes];
+		// the tail of the bytes counts as consumed
+		assert_eq!(decode_strict(&types, &encoded), decode(&types, &encoded));
+
+		let mut appended = encoded.clone();
+		appended.extend_from_slice(&[0xffu8; 32]);
+		assert_eq!(decode(&types, &appended), decode(&types, &encoded));
+		assert_eq!(decode_strict(&types, &appended), Err(Error::TrailingData { remaining: 32 }));
+	}
+
+	#[test]
+	fn decode_reuse_buffer() {
+		let types = [ParamType::U32, ParamType::Array(ParamType::Array(ParamType::U32.into()).into())];
+		let first = encode_values(&[
+			ValueType::U32(1),
+			ValueType::Array(vec![
+				ValueType::Array(vec![ValueType::U32(2), ValueType::U32(3), ValueType::U32(4)]),
+			]),
+		]);
+		let second = encode_values(&[
+			ValueType::U32(5),
+			ValueType::Array(vec![
+				ValueType::Array(vec![ValueType::U32(6), ValueType::U32(7)]),
+			]),
+		]);
+
+		let mut out = Vec::new();
+		decode_reuse(&types, &first, &mut out).unwrap();
+		assert_eq!(out, decode(&types, &first).unwrap());
+
+		let inner_ptr = |out: &Vec<ValueType>| match out[1] {
+			ValueType::Array(ref outer) => match outer[0] {
+				ValueType::Array(ref inner) => (outer.as_ptr(), inner.as_ptr()),
+				_ => unreachable!(),
+			},
+			_ => unreachable!(),
+		};
+		let capacity = out.capacity();
+		let ptrs = inner_ptr(&out);
+
+		decode_reuse(&types, &second, &mut out).unwrap();
+		assert_eq!(out, decode(&types, &second).unwrap());
+		assert_eq!(out.capacity(), capacity);
+		assert_eq!(inner_ptr(&out), ptrs);
+	}
+
+	#[test]
+	fn decode_traced_string_tail() {
+		let encoded = ("".to_owned() +
+			"0000000000000000000000000000000000000000000000000000000000000005" +
+			"0000000000000000000000000000000000000000000000000000000000000040" +
+			"0000000000000000000000000000000000000000000000000000000000000009" +
+			"6761766f66796f726b0000000000000000000000000000000000000000000000").from_hex().unwrap();
+
+		let (decoded, trace) = decode_traced(&[ParamType::U256, ParamType::String], &encoded).unwrap();
+
+		assert_eq!(decoded[1], ValueType::String("gavofyork".to_owned()));
+		assert_eq!(trace.len(), 2);
+		assert_eq!(trace[0].param_index, 0);
+		assert_eq!(trace[0].head_word_index, 0);
+		assert_eq!(trace[0].tail_offset, None);
+		assert_eq!(trace[1].param_index, 1);
+		assert_eq!(trace[1].head_word_index, 1);
+		assert_eq!(trace[1].tail_offset, Some(0x40));
+	}
+
+	#[test]
+	fn decode_with_raw_words() {
+		let encoded = ("".to_owned() +
+			"0000000000000000000000001111111111111111111111111111111111111111" +
+			"0000000000000000000000000000000000000000000000000000000000000040" +
+			"0000000000000000000000000000000000000000000000000000000000000003" +
+			"0102030000000000000000000000000000000000000000000000000000000000").from_hex().unwrap();
+		let word = |i: usize| {
+			let mut word = [0u8; 32];
+			word.copy_from_slice(&encoded[32 * i..32 * (i + 1)]);
+			word
+		};
+
+		let decoded = decode_with_words(&[ParamType::Address, ParamType::Bytes], &encoded).unwrap();
+
+		assert_eq!(decoded, vec![
+			(ValueType::Address([0x11u8; 20]), vec![word(0)]),
+			(ValueType::Bytes(vec![1, 2, 3]), vec![word(1), word(2), word(3)]),
+		]);
+	}
+
+	#[test]
+	fn decode_invalid_utf8_string() {
+		let encoded = ("".to_owned() +
+			"0000000000000000000000000000000000000000000000000000000000000020" +
+			"0000000000000000000000000000000000000000000000000000000000000003" +
+			"61ff620000000000000000000000000000000000000000000000000000000000").from_hex().unwrap();
+
+		assert_eq!(decode(&[ParamType::String], &encoded), Err(Error::InvalidUtf8));
+
+		let mut config = DecodeConfig::default();
+		config.string_lossy = true;
+		let decoded = decode_with_config(&[ParamType::String], &encoded, &config).unwrap();
+		assert_eq!(decoded, vec![ValueType::String("a\u{fffd}b".to_owned())]);
+	}
+
+	#[test]
+	fn decode_fixed_array_of_static_tuples() {
+		let encoded = ("".to_owned() +
+			"0000000000000000000000000000000000000000000000000000000000000001" +
+			"0000000000000000000000000000000000000000000000000000000000000001" +
+			"0000000000000000000000000000000000000000000000000000000000000002" +
+			"0000000000000000000000000000000000000000000000000000000000000000" +
+			"0000000000000000000000000000000000000000000000000000000000000005").from_hex().unwrap();
+
+		let tuple = ParamType::Tuple(vec![ParamType::U256, ParamType::Bool].into());
+		let types = [ParamType::FixedArray(tuple.into(), 2), ParamType::U32];
+		let decoded = decode(&types, &encoded).unwrap();
+
+		let mut one = [0u8; 32];
+		one[31] = 1;
+		let mut two = [0u8; 32];
+		two[31] = 2;
+		assert_eq!(decoded, vec![
+			ValueType::FixedArray(vec![
+				ValueType::Tuple(vec![ValueType::U256(one), ValueType::Bool(true)]),
+				ValueType::Tuple(vec![ValueType::U256(two), ValueType::Bool(false)]),
+			]),
+			ValueType::U32(5),
+		]);
+		assert_eq!(encode_values(&decoded), encoded);
+	}
+
+	#[test]
+	fn decode_static_tuple() {
+		// static tuples are laid out in place, without an offset
+		let encoded = ("".to_owned() +
+			"0000000000000000000000000000000000000000000000000000000000000001" +
+			"0000000000000000000000001111111111111111111111111111111111111111" +
+			"0000000000000000000000000000000000000000000000000000000000000001").from_hex().unwrap();
+
+		let types = [ParamType::Tuple(vec![ParamType::U32, ParamType::Address].into()), ParamType::Bool];
+		let decoded = decode(&types, &encoded).unwrap();
+
+		assert_eq!(decoded, vec![
+			ValueType::Tuple(vec![ValueType::U32(1), ValueType::Address([0x11u8; 20])]),
+			ValueType::Bool(true),
+		]);
+		assert_eq!(encode_values(&decoded), encoded);
+	}
+
+	#[test]
+	fn decode_dynamic_tuple() {
+		let encoded = ("".to_owned() +
+			"0000000000000000000000000000000000000000000000000000000000000040" +
+			"0000000000000000000000000000000000000000000000000000000000000002" +
+			"0000000000000000000000000000000000000000000000000000000000000001" +
+			"0000000000000000000000000000000000000000000000000000000000000040" +
+			"0000000000000000000000000000000000000000000000000000000000000009" +
+			"6761766f66796f726b0000000000000000000000000000000000000000000000").from_hex().unwrap();
+
+		let types = [ParamType::Tuple(vec![ParamType::U32, ParamType::String].into()), ParamType::U32];
+		let decoded = decode(&types, &encoded).unwrap();
+
+		assert_eq!(decoded, vec![
+			ValueType::Tuple(vec![ValueType::U32(1), ValueType::String("gavofyork".to_owned())]),
+			ValueType::U32(2),
+		]);
+		assert_eq!(encode_values(&decoded), encoded);
+	}
+
+	#[test]
+	fn decode_array_of_dynamic_tuples() {
+		// (uint256,string)[]: element offsets follow the length, string offsets are relative to their tuple
+		let encoded = ("".to_owned() +
+			"0000000000000000000000000000000000000000000000000000000000000020" +
+			"0000000000000000000000000000000000000000000000000000000000000002" +
+			"0000000000000000000000000000000000000000000000000000000000000040" +
+			"00000000000000000000000000000000000000000000000000000000000000c0" +
+			"0000000000000000000000000000000000000000000000000000000000000001" +
+			"0000000000000000000000000000000000000000000000000000000000000040" +
+			"0000000000000000000000000000000000000000000000000000000000000009" +
+			"6761766f66796f726b0000000000000000000000000000000000000000000000" +
+			"0000000000000000000000000000000000000000000000000000000000000002" +
+			"0000000000000000000000000000000000000000000000000000000000000040" +
+			"0000000000000000000000000000000000000000000000000000000000000006" +
+			"6465627269730000000000000000000000000000000000000000000000000000").from_hex().unwrap();
+
+		let tuple = ParamType::Tuple(vec![ParamType::U256, ParamType::String].into());
+		let decoded = decode(&[ParamType::Array(tuple.into())], &encoded).unwrap();
+
+		let mut one = [0u8; 32];
+		one[31] = 1;
+		let mut two = [0u8; 32];
+		two[31] = 2;
+		assert_eq!(decoded, vec![ValueType::Array(vec![
+			ValueType::Tuple(vec![ValueType::U256(one), ValueType::String("gavofyork".to_owned())]),
+			ValueType::Tuple(vec![ValueType::U256(two), ValueType::String("debris".to_owned())]),
+		])]);
+		assert_eq!(encode_values(&decoded), encoded);
+	}
+
+	#[test]
+	fn decode_fixed_array_of_strings() {
+		let values = vec![
+			ValueType::FixedArray(vec![
+				ValueType::String("gavofyork".to_owned()),
+				ValueType::String("debris".to_owned()),
+			]),
+			ValueType::Bytes(vec![0x12, 0x34]),
+		];
+		let types = [ParamType::FixedArray(ParamType::String.into(), 2), ParamType::Bytes];
+
+		assert_eq!(decode(&types, &encode_values(&values)).unwrap(), values);
+	}
+
+	#[test]
+	fn decode_fixed_array_fixed_arrays() {
+		let encoded = ("".to_owned() +
+			"0000000000000000000000001111111111111111111111111111111111111111" +
+			"0000000000000000000000002222222222222222222222222222222222222222" +
+			"0000000000000000000000003333333333333333333333333333333333333333" +
+			"0000000000000000000000004444444444444444444444444444444444444444").from_hex().unwrap();
+
+		// address[2][2], static all the way down so elements are laid out in place
+		let inner = ParamType::FixedArray(ParamType::Address.into(), 2);
+		let types = [ParamType::FixedArray(inner.into(), 2)];
+		let decoded = decode(&types, &encoded).unwrap();
+
+		assert_eq!(decoded, vec![
+			ValueType::FixedArray(vec![
+				ValueType::FixedArray(vec![ValueType::Address([0x11u8; 20]), ValueType::Address([0x22u8; 20])]),
+				ValueType::FixedArray(vec![ValueType::Address([0x33u8; 20]), ValueType::Address([0x44u8; 20])]),
+			]),
+		]);
+		assert_eq!(encode_values(&decoded), encoded);
+	}
+
+	#[test]
+	fn decode_fixed_array_of_dynamic_fixed_arrays() {
+		// string[2][2] is referenced by offset, as is each of its string[2] elements
+		let values = vec![
+			ValueType::FixedArray(vec![
+				ValueType::FixedArray(vec![ValueType::String("a".to_owned()), ValueType::String("bc".to_owned())]),
+				ValueType::FixedArray(vec![ValueType::String("def".to_owned()), ValueType::String("".to_owned())]),
+			]),
+			ValueType::U32(7),
+		];
+		let inner = ParamType::FixedArray(ParamType::String.into(), 2);
+		let types = [ParamType::FixedArray(inner.into(), 2), ParamType::U32];
+
+		let encoded = encode_values(&values);
+		assert_eq!(&encoded[..32], &"0000000000000000000000000000000000000000000000000000000000000040".from_hex().unwrap()[..]);
+		assert_eq!(decode_canonical(&types, &encoded).unwrap(), values);
+	}
+
+	#[test]
+	fn decode_fixed_bytes() {
+		let encoded = "1234000000000000000000000000000000000000000000000000000000000000".from_hex().unwrap();
+		let decoded = decode(&[ParamType::FixedBytes(2)], &encoded).unwrap();
+
+		assert_eq!(decoded, vec![ValueType::FixedBytes(vec![0x12, 0x34])]);
+		assert_eq!(encode_values(&decoded), encoded);
+		assert_eq!(decode(&[ParamType::FixedBytes(33)], &encoded), Err(Error::InvalidParamType));
+		// `bytes0` doesn't parse either
+		assert_eq!(decode(&[ParamType::FixedBytes(0)], &encoded), Err(Error::InvalidParamType));
+	}
+
+	#[test]
+	fn decode_sized_integers() {
+		let encoded = ("".to_owned() +
+			"00000000000000000000000000000000ffffffffffffffffffffffffffffffff" +
+			"fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffb" +
+			"00000000000000000000000000000000000000000000000000000000007fffff").from_hex().unwrap();
+		let types = [ParamType::Uint(128), ParamType::Int(24), ParamType::Int(24)];
+		let decoded = decode(&types, &encoded).unwrap();
+
+		let word = |i: usize| {
+			let mut word = [0u8; 32];
+			word.copy_from_slice(&encoded[32 * i..32 * (i + 1)]);
+			word
+		};
+		assert_eq!(decoded, vec![ValueType::Uint(word(0), 128), ValueType::Int(word(1), 24), ValueType::Int(word(2), 24)]);
+		assert_eq!(encode_values(&decoded), encoded);
+	}
+
+	#[test]
+	fn decode_sized_integers_out_of_range() {
+		let too_wide = "0000000000000000000000000000000100000000000000000000000000000000".from_hex().unwrap();
+		assert_eq!(decode(&[ParamType::Uint(128)], &too_wide), Err(Error::IntegerOverflow { width: 128 }));
+
+		// 0x800000 is negative as int24, so it must be sign extended
+		let not_extended = "0000000000000000000000000000000000000000000000000000000000800000".from_hex().unwrap();
+		assert_eq!(decode(&[ParamType::Int(24)], &not_extended), Err(Error::IntegerOverflow { width: 24 }));
+		assert!(decode(&[ParamType::Int(256)], &not_extended).is_ok());
+
+		assert_eq!(decode(&[ParamType::Uint(12)], &too_wide), Err(Error::InvalidParamType));
+	}
+
+	#[test]
+	fn decode_unsigned_out_of_range() {
+		let bits_33 = "0000000000000000000000000000000000000000000000000000000100000000".from_hex().unwrap();
+		assert_eq!(decode(&[ParamType::U32], &bits_33), Err(Error::IntegerOverflow { width: 32 }));
+		assert_eq!(decode(&[ParamType::U64], &bits_33), Ok(vec![ValueType::U64(1 << 32)]));
+
+		let bits_65 = "0000000000000000000000000000000000000000000000010000000000000000".from_hex().unwrap();
+		assert_eq!(decode(&[ParamType::U64], &bits_65), Err(Error::IntegerOverflow { width: 64 }));
+	}
+
+	#[test]
+	fn decode_negative_integers() {
+		let encoded = ("".to_owned() +
+			"ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff" +
+			"ffffffffffffffffffffffffffffffffffffffffffffffffffffffff80000000" +
+			"ffffffffffffffffffffffffffffffffffffffffffffffff8000000000000000" +
+			"ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff85").from_hex().unwrap();
+		let types = [ParamType::I32, ParamType::I32, ParamType::I64, ParamType::I64];
+
+		assert_eq!(decode(&types, &encoded).unwrap(), vec![
+			ValueType::I32(-1),
+			ValueType::I32(i32::MIN),
+			ValueType::I64(i64::MIN),
+			ValueType::I64(-123),
+		]);
+		assert_eq!(encode_values(&decode(&types, &encoded).unwrap()), encoded);
+	}
+
+	#[test]
+	fn decode_signed_out_of_range() {
+		// i32::MIN - 1 and i32::MAX + 1, which don't fit although the low 32 bits look fine
+		let below = "ffffffffffffffffffffffffffffffffffffffffffffffffffffffff7fffffff".from_hex().unwrap();
+		let above = "0000000000000000000000000000000000000000000000000000000080000000".from_hex().unwrap();
+		assert_eq!(decode(&[ParamType::I32], &below), Err(Error::IntegerOverflow { width: 32 }));
+		assert_eq!(decode(&[ParamType::I32], &above), Err(Error::IntegerOverflow { width: 32 }));
+		assert_eq!(decode(&[ParamType::I64], &below), Ok(vec![ValueType::I64(i32::MIN as i64 - 1)]));
+
+		let not_extended = "00000000000000000000000000000000000000000000000000000000ffffffff".from_hex().unwrap();
+		assert_eq!(decode(&[ParamType::I32], &not_extended), Err(Error::IntegerOverflow { width: 32 }));
+	}
+
+	#[test]
+	fn decode_misaligned_payload() {
+		let encoded = "000000000000000000000000000000000000000000000000000000000000000102".from_hex().unwrap();
+		let err = decode(&[ParamType::U32], &encoded).unwrap_err();
+
+		assert_eq!(err, Error::LengthMismatch { expected: 64, got: 33 });
+		assert_eq!(err.to_string(), "payload length 33 is not a multiple of 32, expected 64");
+		assert_eq!(decode(&[ParamType::U32, ParamType::U32], &encoded[..32]).unwrap_err().to_string(), "payload ends before byte 64");
+	}
+
+	#[test]
+	fn decode_streaming() {
+		let values = vec![
+			ValueType::Address([0x11u8; 20]),
+			ValueType::Array(vec![ValueType::U32(1), ValueType::U32(2)]),
+			ValueType::String("gavofyork".to_owned()),
+		];
+		let encoded = encode_values(&values);
+
+		let mut decoder = Decoder::new(&encoded).unwrap();
+		assert_eq!(decoder.next(&ParamType::Address), Ok(ValueType::Address([0x11u8; 20])));
+		assert_eq!(decoder.next(&ParamType::Array(ParamType::U32.into())), Ok(ValueType::Array(vec![ValueType::U32(1), ValueType::U32(2)])));
+		assert_eq!(decoder.next(&ParamType::String), Ok(ValueType::String("gavofyork".to_owned())));
+
+		// the rest of the payload is never looked at if decoding stops after the first param
+		let mut decoder = Decoder::new(&encoded[..32]).unwrap();
+		assert_eq!(decoder.next(&ParamType::Address), Ok(ValueType::Address([0x11u8; 20])));
+		assert_eq!(decoder.next(&ParamType::Array(ParamType::U32.into())), Err(Error::UnexpectedEnd { position: 64 }));
+
+		assert!(Decoder::new(&encoded[..33]).is_err());
+	}
+
+	#[test]
+	fn decode_too_many_params() {
+		let types = vec![ParamType::U32; 1000];
+
+		// rejected before payload is looked at, so even an empty one fails this way
+		assert_eq!(decode(&types, &[]), Err(Error::TooManyParams { count: 1000, max: 256 }));
+
+		let mut config = DecodeConfig::default();
+		config.max_params = 1000;
+		assert_eq!(decode_with_config(&types, &[], &config), Err(Error::UnexpectedEnd { position: 32 }));
+	}
+
+	#[test]
+	fn decode_dirty_address() {
+		let encoded = "ffffffffffffffffffffffff1111111111111111111111111111111111111111".from_hex().unwrap();
+
+		assert_eq!(decode(&[ParamType::Address], &encoded), Err(Error::InvalidPadding));
+
+		let mut config = DecodeConfig::default();
+		config.lenient_address = true;
+		let decoded = decode_with_config(&[ParamType::Address], &encoded, &config).unwrap();
+		assert_eq!(decoded, vec![ValueType::Address([0x11u8; 20])]);
+	}
+}
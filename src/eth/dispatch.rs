@@ -1,10 +1,16 @@
 use byteorder::{BigEndian, ByteOrder};
-use tiny_keccak::Keccak;
 use parity_hash::H256;
 
 use lib::*;
-use super::{Signature, ValueType};
-use super::util::Error;
+use super::{Signature, ValueType, ParamType, ArrayRef};
+use super::util::{keccak256, Error};
+use super::keccak;
+use super::decode::decode;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+/// Length of a selector map entry: big-endian selector followed by the target index
+const SELECTOR_MAP_ENTRY_LEN: usize = 5;
 
 #[derive(Clone)]
 pub struct HashSignature {
@@ -16,15 +22,20 @@ pub struct HashSignature {
 pub struct NamedSignature {
 	name: Cow<'static, str>,
 	signature: Signature,
+	// computed on creation, name and signature can't change afterwards
+	hash: H256,
 }
 
 #[derive(Default)]
 pub struct Table {
-	// slice instead of hashmap since dispatch table is usually small (todo: maybe add variant with hash tables)
+	// slice instead of hashmap since dispatch table is usually small (see `FastTable` for large ones)
 	pub inner: Cow<'static, [HashSignature]>,
 
 	// anonymous signature/constructor
 	pub fallback: Option<Signature>,
+
+	// `inner` is ordered by hash, so lookups can use binary search
+	sorted: bool,
 }
 
 impl From<NamedSignature> for HashSignature {
@@ -43,33 +54,104 @@ impl Table {
 	pub fn new<T>(inner: T) -> Self
 		where T: Into<Cow<'static, [HashSignature]>>
 	{
-		Table { inner: inner.into(), fallback: None }
+		let inner = inner.into();
+		let sorted = is_sorted(&inner);
+		Table { inner: inner, fallback: None, sorted: sorted }
 	}
 
 	pub fn with_fallback<T>(inner: T, fallback: Signature) -> Self
 		where T: Into<Cow<'static, [HashSignature]>>
 	{
-		Table { inner: inner.into(), fallback: Some(fallback) }
+		let inner = inner.into();
+		let sorted = is_sorted(&inner);
+		Table { inner: inner, fallback: Some(fallback), sorted: sorted }
+	}
+
+	/// Table borrowing a slice built at compile time, as the derive does (with selectors already
+	/// computed), so that creating it neither hashes nor allocates
+	///
+	/// # Panics
+	///
+	/// If two signatures have the same selector, which fails compilation when the table is a constant.
+	pub const fn from_static(inner: &'static [HashSignature]) -> Self {
+		assert!(!has_duplicates_const(inner), "two signatures of the dispatch table have the same selector");
+		Table { inner: Cow::Borrowed(inner), fallback: None, sorted: is_sorted_const(inner) }
+	}
+
+	pub const fn from_static_with_fallback(inner: &'static [HashSignature], fallback: Signature) -> Self {
+		assert!(!has_duplicates_const(inner), "two signatures of the dispatch table have the same selector");
+		Table { inner: Cow::Borrowed(inner), fallback: Some(fallback), sorted: is_sorted_const(inner) }
 	}
 
+	/// Appends signature, keeping the table ordered if it was sorted
 	pub fn push<S>(&mut self, signature: S)
 		where S: Into<HashSignature>
 	{
-		self.inner.to_mut().push(signature.into())
+		let signature = signature.into();
+		if self.inner.is_empty() {
+			self.sorted = true;
+		}
+
+		if self.sorted {
+			let position = match self.inner.binary_search_by_key(&signature.hash, |x| x.hash) {
+				Ok(position) | Err(position) => position,
+			};
+			self.inner.to_mut().insert(position, signature);
+		} else {
+			self.inner.to_mut().push(signature);
+		}
+	}
+
+	/// Appends signatures of `other`, taking its fallback if this table has none
+	///
+	/// Fails without changing the table if a selector of `other` is already in it.
+	pub fn merge(&mut self, other: Table) -> Result<(), Error> {
+		if let Some(duplicate) = other.inner.iter().find(|s| self.hash_signature(s.hash).is_ok()) {
+			return Err(Error::DuplicateSelector { hash: duplicate.hash });
+		}
+
+		for signature in other.inner.into_owned() {
+			self.push(signature);
+		}
+		if self.fallback.is_none() {
+			self.fallback = other.fallback;
+		}
+		Ok(())
+	}
+
+	/// Same as `push`, but fails if the table already has a signature with the same selector
+	/// (which would make one of them unreachable)
+	pub fn try_push<S>(&mut self, signature: S) -> Result<(), Error>
+		where S: Into<HashSignature>
+	{
+		let signature = signature.into();
+		if self.hash_signature(signature.hash).is_ok() {
+			return Err(Error::DuplicateSelector { hash: signature.hash });
+		}
+		self.push(signature);
+		Ok(())
 	}
 
-	pub fn dispatch<D>(&self, payload: &[u8], mut d: D) -> Result<Vec<u8>, Error>
+	pub fn dispatch<D>(&self, payload: &[u8], d: D) -> Result<Vec<u8>, Error>
 		where D: FnMut(u32, Vec<ValueType>) -> Option<ValueType>
 	{
 		if payload.len() < 4 { return Err(Error::NoLengthForSignature); }
 		let method_id = BigEndian::read_u32(&payload[0..4]);
 
-		let hash_signature = self.hash_signature(method_id)?;
+		self.hash_signature(method_id)?.dispatch(method_id, &payload[4..], d)
+	}
 
-		let args = hash_signature.signature.decode_invoke(&payload[4..]);
-		let result = d(method_id, args);
+	/// Decodes call payload without dispatching it, returning method id with its arguments
+	///
+	/// Unlike `dispatch`, arguments that don't match the signature are reported as an error.
+	pub fn decode_input(&self, payload: &[u8]) -> Result<(u32, Vec<ValueType>), Error> {
+		if payload.len() < 4 { return Err(Error::NoLengthForSignature); }
+		let method_id = BigEndian::read_u32(&payload[0..4]);
+
+		let hash_signature = self.hash_signature(method_id)?;
+		let args = decode(hash_signature.signature.params(), &payload[4..])?;
 
-		Ok(hash_signature.signature.encode_result(result)?)
+		Ok((method_id, args))
 	}
 
 	/// Fallback/constructor dispatch cannot return anything
@@ -85,37 +167,219 @@ impl Table {
 		}
 	}
 
+	/// Fallback dispatch for proxy contracts
+	///
+	/// Unlike `fallback_dispatch`, the raw payload is handed over undecoded and whatever
+	/// the handler returns (e.g. output of the delegated call) is forwarded to the caller.
+	pub fn proxy_fallback_dispatch<D>(&self, payload: &[u8], mut d: D) -> Vec<u8>
+		where D: FnMut(&[u8]) -> Vec<u8>
+	{
+		d(payload)
+	}
+
+	/// Signature of the constructor (the anonymous fallback), if any
+	pub fn constructor_signature(&self) -> Option<&Signature> {
+		self.fallback.as_ref()
+	}
+
+	/// Constructor is invoked without selector, so no selector ever routes to it
+	pub fn is_constructor_selector(&self, _method_id: u32) -> bool {
+		false
+	}
+
+	/// Whether `inner` is ordered by hash, which lookups use binary search for
+	pub fn is_sorted(&self) -> bool {
+		self.sorted
+	}
+
 	pub fn hash_signature(&self, method_id: u32) -> Result<&HashSignature, Error> {
-		self.inner.iter().find(|x| x.hash == method_id).ok_or(Error::UnknownSignature)
+		if self.sorted {
+			self.inner.binary_search_by_key(&method_id, |x| x.hash)
+				.map(|position| &self.inner[position])
+				.map_err(|_| Error::UnknownSignature)
+		} else {
+			self.inner.iter().find(|x| x.hash == method_id).ok_or(Error::UnknownSignature)
+		}
 	}
 
-	pub fn call<D>(&self, hash: u32, args: &[ValueType], mut d: D)
+	/// Selectors of the table along with their signatures, in the order of `inner`
+	///
+	/// The fallback is not included, as it has no selector (see `constructor_signature`).
+	pub fn iter(&self) -> impl Iterator<Item = (u32, &Signature)> {
+		self.inner.iter().map(|s| (s.hash, &s.signature))
+	}
+
+	/// Calls method `hash` with `args` through `d`, which performs the call and returns its output
+	/// (`None` if there is none to decode)
+	///
+	/// Fails with `ArgumentMismatch` without calling `d` if `args` don't match the params.
+	pub fn call<D>(&self, hash: u32, args: &[ValueType], d: D)
 		-> Result<Option<ValueType>, Error>
-		where D: FnMut(Vec<u8>) -> Option<[u8; 32]>
+		where D: FnMut(Vec<u8>) -> Option<Vec<u8>>
 	{
-		let hash_signature = self.hash_signature(hash)?;
-		let args_payload = hash_signature.signature.encode_invoke(args);
-		let mut payload = Vec::with_capacity(args_payload.len() + 4);
-		let mut encoded_signature = [0u8; 4];
-		BigEndian::write_u32(&mut encoded_signature, hash);
-		payload.extend_from_slice(&encoded_signature);
-		payload.extend(args_payload);
+		self.call_with(hash, args, d)
+	}
 
-		let result = d(payload);
-		Ok(match result {
-			Some(ref result_slice) => hash_signature.signature.decode_result(&result_slice[..])?,
-			None => None,
-		})
+	/// Same as `call`, for `d` returning its output in any buffer (e.g. a fixed size array)
+	pub fn call_with<D, R>(&self, hash: u32, args: &[ValueType], d: D)
+		-> Result<Option<ValueType>, Error>
+		where D: FnMut(Vec<u8>) -> Option<R>, R: AsRef<[u8]>
+	{
+		self.hash_signature(hash)?.call(args, d)
+	}
+
+	/// Serializes selectors of the table into a compact map, routing each to the target index
+	/// returned by `target` (e.g. index of the implementation contract a proxy delegates to)
+	///
+	/// Entries are `selector (4 bytes, big endian) || target index (1 byte)`, sorted by selector,
+	/// so the map can be stored as is in contract storage and read back with `lookup_selector`.
+	pub fn selector_map<F>(&self, mut target: F) -> Vec<u8>
+		where F: FnMut(&HashSignature) -> u8
+	{
+		let mut entries: Vec<(u32, u8)> = self.inner.iter().map(|s| (s.hash, target(s))).collect();
+		entries.sort_by_key(|&(selector, _)| selector);
+
+		let mut map = vec![0u8; entries.len() * SELECTOR_MAP_ENTRY_LEN];
+		for (entry, &(selector, index)) in map.chunks_mut(SELECTOR_MAP_ENTRY_LEN).zip(entries.iter()) {
+			BigEndian::write_u32(&mut entry[0..4], selector);
+			entry[4] = index;
+		}
+		map
+	}
+}
+
+/// Dispatch table backed by a hash map, for interfaces too large for the linear (or binary)
+/// search of `Table`
+#[cfg(feature = "std")]
+#[derive(Default)]
+pub struct FastTable {
+	inner: HashMap<u32, HashSignature>,
+
+	// anonymous signature/constructor
+	pub fallback: Option<Signature>,
+}
+
+#[cfg(feature = "std")]
+impl FastTable {
+	pub fn new<I, S>(signatures: I) -> Self
+		where I: IntoIterator<Item=S>, S: Into<HashSignature>
+	{
+		let mut table = FastTable::default();
+		for signature in signatures {
+			table.push(signature);
+		}
+		table
+	}
+
+	/// Adds signature, replacing the one with the same hash if any
+	pub fn push<S>(&mut self, signature: S)
+		where S: Into<HashSignature>
+	{
+		let signature = signature.into();
+		self.inner.insert(signature.hash, signature);
+	}
+
+	pub fn len(&self) -> usize {
+		self.inner.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.inner.is_empty()
+	}
+
+	pub fn dispatch<D>(&self, payload: &[u8], d: D) -> Result<Vec<u8>, Error>
+		where D: FnMut(u32, Vec<ValueType>) -> Option<ValueType>
+	{
+		if payload.len() < 4 { return Err(Error::NoLengthForSignature); }
+		let method_id = BigEndian::read_u32(&payload[0..4]);
+
+		self.hash_signature(method_id)?.dispatch(method_id, &payload[4..], d)
+	}
+
+	pub fn hash_signature(&self, method_id: u32) -> Result<&HashSignature, Error> {
+		self.inner.get(&method_id).ok_or(Error::UnknownSignature)
+	}
+
+	pub fn call<D>(&self, hash: u32, args: &[ValueType], d: D)
+		-> Result<Option<ValueType>, Error>
+		where D: FnMut(Vec<u8>) -> Option<Vec<u8>>
+	{
+		self.call_with(hash, args, d)
+	}
+
+	pub fn call_with<D, R>(&self, hash: u32, args: &[ValueType], d: D)
+		-> Result<Option<ValueType>, Error>
+		where D: FnMut(Vec<u8>) -> Option<R>, R: AsRef<[u8]>
+	{
+		self.hash_signature(hash)?.call(args, d)
+	}
+}
+
+#[cfg(feature = "std")]
+impl<'a> From<&'a Table> for FastTable {
+	fn from(table: &'a Table) -> FastTable {
+		let mut fast = FastTable::new(table.inner.iter().cloned());
+		fast.fallback = table.fallback.clone();
+		fast
+	}
+}
+
+/// Target index of `selector` in a map serialized by `Table::selector_map`, `None` if it's not there
+pub fn lookup_selector(map: &[u8], selector: u32) -> Option<u8> {
+	let entries = map.len() / SELECTOR_MAP_ENTRY_LEN;
+	let (mut low, mut high) = (0, entries);
+	while low < high {
+		let middle = (low + high) / 2;
+		let entry = &map[middle * SELECTOR_MAP_ENTRY_LEN..(middle + 1) * SELECTOR_MAP_ENTRY_LEN];
+		match BigEndian::read_u32(&entry[0..4]).cmp(&selector) {
+			cmp::Ordering::Equal => return Some(entry[4]),
+			cmp::Ordering::Less => low = middle + 1,
+			cmp::Ordering::Greater => high = middle,
+		}
+	}
+	None
+}
+
+fn is_sorted(signatures: &[HashSignature]) -> bool {
+	signatures.windows(2).all(|w| w[0].hash <= w[1].hash)
+}
+
+const fn is_sorted_const(signatures: &[HashSignature]) -> bool {
+	let mut i = 1;
+	while i < signatures.len() {
+		if signatures[i - 1].hash > signatures[i].hash {
+			return false;
+		}
+		i += 1;
+	}
+	true
+}
+
+const fn has_duplicates_const(signatures: &[HashSignature]) -> bool {
+	let mut i = 0;
+	while i < signatures.len() {
+		let mut j = i + 1;
+		while j < signatures.len() {
+			if signatures[i].hash == signatures[j].hash {
+				return true;
+			}
+			j += 1;
+		}
+		i += 1;
 	}
+	false
 }
 
 impl NamedSignature {
 	pub fn new<T>(name: T, signature: Signature) -> Self
 		where T: Into<Cow<'static, str>>
 	{
+		let name = name.into();
+		let hash = keccak256(signature_string(&name, signature.params()).as_bytes());
 		NamedSignature {
-			name: name.into(),
+			name: name,
 			signature: signature,
+			hash: hash,
 		}
 	}
 
@@ -127,21 +391,164 @@ impl NamedSignature {
 		&self.signature
 	}
 
+	/// Canonical form the selector is computed from, e.g. `transfer(address,uint256)`
+	pub fn canonical(&self) -> String {
+		signature_string(&self.name, self.signature.params())
+	}
+
+	/// `keccak256` of the canonical form, the selector is its first 4 bytes
+	///
+	/// Computed when the signature is created, calls (and clones) reuse the digest.
 	pub fn hash(&self) -> H256 {
-		let mut signature_str = self.name.to_string();
-		signature_str.push('(');
-		for (i, p) in self.signature.params().iter().enumerate() {
-			p.to_member(&mut signature_str);
-			if i != self.signature.params().len()-1 { signature_str.push(','); }
+		self.hash
+	}
+}
+
+impl str::FromStr for NamedSignature {
+	type Err = Error;
+
+	/// Parses a canonical signature like `transfer(address,uint256)`, see `Signature::from_str`
+	fn from_str(s: &str) -> Result<NamedSignature, Error> {
+		let signature = s.parse()?;
+		let name = &s[..s.find('(').expect("parsed signature has params")];
+		if name.is_empty() {
+			return Err(Error::InvalidSignature);
 		}
-		signature_str.push(')');
+		Ok(NamedSignature::new(name.to_owned(), signature))
+	}
+}
 
-		let mut keccak = Keccak::new_keccak256();
-		let mut res = H256::zero();
-		keccak.update(signature_str.as_bytes());
-		keccak.finalize(res.as_mut());
-		res
+/// Canonical `name(type,type)` form of the signature, the selector is computed from
+pub fn signature_string<'a, P>(name: &str, params: P) -> String
+	where P: IntoIterator<Item = &'a ParamType>
+{
+	let mut signature_str = name.to_string();
+	signature_str.push('(');
+	for (i, p) in params.into_iter().enumerate() {
+		if i != 0 { signature_str.push(','); }
+		p.to_member(&mut signature_str);
 	}
+	signature_str.push(')');
+	signature_str
+}
+
+/// Selector of the method `name` taking `params`, without building a `Signature`
+pub fn method_selector(name: &str, params: &[ParamType]) -> [u8; 4] {
+	let mut selector = [0u8; 4];
+	BigEndian::write_u32(&mut selector, compute_selector(&signature_string(name, params)));
+	selector
+}
+
+/// Selector of the function with the given canonical signature, e.g. `transfer(address,uint256)`
+///
+/// Manual counterpart of `NamedSignature::hash`, handy to check generated selectors in tests.
+pub fn compute_selector(signature: &str) -> u32 {
+	BigEndian::read_u32(&keccak256(signature.as_bytes())[0..4])
+}
+
+/// Longest canonical signature `const_selector` can hash
+const MAX_CONST_SIGNATURE_LEN: usize = 1024;
+
+/// Canonical signature built without allocating, mirrors `signature_string`
+struct SignatureBuffer {
+	bytes: [u8; MAX_CONST_SIGNATURE_LEN],
+	len: usize,
+}
+
+impl SignatureBuffer {
+	const fn push(&mut self, byte: u8) {
+		assert!(self.len < MAX_CONST_SIGNATURE_LEN, "signature is too long to be hashed in a constant");
+		self.bytes[self.len] = byte;
+		self.len += 1;
+	}
+
+	const fn push_str(&mut self, s: &str) {
+		let bytes = s.as_bytes();
+		let mut i = 0;
+		while i < bytes.len() {
+			self.push(bytes[i]);
+			i += 1;
+		}
+	}
+
+	const fn push_number(&mut self, mut n: usize) {
+		let mut digits = [0u8; 20];
+		let mut count = 0;
+		loop {
+			digits[count] = b'0' + (n % 10) as u8;
+			count += 1;
+			n /= 10;
+			if n == 0 { break; }
+		}
+		while count > 0 {
+			count -= 1;
+			self.push(digits[count]);
+		}
+	}
+
+	const fn push_members(&mut self, params: &[ParamType]) {
+		let mut i = 0;
+		while i < params.len() {
+			if i != 0 { self.push(b','); }
+			self.push_member(&params[i]);
+			i += 1;
+		}
+	}
+
+	const fn push_member(&mut self, param: &ParamType) {
+		match *param {
+			ParamType::I32 => self.push_str("int32"),
+			ParamType::U32 => self.push_str("uint32"),
+			ParamType::I64 => self.push_str("int64"),
+			ParamType::U64 => self.push_str("uint64"),
+			ParamType::Address => self.push_str("address"),
+			ParamType::U256 | ParamType::H256 => self.push_str("uint256"),
+			ParamType::Uint(bits) => { self.push_str("uint"); self.push_number(bits); },
+			ParamType::Int(bits) => { self.push_str("int"); self.push_number(bits); },
+			ParamType::Bytes => self.push_str("bytes"),
+			ParamType::Bool => self.push_str("bool"),
+			ParamType::String => self.push_str("string"),
+			ParamType::FixedBytes(len) => { self.push_str("bytes"); self.push_number(len); },
+			ParamType::Array(ref p_n) => { self.push_member(array_ref(p_n)); self.push_str("[]"); },
+			ParamType::FixedArray(ref p_n, len) => {
+				self.push_member(array_ref(p_n));
+				self.push(b'[');
+				self.push_number(len);
+				self.push(b']');
+			},
+			ParamType::Tuple(ref members) => {
+				self.push(b'(');
+				match *members {
+					Cow::Borrowed(members) => self.push_members(members),
+					Cow::Owned(ref members) => self.push_members(members.as_slice()),
+				}
+				self.push(b')');
+			},
+		}
+	}
+}
+
+const fn array_ref(array_ref: &ArrayRef) -> &ParamType {
+	match *array_ref {
+		ArrayRef::Owned(ref p) => p,
+		ArrayRef::Static(p) => p,
+	}
+}
+
+/// Same as `method_selector`, as a big-endian `u32`, but usable in constants
+///
+/// Lets generated code compute selectors of methods taking types only known once the contract
+/// is compiled, like structs deriving `AbiType`.
+pub const fn const_selector(name: &str, params: &[ParamType]) -> u32 {
+	let mut signature = SignatureBuffer { bytes: [0u8; MAX_CONST_SIGNATURE_LEN], len: 0 };
+	signature.push_str(name);
+	signature.push(b'(');
+	signature.push_members(params);
+	signature.push(b')');
+
+	let (signature, _) = signature.bytes.split_at(signature.len);
+	let hash = keccak::keccak256(signature);
+	u32::from_be_bytes([hash[0], hash[1], hash[2], hash[3]])
 }
 
 impl HashSignature {
@@ -159,6 +566,36 @@ impl HashSignature {
 	pub fn signature(&self) -> &Signature {
 		&self.signature
 	}
+
+	/// Decodes arguments of the call to `method_id` and encodes what `d` returns for them
+	fn dispatch<D>(&self, method_id: u32, args_payload: &[u8], mut d: D) -> Result<Vec<u8>, Error>
+		where D: FnMut(u32, Vec<ValueType>) -> Option<ValueType>
+	{
+		let args = decode(self.signature.params(), args_payload)?;
+		let result = d(method_id, args);
+
+		Ok(self.signature.encode_result(result)?)
+	}
+
+	/// Encodes the call with `args`, decoding the result `d` returns for the payload
+	fn call<D, R>(&self, args: &[ValueType], mut d: D) -> Result<Option<ValueType>, Error>
+		where D: FnMut(Vec<u8>) -> Option<R>, R: AsRef<[u8]>
+	{
+		self.signature.matches(args)?;
+
+		// sized for the head upfront, dynamic args only grow it by their tails
+		let mut payload = Vec::with_capacity(4 + self.signature.static_size());
+		let mut encoded_signature = [0u8; 4];
+		BigEndian::write_u32(&mut encoded_signature, self.hash);
+		payload.extend_from_slice(&encoded_signature);
+		payload.extend(self.signature.encode_invoke(args));
+
+		let result = d(payload);
+		Ok(match result {
+			Some(ref result_slice) => self.signature.decode_result(result_slice.as_ref())?,
+			None => None,
+		})
+	}
 }
 
 #[test]
@@ -166,10 +603,7 @@ fn match_signature() {
 
 	use super::ParamType;
 
-	let named = NamedSignature {
-		name: Cow::Borrowed("baz"),
-		signature: Signature::new_void(vec![ParamType::U32, ParamType::Bool]),
-	};
+	let named = NamedSignature::new("baz", Signature::new_void(vec![ParamType::U32, ParamType::Bool]));
 
 	let hashed: HashSignature = named.into();
 
@@ -181,16 +615,90 @@ fn match_signature_2() {
 
 	use super::ParamType;
 
-	let named = NamedSignature {
-		name: Cow::Borrowed("sam"),
-		signature: Signature::new_void(vec![ParamType::Bytes, ParamType::Bool, ParamType::Array(ParamType::U256.into())]),
-	};
+	let named = NamedSignature::new("sam", Signature::new_void(vec![ParamType::Bytes, ParamType::Bool, ParamType::Array(ParamType::U256.into())]));
 
 	let hashed: HashSignature = named.into();
 
 	assert_eq!(hashed.hash, 0xa5643bf2);
 }
 
+#[test]
+fn signature_hash_is_computed_once() {
+	let named = NamedSignature::new("baz", Signature::new_void(vec![ParamType::U32, ParamType::Bool]));
+	assert!(named.hash == keccak256(b"baz(uint32,bool)"));
+
+	let hash = named.hash();
+	assert!(named.clone().hash == hash);
+	assert_eq!(HashSignature::from(named).hash, 0xcdcd77c0);
+}
+
+#[test]
+fn signatures_are_sync() {
+	fn assert_sync<T: Sync>() {}
+
+	assert_sync::<NamedSignature>();
+	assert_sync::<Table>();
+}
+
+#[test]
+fn merge_tables() {
+	let signature = |name: &str| -> HashSignature {
+		NamedSignature::new(name.to_owned(), Signature::new_void(vec![ParamType::U32])).into()
+	};
+
+	let mut token = Table::new(vec![signature("transfer"), signature("approve")]);
+	let admin = Table::with_fallback(vec![signature("pause")], Signature::new_void(vec![ParamType::Address]));
+	token.merge(admin).unwrap();
+
+	assert_eq!(token.inner.len(), 3);
+	assert!(token.hash_signature(signature("pause").hash).is_ok());
+	assert_eq!(token.fallback.as_ref().unwrap().params(), &[ParamType::Address]);
+
+	// fallback of the table merged into is kept
+	token.merge(Table::with_fallback(vec![signature("unpause")], Signature::new_void(vec![]))).unwrap();
+	assert_eq!(token.fallback.as_ref().unwrap().params(), &[ParamType::Address]);
+
+	let duplicate = Table::new(vec![signature("mint"), signature("approve")]);
+	assert_eq!(token.merge(duplicate), Err(Error::DuplicateSelector { hash: signature("approve").hash }));
+	assert_eq!(token.inner.len(), 4);
+}
+
+#[test]
+fn push_colliding_selectors() {
+	let burn: HashSignature = NamedSignature::new("burn", Signature::new_void(vec![ParamType::U256])).into();
+	// same selector 0x42966c68 as `burn(uint256)`
+	let collate: HashSignature = NamedSignature::new("collate_propagate_storage", Signature::new_void(vec![ParamType::FixedBytes(16)])).into();
+	assert_eq!(burn.hash, collate.hash);
+
+	let mut table = Table::default();
+	table.try_push(burn).unwrap();
+	assert_eq!(table.try_push(collate), Err(Error::DuplicateSelector { hash: 0x42966c68 }));
+	assert_eq!(table.inner.len(), 1);
+	assert_eq!(table.hash_signature(0x42966c68).unwrap().signature().params(), &[ParamType::U256]);
+}
+
+#[test]
+#[should_panic(expected = "same selector")]
+fn static_table_with_colliding_selectors() {
+	static SIGNATURES: [HashSignature; 2] = [
+		HashSignature { hash: 0x42966c68, signature: Signature { params: Cow::Borrowed(&[ParamType::U256]), result: None, multiple_results: false } },
+		HashSignature { hash: 0x42966c68, signature: Signature { params: Cow::Borrowed(&[ParamType::FixedBytes(16)]), result: None, multiple_results: false } },
+	];
+	Table::from_static(&SIGNATURES);
+}
+
+#[test]
+fn parse_named_signature() {
+	let named: NamedSignature = "transfer(address,uint256)".parse().unwrap();
+	assert_eq!(named.name(), "transfer");
+	assert_eq!(named.canonical(), "transfer(address,uint256)");
+
+	let hashed: HashSignature = named.into();
+	assert_eq!(hashed.hash, 0xa9059cbb);
+
+	assert_eq!("(address)".parse::<NamedSignature>().err(), Some(Error::InvalidSignature));
+}
+
 #[test]
 fn table() {
 
@@ -199,17 +707,11 @@ fn table() {
 	let mut table = Table::default();
 
 	table.push(
-		NamedSignature {
-			name: Cow::Borrowed("baz"),
-			signature: Signature::new_void(vec![ParamType::U32, ParamType::Bool]),
-		}
+		NamedSignature::new("baz", Signature::new_void(vec![ParamType::U32, ParamType::Bool]))
 	);
 
 	table.push(
-		NamedSignature {
-			name: Cow::Borrowed("sam"),
-			signature: Signature::new_void(vec![ParamType::Bytes, ParamType::Bool, ParamType::Array(ParamType::U256.into())]),
-		}
+		NamedSignature::new("sam", Signature::new_void(vec![ParamType::Bytes, ParamType::Bool, ParamType::Array(ParamType::U256.into())]))
 	);
 
 	table.dispatch(
@@ -254,3 +756,367 @@ fn table() {
 		}
 	).expect("dispatch failed");
 }
+
+#[test]
+fn sorted_table() {
+
+	use super::ParamType;
+
+	let unsorted = Table::new(vec![
+		HashSignature::new(3, Signature::new_void(vec![])),
+		HashSignature::new(1, Signature::new_void(vec![ParamType::Bool])),
+	]);
+	assert!(!unsorted.is_sorted());
+	assert_eq!(unsorted.hash_signature(1).unwrap().signature().params().len(), 1);
+
+	let mut table = Table::default();
+	table.push(HashSignature::new(3, Signature::new_void(vec![])));
+	table.push(HashSignature::new(1, Signature::new_void(vec![ParamType::Bool])));
+	table.push(HashSignature::new(2, Signature::new_void(vec![ParamType::U32, ParamType::U32])));
+
+	assert!(table.is_sorted());
+	assert_eq!(table.inner.iter().map(|x| x.hash).collect::<Vec<_>>(), vec![1, 2, 3]);
+	assert_eq!(table.hash_signature(1).unwrap().signature().params().len(), 1);
+	assert_eq!(table.hash_signature(2).unwrap().signature().params().len(), 2);
+	assert_eq!(table.hash_signature(3).unwrap().signature().params().len(), 0);
+	assert_eq!(table.hash_signature(4).err(), Some(Error::UnknownSignature));
+}
+
+#[test]
+fn unit_result() {
+
+	use super::ParamType;
+
+	let mut table = Table::default();
+	let signature = Signature::new_void(vec![ParamType::Bool]);
+	table.push(HashSignature::new(1, signature.clone()));
+
+	let result = table.dispatch(
+		&[
+			0x00, 0x00, 0x00, 0x01,
+			0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01
+		],
+		|_, _| Some(ValueType::Unit)
+	).expect("dispatch failed");
+	assert!(result.is_empty());
+
+	assert_eq!(signature.decode_result(&[]), Ok(None));
+	assert_eq!(
+		Signature::new(vec![], Some(ParamType::Bool)).encode_result(Some(ValueType::Unit)),
+		Err(Error::ResultCantFit)
+	);
+	assert_eq!(Signature::new(vec![], Some(ParamType::Bool)).encode_result(None), Err(Error::ResultCantFit));
+}
+
+#[test]
+fn decode_input() {
+
+	use super::ParamType;
+	use super::util::pad_u32;
+
+	let mut table = Table::default();
+	table.push(
+		NamedSignature::new("transfer", Signature::new(vec![ParamType::Address, ParamType::U256], Some(ParamType::Bool)))
+	);
+
+	let mut payload = vec![0xa9, 0x05, 0x9c, 0xbb];
+	payload.extend_from_slice(&[0u8; 12]);
+	payload.extend_from_slice(&[0x11u8; 20]);
+	payload.extend_from_slice(&pad_u32(1000));
+
+	let (method_id, values) = table.decode_input(&payload).expect("decode failed");
+	assert_eq!(method_id, 0xa9059cbb);
+	assert_eq!(values, vec![ValueType::Address([0x11u8; 20]), ValueType::U256(pad_u32(1000))]);
+
+	assert_eq!(table.decode_input(&payload[..36]).err(), Some(Error::UnexpectedEnd { position: 64 }));
+	assert_eq!(table.decode_input(&[0x12, 0x34, 0x56, 0x78]).err(), Some(Error::UnknownSignature));
+	assert_eq!(table.decode_input(&[0xa9]).err(), Some(Error::NoLengthForSignature));
+}
+
+#[test]
+fn proxy_fallback() {
+	let table = Table::default();
+
+	let result = table.proxy_fallback_dispatch(&[0x12, 0x34, 0x56, 0x78, 0x01], |payload| {
+		assert_eq!(payload, &[0x12, 0x34, 0x56, 0x78, 0x01]);
+		payload.iter().map(|b| b.wrapping_add(1)).collect()
+	});
+
+	assert_eq!(result, vec![0x13, 0x35, 0x57, 0x79, 0x02]);
+}
+
+#[test]
+fn compute_known_selectors() {
+	assert_eq!(compute_selector("transfer(address,uint256)"), 0xa9059cbb);
+	assert_eq!(compute_selector("balanceOf(address)"), 0x70a08231);
+	assert_eq!(compute_selector("totalSupply()"), 0x18160ddd);
+	assert_eq!(compute_selector("safeTransferFrom(address,address,uint256,bytes)"), 0xb88d4fde);
+	assert_eq!(compute_selector("Error(string)"), super::ERROR_SELECTOR);
+
+	let named = NamedSignature::new("baz", Signature::new_void(vec![super::ParamType::U32, super::ParamType::Bool]));
+	assert_eq!(compute_selector("baz(uint32,bool)"), HashSignature::from(named).hash());
+}
+
+#[test]
+fn known_selectors() {
+
+	use super::ParamType::*;
+	use super::ParamType;
+
+	fn array(p: ParamType) -> ParamType {
+		Array(p.into())
+	}
+
+	let vectors: Vec<(&'static str, Vec<ParamType>, u32)> = vec![
+		// ERC20
+		("totalSupply", vec![], 0x18160ddd),
+		("balanceOf", vec![Address], 0x70a08231),
+		("transfer", vec![Address, U256], 0xa9059cbb),
+		("transferFrom", vec![Address, Address, U256], 0x23b872dd),
+		("approve", vec![Address, U256], 0x095ea7b3),
+		("allowance", vec![Address, Address], 0xdd62ed3e),
+		("name", vec![], 0x06fdde03),
+		("symbol", vec![], 0x95d89b41),
+		("decimals", vec![], 0x313ce567),
+		// ERC721
+		("ownerOf", vec![U256], 0x6352211e),
+		("safeTransferFrom", vec![Address, Address, U256], 0x42842e0e),
+		("safeTransferFrom", vec![Address, Address, U256, Bytes], 0xb88d4fde),
+		("setApprovalForAll", vec![Address, Bool], 0xa22cb465),
+		("isApprovedForAll", vec![Address, Address], 0xe985e9c5),
+		("getApproved", vec![U256], 0x081812fc),
+		("tokenURI", vec![U256], 0xc87b56dd),
+		("tokenOfOwnerByIndex", vec![Address, U256], 0x2f745c59),
+		("tokenByIndex", vec![U256], 0x4f6ccce7),
+		// ERC1155
+		("safeBatchTransferFrom", vec![Address, Address, array(U256), array(U256), Bytes], 0x2eb2c2d6),
+		("balanceOfBatch", vec![array(Address), array(U256)], 0x4e1273f4),
+		("uri", vec![U256], 0x0e89341c),
+		// WETH
+		("deposit", vec![], 0xd0e30db0),
+		("withdraw", vec![U256], 0x2e1a7d4d),
+		// Uniswap V2 pair
+		("getReserves", vec![], 0x0902f1ac),
+		("swap", vec![U256, U256, Address, Bytes], 0x022c0d9f),
+		("sync", vec![], 0xfff6cae9),
+		("skim", vec![Address], 0xbc25cf77),
+		("mint", vec![Address], 0x6a627842),
+		("burn", vec![Address], 0x89afcb44),
+		// Uniswap V2 router
+		("swapExactTokensForTokens", vec![U256, U256, array(Address), Address, U256], 0x38ed1739),
+		("swapExactETHForTokens", vec![U256, array(Address), Address, U256], 0x7ff36ab5),
+		("getAmountsOut", vec![U256, array(Address)], 0xd06ca61f),
+		("addLiquidity", vec![Address, Address, U256, U256, U256, U256, Address, U256], 0xe8e33700),
+		("removeLiquidity", vec![Address, Address, U256, U256, U256, Address, U256], 0xbaa2abde),
+		// Ownable
+		("owner", vec![], 0x8da5cb5b),
+		("transferOwnership", vec![Address], 0xf2fde38b),
+		// wasm integer types and nested arrays
+		("setValues", vec![U32, U64, I32, I64, String], 0x449286e0),
+		("test", vec![array(array(U256))], 0xbf062876),
+		("f", vec![array(array(Address)), array(String)], 0x4252f1a0),
+		// tuples and fixed arrays
+		("submit", vec![FixedArray(Tuple(vec![U256, Bool].into()).into(), 2)], 0xa2a27881),
+		("set", vec![FixedArray(U256.into(), 3), Tuple(vec![Address, String].into())], 0xf86ecc67),
+	];
+
+	for (name, params, expected) in vectors {
+		let hashed: HashSignature = NamedSignature::new(name, Signature::new_void(params)).into();
+		assert_eq!(hashed.hash(), expected, "wrong selector for {}", name);
+	}
+}
+
+#[test]
+fn call_decodes_output() {
+	let mut table = Table::default();
+	table.push(HashSignature::new(1, Signature::new(vec![ParamType::U32], Some(ParamType::U32))));
+	table.push(HashSignature::new(2, Signature::new_void(vec![])));
+
+	let result = table.call(1, &[ValueType::U32(5)], |payload| {
+		assert_eq!(&payload[0..4], &[0, 0, 0, 1]);
+		Some(payload[4..].to_vec())
+	});
+	assert_eq!(result, Ok(Some(ValueType::U32(5))));
+
+	// callee returning nothing is a decode error rather than a missing value
+	assert_eq!(table.call(1, &[ValueType::U32(5)], |_| Some(Vec::new())), Err(Error::UnexpectedEnd { position: 32 }));
+	assert_eq!(table.call(2, &[], |_| Some(Vec::new())), Ok(None));
+}
+
+#[test]
+fn call_with_word_output() {
+	let mut table = Table::default();
+	table.push(HashSignature::new(1, Signature::new(vec![], Some(ParamType::Bool))));
+
+	let mut word = [0u8; 32];
+	word[31] = 1;
+	assert_eq!(table.call_with(1, &[], |_| Some(word)), Ok(Some(ValueType::Bool(true))));
+}
+
+#[test]
+fn call_rejects_mismatched_args() {
+	let mut table = Table::default();
+	table.push(HashSignature::new(1, Signature::new_void(vec![ParamType::Address, ParamType::U256])));
+
+	let mut called = false;
+	assert_eq!(
+		table.call(1, &[ValueType::Address([0x11u8; 20])], |_| { called = true; None }),
+		Err(Error::ArgumentMismatch { index: 1, expected: Some(ParamType::U256), got: None })
+	);
+	assert_eq!(
+		table.call(1, &[ValueType::Address([0x11u8; 20]), ValueType::Bool(true)], |_| { called = true; None }),
+		Err(Error::ArgumentMismatch { index: 1, expected: Some(ParamType::U256), got: Some(ParamType::Bool) })
+	);
+	assert!(!called, "no call should be made with mismatched args");
+
+	// hashes and numbers are both `uint256` words
+	assert_eq!(table.call(1, &[ValueType::Address([0x11u8; 20]), ValueType::H256([0u8; 32])], |_| None), Ok(None));
+}
+
+#[test]
+fn static_table() {
+	static SIGNATURES: [HashSignature; 2] = [
+		HashSignature { hash: 1, signature: Signature { params: Cow::Borrowed(&[ParamType::U32]), result: Some(ParamType::U32), multiple_results: false } },
+		HashSignature { hash: 2, signature: Signature { params: Cow::Borrowed(&[]), result: None, multiple_results: false } },
+	];
+	const TABLE: &'static Table = &Table::from_static(&SIGNATURES);
+
+	assert!(TABLE.is_sorted());
+	assert!(TABLE.fallback.is_none());
+
+	let mut payload = vec![0, 0, 0, 1];
+	payload.extend_from_slice(&[0u8; 31]);
+	payload.push(7);
+	let result = TABLE.dispatch(&payload, |method_id, mut args| {
+		assert_eq!(method_id, 1);
+		args.pop()
+	}).unwrap();
+	assert_eq!(result[31], 7);
+
+	let with_fallback = Table::from_static_with_fallback(&[], Signature::new_void(vec![ParamType::Bool]));
+	assert!(with_fallback.is_sorted());
+	assert_eq!(with_fallback.constructor_signature().map(|s| s.params().len()), Some(1));
+}
+
+#[test]
+fn selector_map_round_trip() {
+	use super::ParamType;
+
+	let mut table = Table::default();
+	// pushed out of selector order, `transfer` and `approve` live in the second implementation
+	table.push(NamedSignature::new("transfer", Signature::new(vec![ParamType::Address, ParamType::U256], Some(ParamType::Bool))));
+	table.push(NamedSignature::new("balanceOf", Signature::new(vec![ParamType::Address], Some(ParamType::U256))));
+	table.push(NamedSignature::new("approve", Signature::new(vec![ParamType::Address, ParamType::U256], Some(ParamType::Bool))));
+	table.push(NamedSignature::new("totalSupply", Signature::new(vec![], Some(ParamType::U256))));
+
+	let map = table.selector_map(|s| match s.hash() {
+		0xa9059cbb | 0x095ea7b3 => 1,
+		_ => 0,
+	});
+
+	assert_eq!(map, vec![
+		0x09, 0x5e, 0xa7, 0xb3, 1,
+		0x18, 0x16, 0x0d, 0xdd, 0,
+		0x70, 0xa0, 0x82, 0x31, 0,
+		0xa9, 0x05, 0x9c, 0xbb, 1,
+	]);
+	assert_eq!(lookup_selector(&map, 0xa9059cbb), Some(1));
+	assert_eq!(lookup_selector(&map, 0x70a08231), Some(0));
+	assert_eq!(lookup_selector(&map, 0x095ea7b3), Some(1));
+	assert_eq!(lookup_selector(&map, 0x12345678), None);
+	assert_eq!(lookup_selector(&[], 0xa9059cbb), None);
+}
+
+#[test]
+fn selector_from_name_and_params() {
+	assert_eq!(method_selector("transfer", &[ParamType::Address, ParamType::U256]), [0xa9, 0x05, 0x9c, 0xbb]);
+	assert_eq!(method_selector("totalSupply", &[]), [0x18, 0x16, 0x0d, 0xdd]);
+	assert_eq!(
+		BigEndian::read_u32(&method_selector("baz", &[ParamType::U32, ParamType::Bool])),
+		HashSignature::from(NamedSignature::new("baz", Signature::new_void(vec![ParamType::U32, ParamType::Bool]))).hash()
+	);
+
+	// structs are passed as tuples, rendered as the parenthesized list of their members
+	let order = ParamType::Tuple(vec![ParamType::U256, ParamType::Address].into());
+	assert_eq!(method_selector("submit", &[order, ParamType::Bool]), [0xd2, 0xaf, 0x30, 0x07]);
+}
+
+#[test]
+fn const_selector_matches_method_selector() {
+	use std::borrow::Cow;
+	use super::ArrayRef;
+
+	const ORDER: ParamType = ParamType::Tuple(Cow::Borrowed(&[ParamType::U256, ParamType::Address]));
+	const SUBMIT: u32 = const_selector("submit", &[ORDER, ParamType::Bool]);
+	assert_eq!(SUBMIT, 0xd2af3007);
+
+	let params = vec![
+		ParamType::Uint(8),
+		ParamType::Int(112),
+		ParamType::FixedBytes(4),
+		ParamType::Array(ParamType::Tuple(vec![ParamType::H256, ParamType::String].into()).into()),
+		ParamType::FixedArray(ArrayRef::Static(&ParamType::Bytes), 10),
+		ParamType::Tuple(Cow::Borrowed(&[])),
+	];
+	assert_eq!(const_selector("set", &params), BigEndian::read_u32(&method_selector("set", &params)));
+	assert_eq!(const_selector("totalSupply", &[]), 0x18160ddd);
+}
+
+#[test]
+fn canonical_signature() {
+	let transfer = NamedSignature::new("transfer", Signature::new(vec![ParamType::Address, ParamType::U256], Some(ParamType::Bool)));
+	assert_eq!(transfer.canonical(), "transfer(address,uint256)");
+
+	let sized = NamedSignature::new("set", Signature::new_void(vec![
+		ParamType::Uint(256),
+		ParamType::Int(256),
+		ParamType::Array(ParamType::Tuple(vec![ParamType::Uint(8), ParamType::FixedBytes(4)].into()).into()),
+	]));
+	assert_eq!(sized.canonical(), "set(uint256,int256,(uint8,bytes4)[])");
+	assert_eq!(NamedSignature::new("totalSupply", Signature::new_void(vec![])).canonical(), "totalSupply()");
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn fast_table_matches_table() {
+	let signatures: Vec<HashSignature> = (0..64).map(|i| {
+		NamedSignature::new(format!("method{}", i), Signature::new(vec![ParamType::U32], Some(ParamType::U32))).into()
+	}).collect();
+	let table = Table::new(signatures.clone());
+	let fast = FastTable::from(&table);
+	assert_eq!(fast.len(), 64);
+
+	for signature in &signatures {
+		let mut payload = vec![0u8; 4];
+		BigEndian::write_u32(&mut payload, signature.hash);
+		payload.extend(signature.signature.encode_invoke(&[ValueType::U32(signature.hash)]));
+
+		let echo = |method_id, mut args: Vec<ValueType>| {
+			assert_eq!(args[0], ValueType::U32(method_id));
+			args.pop()
+		};
+		assert_eq!(fast.dispatch(&payload, echo), table.dispatch(&payload, echo));
+		assert_eq!(fast.hash_signature(signature.hash).unwrap().hash(), signature.hash);
+	}
+	assert_eq!(fast.hash_signature(0).err(), Some(Error::UnknownSignature));
+	assert_eq!(fast.dispatch(&[0, 0, 0], |_, _| None), Err(Error::NoLengthForSignature));
+
+	// the linear scan of an unsorted table resolves every selector the same way
+	let unsorted = Table::new(signatures.iter().rev().cloned().collect::<Vec<_>>());
+	assert!(!unsorted.is_sorted());
+	for signature in &signatures {
+		assert_eq!(unsorted.hash_signature(signature.hash).unwrap().hash(), fast.hash_signature(signature.hash).unwrap().hash());
+	}
+}
+
+#[test]
+fn iterate_table() {
+	let table = Table::with_fallback(vec![
+		NamedSignature::new("transfer", Signature::new(vec![ParamType::Address, ParamType::U256], Some(ParamType::Bool))).into(),
+		NamedSignature::new("balanceOf", Signature::new(vec![ParamType::Address], Some(ParamType::U256))).into(),
+	], Signature::new_void(vec![ParamType::U256]));
+
+	let entries: Vec<(u32, usize)> = table.iter().map(|(hash, signature)| (hash, signature.params().len())).collect();
+	assert_eq!(entries, vec![(0xa9059cbb, 2), (0x70a08231, 1)]);
+	assert_eq!(Table::default().iter().count(), 0);
+}
@@ -0,0 +1,105 @@
+//! EIP-712 typed structured data hashing
+//!
+//! Struct fields are encoded into one word each: atomic values the same way as ABI words,
+//! `string` and `bytes` as the hash of their contents (`hash_dynamic`) and nested structs
+//! as their own `hash_struct`.
+
+use lib::*;
+use super::util::{keccak256, Hash};
+
+/// Type hash of the struct with the given encoded type, e.g. `keccak256("Mail(address from,address to,string contents)")`
+///
+/// Types of nested structs have to be appended to `signature`, sorted by name, as EIP-712 specifies.
+pub fn type_hash(signature: &str) -> Hash {
+	keccak256(signature.as_bytes()).into()
+}
+
+/// Encoded word of a `string` or `bytes` field
+pub fn hash_dynamic(data: &[u8]) -> Hash {
+	keccak256(data).into()
+}
+
+/// `hashStruct` of the struct: `keccak256(type_hash || field1 || field2 || ...)`
+pub fn hash_struct(type_hash: Hash, encoded_fields: &[Hash]) -> Hash {
+	let mut preimage = Vec::with_capacity(32 * (encoded_fields.len() + 1));
+	preimage.extend_from_slice(&type_hash);
+	for field in encoded_fields {
+		preimage.extend_from_slice(field);
+	}
+	keccak256(&preimage).into()
+}
+
+/// Digest to be signed for the struct: `keccak256("\x19\x01" || domain_separator || struct_hash)`
+pub fn hash_typed_data(domain_separator: Hash, struct_hash: Hash) -> Hash {
+	let mut preimage = Vec::with_capacity(2 + 64);
+	preimage.extend_from_slice(b"\x19\x01");
+	preimage.extend_from_slice(&domain_separator);
+	preimage.extend_from_slice(&struct_hash);
+	keccak256(&preimage).into()
+}
+
+#[cfg(test)]
+mod tests {
+	extern crate rustc_hex as hex;
+
+	use self::hex::FromHex;
+	use super::*;
+	use super::super::util::pad_u32;
+
+	fn hash(s: &str) -> Hash {
+		let mut result = [0u8; 32];
+		result.copy_from_slice(&s.from_hex().unwrap());
+		result
+	}
+
+	fn address(s: &str) -> Hash {
+		let mut result = [0u8; 32];
+		result[12..].copy_from_slice(&s.from_hex().unwrap());
+		result
+	}
+
+	// the `Mail` example from the EIP
+	const PERSON: &'static str = "Person(string name,address wallet)";
+	const MAIL: &'static str = "Mail(Person from,Person to,string contents)Person(string name,address wallet)";
+
+	fn person(name: &str, wallet: &str) -> Hash {
+		hash_struct(type_hash(PERSON), &[hash_dynamic(name.as_bytes()), address(wallet)])
+	}
+
+	#[test]
+	fn mail_type_hash() {
+		assert_eq!(type_hash(MAIL), hash("a0cedeb2dc280ba39b857546d74f5549c3a1d7bdc2dd96bf881f76108e23dac2"));
+		assert_eq!(
+			type_hash("Mail(address from,address to,string contents)"),
+			hash("536e54c54e6699204b424f41f6dea846ee38ac369afec3e7c141d2c92c65e67f")
+		);
+	}
+
+	#[test]
+	fn mail_hash_struct() {
+		let from = person("Cow", "CD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826");
+		let to = person("Bob", "bBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB");
+		assert_eq!(from, hash("fc71e5fa27ff56c350aa531bc129ebdf613b772b6604664f5d8dbe21b85eb0c8"));
+		assert_eq!(to, hash("cd54f074a4af31b4411ff6a60c9719dbd559c221c8ac3492d9d872b041d703d1"));
+
+		let mail = hash_struct(type_hash(MAIL), &[from, to, hash_dynamic(b"Hello, Bob!")]);
+		assert_eq!(mail, hash("c52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e"));
+	}
+
+	#[test]
+	fn mail_digest() {
+		let domain = hash_struct(
+			type_hash("EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)"),
+			&[
+				hash_dynamic(b"Ether Mail"),
+				hash_dynamic(b"1"),
+				pad_u32(1),
+				address("CcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"),
+			],
+		);
+		assert_eq!(domain, hash("f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f"));
+
+		let mail = hash("c52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e");
+		assert_eq!(hash_typed_data(domain, mail), hash("be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2"));
+	}
+}
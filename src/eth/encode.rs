@@ -82,29 +82,27 @@ impl Mediate {
 		}
 	}
 
-	fn closing(&self, offset: u32) -> Vec<Hash> {
+	fn closing(&self) -> Vec<Hash> {
 		match *self {
 			Mediate::Raw(_) => vec![],
 			Mediate::Prefixed(ref pre) => pre.clone(),
 			Mediate::FixedArray(ref nes) => {
-				// offset is not taken into account, cause it would be counted twice
 				// fixed array is just raw representations of similar consecutive items
 				nes.iter()
-					.enumerate()
-					.flat_map(|(i, m)| m.closing(Mediate::offset_for(nes, i)))
+					.flat_map(|m| m.closing())
 					.collect()
 			},
 			Mediate::Array(ref nes) => {
-				// + 32 added to offset represents len of the array prepanded to closing
+				// elements are laid out as a standalone payload after the length,
+				// so their offsets are relative to the word following the length
 				let prefix = vec![pad_u32(nes.len() as u32)].into_iter();
 
 				let inits = nes.iter()
 					.enumerate()
-					.flat_map(|(i, m)| m.init(offset + Mediate::offset_for(nes, i) + 32));
+					.flat_map(|(i, m)| m.init(Mediate::offset_for(nes, i)));
 
 				let closings = nes.iter()
-					.enumerate()
-					.flat_map(|(i, m)| m.closing(offset + Mediate::offset_for(nes, i)));
+					.flat_map(|m| m.closing());
 
 				prefix.chain(inits).chain(closings).collect()
 			},
@@ -112,19 +110,61 @@ impl Mediate {
 	}
 }
 
+fn pad_address(address: &[u8; 20]) -> Hash {
+	let mut padded = [0u8; 32];
+	padded[12..].copy_from_slice(address);
+	padded
+}
+
+/// Encodes `address[]` straight into its final words, skipping per element mediates
+///
+/// Returns `None` if not all values are addresses.
+fn encode_address_array(values: &[ValueType]) -> Option<Mediate> {
+	// arrays are homogeneous, so for anything but addresses this bails out on the first element,
+	// before allocating the words
+	match values.first() {
+		Some(&ValueType::Address(_)) | None => {},
+		Some(_) => return None,
+	}
+
+	let mut words = Vec::with_capacity(values.len() + 1);
+	words.push(pad_u32(values.len() as u32));
+	for value in values {
+		match *value {
+			ValueType::Address(ref address) => words.push(pad_address(address)),
+			_ => return None,
+		}
+	}
+
+	// static elements are laid out right after the length, same as `Mediate::Array` does
+	Some(Mediate::Prefixed(words))
+}
+
 /// Encodes vector of tokens into ABI compliant vector of bytes.
 pub fn encode(tokens: &[ValueType]) -> Vec<u8> {
 	let mediates: Vec<Mediate> = tokens.iter()
 		.map(encode_token)
 		.collect();
 
+	encode_mediates(&mediates)
+}
+
+/// Same as `encode`, for values borrowed from different places
+pub fn encode_refs(tokens: &[&ValueType]) -> Vec<u8> {
+	let mediates: Vec<Mediate> = tokens.iter()
+		.map(|token| encode_token(token))
+		.collect();
+
+	encode_mediates(&mediates)
+}
+
+fn encode_mediates(mediates: &[Mediate]) -> Vec<u8> {
 	let inits = mediates.iter()
 		.enumerate()
-		.flat_map(|(i, m)| m.init(Mediate::offset_for(&mediates, i)));
+		.flat_map(|(i, m)| m.init(Mediate::offset_for(mediates, i)));
 
 	let closings = mediates.iter()
-		.enumerate()
-		.flat_map(|(i, m)| m.closing(Mediate::offset_for(&mediates, i)));
+		.flat_map(|m| m.closing());
 
 	inits.chain(closings)
 		.flat_map(|item| item.to_vec())
@@ -133,24 +173,39 @@ pub fn encode(tokens: &[ValueType]) -> Vec<u8> {
 
 fn encode_token(token: &ValueType) -> Mediate {
 	match *token {
-		ValueType::Address(ref address) => {
-			let mut padded = [0u8; 32];
-			padded[12..].copy_from_slice(address);
-			Mediate::Raw(vec![padded])
-		},
+		ValueType::Address(ref address) => Mediate::Raw(vec![pad_address(address)]),
         ValueType::U32(val) => Mediate::Raw(vec![pad_u32(val)]),
         ValueType::U64(val) => Mediate::Raw(vec![pad_u64(val)]),
         ValueType::I32(val) => Mediate::Raw(vec![pad_i32(val)]),
         ValueType::I64(val) => Mediate::Raw(vec![pad_i64(val)]),
 		ValueType::Bytes(ref bytes) => Mediate::Prefixed(pad_bytes(bytes)),
+		ValueType::FixedBytes(ref bytes) => Mediate::Raw(pad_fixed_bytes(bytes)),
 		ValueType::String(ref s) => Mediate::Prefixed(pad_bytes(s.as_bytes())),
 		ValueType::U256(ref h) => Mediate::Raw(vec![h.clone()]),
 		ValueType::H256(ref h) => Mediate::Raw(vec![h.clone()]),
+		ValueType::Uint(ref h, _) | ValueType::Int(ref h, _) => Mediate::Raw(vec![h.clone()]),
+		ValueType::Unit => Mediate::Raw(vec![]),
+		ValueType::Tuple(ref values) | ValueType::FixedArray(ref values) => {
+			// members are encoded as a standalone payload: in place if static, in the tail otherwise
+			let words = encode(values).chunks(32).map(|chunk| {
+				let mut word = [0u8; 32];
+				word.copy_from_slice(chunk);
+				word
+			}).collect();
+			match token.is_dynamic() {
+				true => Mediate::Prefixed(words),
+				false => Mediate::Raw(words),
+			}
+		},
 		ValueType::Bool(b) => {
 			let value = if b { 1 } else { 0 };
 			Mediate::Raw(vec![pad_u32(value)])
 		},
 		ValueType::Array(ref values) => {
+			if let Some(mediate) = encode_address_array(values) {
+				return mediate;
+			}
+
 			let mediates = values.iter()
 				.map(encode_token)
 				.collect();
@@ -165,9 +220,10 @@ mod tests {
 	extern crate rustc_hex as hex;
 
 	use self::hex::FromHex;
-	use super::super::util::pad_u32;
-	use super::super::ValueType;
-	use super::encode;
+	use super::super::util::{pad_u32, pad_i128};
+	use super::super::{ValueType, ParamType, Error};
+	use super::super::decode::decode;
+	use super::{encode, encode_token, encode_mediates, Mediate};
 
 	#[test]
 	fn encode_address() {
@@ -191,6 +247,20 @@ mod tests {
 		assert_eq!(encoded, expected);
 	}
 
+	#[test]
+	fn encode_address_array_fast_path() {
+		let addresses: Vec<ValueType> = (0..50u8).map(|i| ValueType::Address([i; 20])).collect();
+		let generic = encode_mediates(&[
+			ValueType::U32(7),
+			ValueType::String("allowlist".to_owned()),
+		].iter().map(encode_token).chain(Some(Mediate::Array(addresses.iter().map(encode_token).collect()))).collect::<Vec<_>>());
+
+		let encoded = encode(&[ValueType::U32(7), ValueType::String("allowlist".to_owned()), ValueType::Array(addresses)]);
+		assert_eq!(encoded.len(), 32 * (3 + 2 + 51));
+		assert_eq!(encoded, generic);
+		assert_eq!(encode(&[ValueType::Array(vec![])]), encode_mediates(&[Mediate::Array(vec![])]));
+	}
+
 	#[test]
 	fn encode_two_addresses() {
 		let address1 = ValueType::Address([0x11u8; 20]);
@@ -213,8 +283,8 @@ mod tests {
 		let expected = ("".to_owned() +
 			"0000000000000000000000000000000000000000000000000000000000000020" +
 			"0000000000000000000000000000000000000000000000000000000000000002" +
+			"0000000000000000000000000000000000000000000000000000000000000040" +
 			"0000000000000000000000000000000000000000000000000000000000000080" +
-			"00000000000000000000000000000000000000000000000000000000000000c0" +
 			"0000000000000000000000000000000000000000000000000000000000000001" +
 			"0000000000000000000000001111111111111111111111111111111111111111" +
 			"0000000000000000000000000000000000000000000000000000000000000001" +
@@ -222,6 +292,58 @@ mod tests {
 		assert_eq!(encoded, expected);
 	}
 
+	#[test]
+	fn encode_dynamic_array_of_uint256_arrays() {
+		let array0 = ValueType::Array(vec![ValueType::U256(pad_u32(1)), ValueType::U256(pad_u32(2))]);
+		let array1 = ValueType::Array(vec![ValueType::U256(pad_u32(3))]);
+		let dynamic = ValueType::Array(vec![array0, array1]);
+		let encoded = encode(&[dynamic]);
+		let expected = ("".to_owned() +
+			"0000000000000000000000000000000000000000000000000000000000000020" +
+			"0000000000000000000000000000000000000000000000000000000000000002" +
+			"0000000000000000000000000000000000000000000000000000000000000040" +
+			"00000000000000000000000000000000000000000000000000000000000000a0" +
+			"0000000000000000000000000000000000000000000000000000000000000002" +
+			"0000000000000000000000000000000000000000000000000000000000000001" +
+			"0000000000000000000000000000000000000000000000000000000000000002" +
+			"0000000000000000000000000000000000000000000000000000000000000001" +
+			"0000000000000000000000000000000000000000000000000000000000000003").from_hex().unwrap();
+		assert_eq!(encoded, expected);
+	}
+
+	#[test]
+	fn reencode_dynamic_array_of_dynamic_arrays() {
+		let types = [ParamType::Array(ParamType::Array(ParamType::Address.into()).into())];
+		let fixtures = [
+			"".to_owned() +
+				"0000000000000000000000000000000000000000000000000000000000000020" +
+				"0000000000000000000000000000000000000000000000000000000000000002" +
+				"0000000000000000000000000000000000000000000000000000000000000040" +
+				"0000000000000000000000000000000000000000000000000000000000000080" +
+				"0000000000000000000000000000000000000000000000000000000000000001" +
+				"0000000000000000000000001111111111111111111111111111111111111111" +
+				"0000000000000000000000000000000000000000000000000000000000000001" +
+				"0000000000000000000000002222222222222222222222222222222222222222",
+			"".to_owned() +
+				"0000000000000000000000000000000000000000000000000000000000000020" +
+				"0000000000000000000000000000000000000000000000000000000000000002" +
+				"0000000000000000000000000000000000000000000000000000000000000040" +
+				"00000000000000000000000000000000000000000000000000000000000000a0" +
+				"0000000000000000000000000000000000000000000000000000000000000002" +
+				"0000000000000000000000001111111111111111111111111111111111111111" +
+				"0000000000000000000000002222222222222222222222222222222222222222" +
+				"0000000000000000000000000000000000000000000000000000000000000002" +
+				"0000000000000000000000003333333333333333333333333333333333333333" +
+				"0000000000000000000000004444444444444444444444444444444444444444",
+		];
+
+		for fixture in fixtures.iter() {
+			let encoded = fixture.from_hex().unwrap();
+			let decoded = decode(&types, &encoded).unwrap();
+			assert_eq!(encode(&decoded), encoded);
+		}
+	}
+
 	#[test]
 	fn encode_dynamic_array_of_dynamic_arrays2() {
 		let address1 = ValueType::Address([0x11u8; 20]);
@@ -235,8 +357,8 @@ mod tests {
 		let expected = ("".to_owned() +
 			"0000000000000000000000000000000000000000000000000000000000000020" +
 			"0000000000000000000000000000000000000000000000000000000000000002" +
-			"0000000000000000000000000000000000000000000000000000000000000080" +
-			"00000000000000000000000000000000000000000000000000000000000000e0" +
+			"0000000000000000000000000000000000000000000000000000000000000040" +
+			"00000000000000000000000000000000000000000000000000000000000000a0" +
 			"0000000000000000000000000000000000000000000000000000000000000002" +
 			"0000000000000000000000001111111111111111111111111111111111111111" +
 			"0000000000000000000000002222222222222222222222222222222222222222" +
@@ -397,5 +519,103 @@ mod tests {
 			"0000000000000000000000000000000000000000000000000000000000000007").from_hex().unwrap();
 		assert_eq!(encoded, expected);
 	}
-}
 
+	#[test]
+	fn encode_unit() {
+		assert!(encode(&[ValueType::Unit]).is_empty());
+		assert_eq!(
+			encode(&[ValueType::Unit, ValueType::Bool(true)]),
+			"0000000000000000000000000000000000000000000000000000000000000001".from_hex().unwrap()
+		);
+	}
+
+	#[test]
+	fn encode_static_tuple() {
+		let tuple = ValueType::Tuple(vec![ValueType::U32(1), ValueType::Bool(true)]);
+		let encoded = encode(&[tuple, ValueType::U32(2)]);
+		let expected = ("".to_owned() +
+			"0000000000000000000000000000000000000000000000000000000000000001" +
+			"0000000000000000000000000000000000000000000000000000000000000001" +
+			"0000000000000000000000000000000000000000000000000000000000000002").from_hex().unwrap();
+		assert_eq!(encoded, expected);
+	}
+
+	#[test]
+	fn encode_dynamic_tuple() {
+		let tuple = ValueType::Tuple(vec![ValueType::U32(1), ValueType::String("gavofyork".to_owned())]);
+		let encoded = encode(&[tuple, ValueType::U32(2)]);
+		let expected = ("".to_owned() +
+			"0000000000000000000000000000000000000000000000000000000000000040" +
+			"0000000000000000000000000000000000000000000000000000000000000002" +
+			"0000000000000000000000000000000000000000000000000000000000000001" +
+			"0000000000000000000000000000000000000000000000000000000000000040" +
+			"0000000000000000000000000000000000000000000000000000000000000009" +
+			"6761766f66796f726b0000000000000000000000000000000000000000000000").from_hex().unwrap();
+		assert_eq!(encoded, expected);
+	}
+
+	#[test]
+	fn encode_negative_ints_sign_extended() {
+		let minus_five = "fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffb".from_hex().unwrap();
+
+		assert_eq!(encode(&[ValueType::I32(-5)]), minus_five);
+		assert_eq!(encode(&[ValueType::I64(-5)]), minus_five);
+		// int8, int16 and int128 are encoded as the same word as int256
+		for &bits in &[8, 16, 128] {
+			assert_eq!(encode(&[ValueType::Int(pad_i128(-5), bits)]), minus_five);
+		}
+
+		assert_eq!(
+			encode(&[ValueType::Int(pad_i128(i128::MIN), 128)]),
+			"ffffffffffffffffffffffffffffffff80000000000000000000000000000000".from_hex().unwrap()
+		);
+		assert_eq!(
+			encode(&[ValueType::I64(i64::MIN)]),
+			"ffffffffffffffffffffffffffffffffffffffffffffffff8000000000000000".from_hex().unwrap()
+		);
+		assert_eq!(
+			encode(&[ValueType::I32(5)]),
+			"0000000000000000000000000000000000000000000000000000000000000005".from_hex().unwrap()
+		);
+	}
+
+	#[test]
+	fn sized_ints_round_trip() {
+		let cases: &[(usize, &[i128])] = &[
+			(8, &[-128, -5, -1, 0, 1, 127]),
+			(16, &[i16::MIN as i128, -300, 300, i16::MAX as i128]),
+			(128, &[i128::MIN, -1, 0, i128::MAX]),
+		];
+
+		for &(bits, values) in cases {
+			for &value in values {
+				let encoded = encode(&[ValueType::Int(pad_i128(value), bits)]);
+				assert_eq!(
+					decode(&[ParamType::Int(bits)], &encoded),
+					Ok(vec![ValueType::Int(pad_i128(value), bits)]),
+					"int{} {} should round trip", bits, value
+				);
+			}
+		}
+
+		// values past the range of the width are rejected on decode
+		for &(bits, value) in &[(8, 128), (8, -129), (16, i16::MAX as i128 + 1), (16, i16::MIN as i128 - 1)] {
+			let encoded = encode(&[ValueType::Int(pad_i128(value), bits)]);
+			assert_eq!(decode(&[ParamType::Int(bits)], &encoded), Err(Error::IntegerOverflow { width: bits }));
+		}
+		// i128::MAX + 1
+		let mut above = [0u8; 32];
+		above[16] = 0x80;
+		let encoded = encode(&[ValueType::Int(above, 128)]);
+		assert_eq!(decode(&[ParamType::Int(128)], &encoded), Err(Error::IntegerOverflow { width: 128 }));
+	}
+
+	#[test]
+	fn encode_fixed_bytes() {
+		let encoded = encode(&[ValueType::FixedBytes(vec![0x12, 0x34]), ValueType::U32(1)]);
+		let expected = ("".to_owned() +
+			"1234000000000000000000000000000000000000000000000000000000000000" +
+			"0000000000000000000000000000000000000000000000000000000000000001").from_hex().unwrap();
+		assert_eq!(encoded, expected);
+	}
+}
@@ -0,0 +1,89 @@
+//! Conversions between little-endian host integers and big-endian ABI words
+//!
+//! WASM memory is little-endian, so an integer read from (or written to) host memory has its
+//! least significant byte first. ABI words are big-endian and right aligned: the least
+//! significant byte is the last byte of the word.
+
+use super::util::{pad_u32, pad_u64, as_u32, as_u64, Error, Hash};
+
+/// Converts host u32 to ABI word, least significant byte goes to `word[31]`
+pub fn le_u32_to_word(v: u32) -> Hash {
+	pad_u32(v)
+}
+
+/// Converts host u64 to ABI word, least significant byte goes to `word[31]`
+pub fn le_u64_to_word(v: u64) -> Hash {
+	pad_u64(v)
+}
+
+/// Converts ABI word to host u32, failing if the value doesn't fit
+pub fn word_to_le_u32(word: &Hash) -> Result<u32, Error> {
+	as_u32(word)
+}
+
+/// Converts ABI word to host u64, failing if the value doesn't fit
+pub fn word_to_le_u64(word: &Hash) -> Result<u64, Error> {
+	as_u64(word)
+}
+
+/// Converts little-endian bytes (as laid out in host memory) of an unsigned integer to ABI word
+///
+/// # Panics
+///
+/// If more than 32 bytes are given.
+pub fn le_bytes_to_word(bytes: &[u8]) -> Hash {
+	assert!(bytes.len() <= 32, "integer wider than 256 bits");
+
+	let mut word = [0u8; 32];
+	for (i, b) in bytes.iter().enumerate() {
+		word[31 - i] = *b;
+	}
+	word
+}
+
+/// Writes the `out.len()` least significant bytes of ABI word to `out` in little-endian order
+///
+/// # Panics
+///
+/// If `out` is longer than 32 bytes.
+pub fn word_to_le_bytes(word: &Hash, out: &mut [u8]) {
+	assert!(out.len() <= 32, "integer wider than 256 bits");
+
+	for (i, b) in out.iter_mut().enumerate() {
+		*b = word[31 - i];
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn u64_word_byte_order() {
+		let word = le_u64_to_word(0x0102030405060708);
+
+		assert_eq!(&word[..24], &[0u8; 24][..]);
+		assert_eq!(&word[24..], &[0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08]);
+		assert_eq!(word_to_le_u64(&word), Ok(0x0102030405060708));
+	}
+
+	#[test]
+	fn le_bytes_word_byte_order() {
+		let host = [0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01];
+		let word = le_bytes_to_word(&host);
+		assert_eq!(word, le_u64_to_word(0x0102030405060708));
+
+		let mut out = [0u8; 8];
+		word_to_le_bytes(&word, &mut out);
+		assert_eq!(out, host);
+	}
+
+	#[test]
+	fn u32_word_byte_order() {
+		let word = le_u32_to_word(0x01020304);
+
+		assert_eq!(&word[28..], &[0x01, 0x02, 0x03, 0x04]);
+		assert_eq!(word_to_le_u32(&word), Ok(0x01020304));
+		assert_eq!(word_to_le_u32(&le_u64_to_word(0x0102030405060708)), Err(Error::IntegerOverflow { width: 32 }));
+	}
+}
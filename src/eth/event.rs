@@ -0,0 +1,287 @@
+//! Event log decoding
+//!
+//! Indexed params are stored in topics following the signature topic, one word each; composite
+//! ones (strings, bytes, arrays and tuples) only as the hash of their encoding, which is what gets
+//! decoded for them (as `H256`).
+//! The rest of the params is ABI encoded into the log data, like arguments of a call.
+
+use lib::*;
+use super::{ParamType, ValueType};
+use super::util::{keccak256, Error, Hash};
+use super::decode::decode;
+use super::encode::{encode, encode_refs};
+use super::dispatch::signature_string;
+
+/// Param of an event
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventParam {
+	/// Type of the param
+	pub kind: ParamType,
+	/// Whether the param is stored in a topic instead of the log data
+	pub indexed: bool,
+}
+
+impl EventParam {
+	pub fn new(kind: ParamType, indexed: bool) -> Self {
+		EventParam {
+			kind: kind,
+			indexed: indexed,
+		}
+	}
+}
+
+/// Event signature, used to decode logs it emitted
+#[derive(Debug, Clone)]
+pub struct Event {
+	name: Cow<'static, str>,
+	params: Cow<'static, [EventParam]>,
+}
+
+impl Event {
+	pub fn new<N, T>(name: N, params: T) -> Self
+		where N: Into<Cow<'static, str>>, T: Into<Cow<'static, [EventParam]>>
+	{
+		Event {
+			name: name.into(),
+			params: params.into(),
+		}
+	}
+
+	pub fn name(&self) -> &str {
+		self.name.as_ref()
+	}
+
+	pub fn params(&self) -> &[EventParam] {
+		self.params.as_ref()
+	}
+
+	/// First topic of the logs, `keccak256` of the canonical signature, e.g. `Transfer(address,address,uint256)`
+	pub fn topic(&self) -> Hash {
+		let signature_str = signature_string(&self.name, self.params.iter().map(|p| &p.kind));
+		keccak256(signature_str.as_bytes()).into()
+	}
+
+	/// Splits `values` of a log emitted by this event into its topics and data
+	///
+	/// Indexed values that don't encode to a single word (e.g. a string given for a `uint256`
+	/// param) are stored as the hash of their encoding, like composite ones.
+	///
+	/// # Panics
+	///
+	/// If the number of values doesn't match the number of params.
+	pub fn encode_log(&self, values: &[ValueType]) -> (Vec<Hash>, Vec<u8>) {
+		assert_eq!(values.len(), self.params.len(), "one value should be given for every event param");
+
+		let mut topics = vec![self.topic()];
+		let mut data_values = Vec::new();
+		for (param, value) in self.params.iter().zip(values) {
+			if !param.indexed {
+				data_values.push(value);
+				continue;
+			}
+
+			let word = encode(slice::from_ref(value));
+			match !is_hashed(&param.kind) && word.len() == 32 {
+				true => {
+					let mut topic = [0u8; 32];
+					topic.copy_from_slice(&word);
+					topics.push(topic);
+				},
+				false => {
+					let mut preimage = Vec::new();
+					topic_preimage(value, false, &mut preimage);
+					topics.push(keccak256(&preimage).into());
+				},
+			}
+		}
+
+		(topics, encode_refs(&data_values))
+	}
+
+	/// Decodes params of a log emitted by this event, in declaration order
+	///
+	/// Non-indexed params are decoded from `data` with the regular decoder, so dynamic ones
+	/// are supported there too.
+	pub fn decode_log(&self, topics: &[Hash], data: &[u8]) -> Result<Vec<ValueType>, Error> {
+		let indexed_count = self.params.iter().filter(|p| p.indexed).count();
+		if topics.len() != indexed_count + 1 || topics[0] != self.topic() {
+			return Err(Error::InvalidTopics);
+		}
+
+		let data_types: Vec<ParamType> = self.params.iter()
+			.filter(|p| !p.indexed)
+			.map(|p| p.kind.clone())
+			.collect();
+		let mut data_values = decode(&data_types, data)?.into_iter();
+		let mut indexed_topics = topics[1..].iter();
+
+		self.params.iter().map(|param| {
+			if !param.indexed {
+				return Ok(data_values.next().expect("one value is decoded for every non-indexed param"));
+			}
+
+			let topic = indexed_topics.next().expect("topics count is checked above");
+			match is_hashed(&param.kind) {
+				true => Ok(ValueType::H256(*topic)),
+				false => Ok(decode(slice::from_ref(&param.kind), topic)?.pop().expect("one value is decoded for one param")),
+			}
+		}).collect()
+	}
+}
+
+/// Whether an indexed param of this type is stored as the hash of its value
+fn is_hashed(kind: &ParamType) -> bool {
+	matches!(*kind, ParamType::Bytes | ParamType::String | ParamType::Array(_) | ParamType::FixedArray(..) | ParamType::Tuple(_))
+}
+
+/// Appends what gets hashed for an indexed `value`: contents of strings and bytes (zero padded
+/// when `padded`, as they are inside arrays and tuples), elements of composites, words otherwise
+fn topic_preimage(value: &ValueType, padded: bool, out: &mut Vec<u8>) {
+	match *value {
+		ValueType::Bytes(ref bytes) => push_contents(bytes, padded, out),
+		ValueType::String(ref string) => push_contents(string.as_bytes(), padded, out),
+		ValueType::Array(ref values) | ValueType::FixedArray(ref values) | ValueType::Tuple(ref values) => {
+			for value in values {
+				topic_preimage(value, true, out);
+			}
+		},
+		ref value => out.extend(encode(slice::from_ref(value))),
+	}
+}
+
+fn push_contents(contents: &[u8], padded: bool, out: &mut Vec<u8>) {
+	out.extend_from_slice(contents);
+	if padded {
+		let padded_len = out.len() + (32 - contents.len() % 32) % 32;
+		out.resize(padded_len, 0);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	extern crate rustc_hex as hex;
+
+	use self::hex::FromHex;
+	use super::{Event, EventParam};
+	use super::super::{ParamType, ValueType, Error, encode_values};
+	use super::super::util::{keccak256, pad_u32, Hash};
+
+	fn hash(s: &str) -> Hash {
+		let mut result = [0u8; 32];
+		result.copy_from_slice(&s.from_hex().unwrap());
+		result
+	}
+
+	fn address_topic(address: [u8; 20]) -> Hash {
+		let mut topic = [0u8; 32];
+		topic[12..].copy_from_slice(&address);
+		topic
+	}
+
+	#[test]
+	fn decode_transfer() {
+		let event = Event::new("Transfer", vec![
+			EventParam::new(ParamType::Address, true),
+			EventParam::new(ParamType::Address, true),
+			EventParam::new(ParamType::U256, false),
+		]);
+		assert_eq!(event.topic(), hash("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"));
+
+		let topics = [event.topic(), address_topic([0x11u8; 20]), address_topic([0x22u8; 20])];
+		let values = event.decode_log(&topics, &pad_u32(1000)).unwrap();
+
+		assert_eq!(values, vec![
+			ValueType::Address([0x11u8; 20]),
+			ValueType::Address([0x22u8; 20]),
+			ValueType::U256(pad_u32(1000)),
+		]);
+	}
+
+	#[test]
+	fn decode_dynamic_data() {
+		let event = Event::new("Swap", vec![
+			EventParam::new(ParamType::Address, true),
+			EventParam::new(ParamType::Array(ParamType::U256.into()), false),
+			EventParam::new(ParamType::String, false),
+		]);
+
+		let data = encode_values(&[
+			ValueType::Array(vec![ValueType::U256(pad_u32(5)), ValueType::U256(pad_u32(7))]),
+			ValueType::String("swap memo".to_owned()),
+		]);
+		let topics = [event.topic(), address_topic([0x11u8; 20])];
+
+		assert_eq!(event.decode_log(&topics, &data).unwrap(), vec![
+			ValueType::Address([0x11u8; 20]),
+			ValueType::Array(vec![ValueType::U256(pad_u32(5)), ValueType::U256(pad_u32(7))]),
+			ValueType::String("swap memo".to_owned()),
+		]);
+	}
+
+	#[test]
+	fn indexed_dynamic_param_is_hash() {
+		let event = Event::new("Named", vec![EventParam::new(ParamType::String, true)]);
+		let name_hash: Hash = keccak256(b"gavofyork").into();
+
+		assert_eq!(event.decode_log(&[event.topic(), name_hash], &[]).unwrap(), vec![ValueType::H256(name_hash)]);
+	}
+
+	#[test]
+	fn mismatched_topics() {
+		let event = Event::new("Named", vec![EventParam::new(ParamType::String, true)]);
+
+		assert_eq!(event.decode_log(&[event.topic()], &[]), Err(Error::InvalidTopics));
+		assert_eq!(event.decode_log(&[[0u8; 32], [0u8; 32]], &[]), Err(Error::InvalidTopics));
+	}
+
+	#[test]
+	fn encode_transfer() {
+		let event = Event::new("Transfer", vec![
+			EventParam::new(ParamType::Address, true),
+			EventParam::new(ParamType::Address, true),
+			EventParam::new(ParamType::U256, false),
+		]);
+		let values = vec![
+			ValueType::Address([0x11u8; 20]),
+			ValueType::Address([0x22u8; 20]),
+			ValueType::U256(pad_u32(1000)),
+		];
+
+		let (topics, data) = event.encode_log(&values);
+
+		assert_eq!(topics, vec![event.topic(), address_topic([0x11u8; 20]), address_topic([0x22u8; 20])]);
+		assert_eq!(data, pad_u32(1000).to_vec());
+		assert_eq!(event.decode_log(&topics, &data).unwrap(), values);
+	}
+
+	#[test]
+	fn encode_hashed_topics() {
+		let event = Event::new("Tagged", vec![
+			EventParam::new(ParamType::String, true),
+			EventParam::new(ParamType::Array(ParamType::U32.into()), true),
+			EventParam::new(ParamType::Bytes, false),
+		]);
+		let values = vec![
+			ValueType::String("gavofyork".to_owned()),
+			ValueType::Array(vec![ValueType::U32(1), ValueType::U32(2)]),
+			ValueType::Bytes(vec![1, 2, 3]),
+		];
+
+		let (topics, data) = event.encode_log(&values);
+
+		let mut words = pad_u32(1).to_vec();
+		words.extend_from_slice(&pad_u32(2));
+		assert_eq!(topics, vec![event.topic(), keccak256(b"gavofyork").into(), keccak256(&words).into()]);
+		assert_eq!(data, encode_values(&[ValueType::Bytes(vec![1, 2, 3])]));
+		assert_eq!(event.decode_log(&topics, &data).unwrap()[2], ValueType::Bytes(vec![1, 2, 3]));
+	}
+
+	#[test]
+	fn encode_multi_word_indexed_value() {
+		let event = Event::new("Named", vec![EventParam::new(ParamType::U256, true)]);
+
+		let (topics, _) = event.encode_log(&[ValueType::String("gavofyork".to_owned())]);
+
+		assert_eq!(topics, vec![event.topic(), keccak256(b"gavofyork").into()]);
+	}
+}
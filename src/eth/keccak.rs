@@ -0,0 +1,120 @@
+//! Keccak-256 usable in constants
+//!
+//! Slower than `tiny_keccak`, only meant for selectors that can't be computed before the
+//! crate is compiled (see `const_selector`).
+
+use super::util::Hash;
+
+/// Bytes absorbed per permutation by Keccak-256
+const RATE: usize = 136;
+
+const ROUND_CONSTANTS: [u64; 24] = [
+	0x0000000000000001, 0x0000000000008082, 0x800000000000808a, 0x8000000080008000,
+	0x000000000000808b, 0x0000000080000001, 0x8000000080008081, 0x8000000000008009,
+	0x000000000000008a, 0x0000000000000088, 0x0000000080008009, 0x000000008000000a,
+	0x000000008000808b, 0x800000000000008b, 0x8000000000008089, 0x8000000000008003,
+	0x8000000000008002, 0x8000000000000080, 0x000000000000800a, 0x800000008000000a,
+	0x8000000080008081, 0x8000000000008080, 0x0000000080000001, 0x8000000080008008,
+];
+
+const ROTATIONS: [u32; 24] = [1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44];
+
+const LANES: [usize; 24] = [10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1];
+
+const fn keccak_f(state: &mut [u64; 25]) {
+	let mut round = 0;
+	while round < 24 {
+		// theta
+		let mut columns = [0u64; 5];
+		let mut x = 0;
+		while x < 5 {
+			columns[x] = state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20];
+			x += 1;
+		}
+		x = 0;
+		while x < 5 {
+			let t = columns[(x + 4) % 5] ^ columns[(x + 1) % 5].rotate_left(1);
+			let mut y = 0;
+			while y < 25 {
+				state[y + x] ^= t;
+				y += 5;
+			}
+			x += 1;
+		}
+
+		// rho and pi
+		let mut last = state[1];
+		let mut i = 0;
+		while i < 24 {
+			let lane = LANES[i];
+			let current = state[lane];
+			state[lane] = last.rotate_left(ROTATIONS[i]);
+			last = current;
+			i += 1;
+		}
+
+		// chi
+		let mut y = 0;
+		while y < 25 {
+			let mut row = [0u64; 5];
+			x = 0;
+			while x < 5 {
+				row[x] = state[y + x];
+				x += 1;
+			}
+			x = 0;
+			while x < 5 {
+				state[y + x] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
+				x += 1;
+			}
+			y += 5;
+		}
+
+		// iota
+		state[0] ^= ROUND_CONSTANTS[round];
+		round += 1;
+	}
+}
+
+const fn absorb_byte(state: &mut [u64; 25], position: usize, byte: u8) {
+	state[position / 8] ^= (byte as u64) << (8 * (position % 8));
+}
+
+/// Keccak-256 digest of the given data, same as `util::keccak256`
+pub const fn keccak256(data: &[u8]) -> Hash {
+	let mut state = [0u64; 25];
+	let mut i = 0;
+	while i < data.len() {
+		absorb_byte(&mut state, i % RATE, data[i]);
+		i += 1;
+		if i % RATE == 0 {
+			keccak_f(&mut state);
+		}
+	}
+	absorb_byte(&mut state, i % RATE, 0x01);
+	absorb_byte(&mut state, RATE - 1, 0x80);
+	keccak_f(&mut state);
+
+	let mut digest = [0u8; 32];
+	i = 0;
+	while i < 32 {
+		digest[i] = (state[i / 8] >> (8 * (i % 8))) as u8;
+		i += 1;
+	}
+	digest
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{keccak256, RATE};
+	use super::super::util;
+
+	#[test]
+	fn matches_tiny_keccak() {
+		let data: Vec<u8> = (0..3 * RATE as u32).map(|i| (i * 7) as u8).collect();
+		// around the rate, where the padding spills into another block
+		for &len in &[0, 1, 31, RATE - 2, RATE - 1, RATE, RATE + 1, 2 * RATE, 3 * RATE] {
+			assert_eq!(&keccak256(&data[..len])[..], util::keccak256(&data[..len]).as_ref());
+		}
+	}
+}
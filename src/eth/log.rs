@@ -1,6 +1,8 @@
 use byteorder::{BigEndian, ByteOrder};
 use parity_hash::H256;
 use bigint::U256;
+use lib::*;
+use super::util::{keccak256, pad_i32, pad_i64};
 
 pub trait AsLog {
     fn as_log(&self) -> H256;
@@ -24,17 +26,13 @@ impl AsLog for u64 {
 
 impl AsLog for i64 {
     fn as_log(&self) -> H256 {
-        let mut result = H256::zero();
-        BigEndian::write_i64(&mut result.as_mut()[24..32], *self);
-        result
+        pad_i64(*self).into()
     }
 }
 
 impl AsLog for i32 {
     fn as_log(&self) -> H256 {
-        let mut result = H256::zero();
-        BigEndian::write_i32(&mut result.as_mut()[28..32], *self);
-        result
+        pad_i32(*self).into()
     }
 }
 
@@ -42,7 +40,7 @@ impl AsLog for i32 {
 impl AsLog for bool {
     fn as_log(&self) -> H256 {
         let mut result = H256::zero();
-        result.as_mut()[32] = if *self { 1 } else { 0 };
+        result.as_mut()[31] = if *self { 1 } else { 0 };
         result
     }
 }
@@ -59,4 +57,51 @@ impl AsLog for H256 {
     fn as_log(&self) -> H256 {
         self.clone()
     }
-}
\ No newline at end of file
+}
+
+// dynamic values don't fit into a topic, it holds the hash of their contents instead
+
+impl AsLog for Vec<u8> {
+    fn as_log(&self) -> H256 {
+        keccak256(self)
+    }
+}
+
+impl AsLog for String {
+    fn as_log(&self) -> H256 {
+        keccak256(self.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AsLog;
+
+    #[test]
+    fn negative_topics_are_sign_extended() {
+        let mut expected = [0xffu8; 32];
+        expected[31] = 0xfb;
+
+        assert_eq!((-5i32).as_log().as_ref(), &expected[..]);
+        assert_eq!((-5i64).as_log().as_ref(), &expected[..]);
+    }
+
+    #[test]
+    fn bool_topic() {
+        let mut expected = [0u8; 32];
+        assert_eq!(false.as_log().as_ref(), &expected[..]);
+
+        expected[31] = 1;
+        assert_eq!(true.as_log().as_ref(), &expected[..]);
+    }
+
+    #[test]
+    fn dynamic_topics_are_hashed() {
+        use super::keccak256;
+
+        let expected = keccak256(b"gavofyork");
+
+        assert_eq!(b"gavofyork".to_vec().as_log().as_ref(), expected.as_ref());
+        assert_eq!("gavofyork".to_owned().as_log().as_ref(), expected.as_ref());
+    }
+}
@@ -7,12 +7,31 @@ mod encode;
 mod decode;
 mod util;
 mod dispatch;
+mod keccak;
 mod log;
+mod event;
+mod storage;
+mod revert;
+pub mod encoding;
+pub mod eip712;
+pub mod packed;
+#[cfg(feature = "serde")]
+mod abi;
 
-pub use self::param_type::{ParamType, ArrayRef};
-pub use self::value_type::ValueType;
+pub use self::param_type::{ParamType, ArrayRef, AbiType, const_concat_len, const_concat, const_str};
+pub use self::value_type::{ValueType, ParseError, MathError, ValueError, TryFromValue};
 pub use self::signature::Signature;
-pub use self::util::Error;
-pub use self::dispatch::{HashSignature, NamedSignature, Table};
+pub use self::util::{Error, hex, keccak256};
+pub use self::dispatch::{HashSignature, NamedSignature, Table, compute_selector, method_selector, const_selector, lookup_selector};
+#[cfg(feature = "std")]
+pub use self::dispatch::FastTable;
 pub use self::log::AsLog;
-pub use self::encode::encode as encode_values;
\ No newline at end of file
+pub use self::event::{Event, EventParam};
+pub use self::encode::encode as encode_values;
+pub use self::decode::{decode as decode_values, decode_with_config, decode_strict, decode_with_len, decode_reuse, decode_canonical, DecodeConfig, Decoder, DEFAULT_MAX_PARAMS};
+#[cfg(feature = "std")]
+pub use self::decode::{decode_traced, decode_with_words, TraceEntry};
+pub use self::storage::{mapping_slot, nested_mapping_slot};
+pub use self::revert::{ERROR_SELECTOR, PANIC_SELECTOR, decode_revert_reason, decode_panic_code};
+#[cfg(feature = "serde")]
+pub use self::abi::Abi;
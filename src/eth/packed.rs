@@ -0,0 +1,137 @@
+//! Packed unsigned integer arrays
+//!
+//! Non-standard codec used by contracts squeezing calldata: elements of `element_bits` bits are
+//! packed back to back (most significant bit first) across 32-byte words, after a word holding
+//! the number of elements. The last word is zero padded. This is not ABI encoding, values have
+//! to be passed as `bytes` for the standard encoder and decoder.
+
+use lib::*;
+use super::util::{pad_u32, as_u32, Error, Hash};
+
+fn assert_element_bits(element_bits: usize) {
+	assert!(element_bits > 0 && element_bits <= 64, "packed elements should be 1 to 64 bits wide");
+}
+
+fn packed_words(element_bits: usize, count: usize) -> usize {
+	(element_bits * count + 255) / 256
+}
+
+/// Packs `values` into words of `element_bits` bit elements, prefixed by their count
+///
+/// # Panics
+///
+/// If `element_bits` is not within `1..=64` or some value doesn't fit into `element_bits` bits.
+pub fn encode_packed_array(element_bits: usize, values: &[u64]) -> Vec<u8> {
+	assert_element_bits(element_bits);
+
+	let words = packed_words(element_bits, values.len());
+	let mut result = Vec::with_capacity(32 * (words + 1));
+	result.extend_from_slice(&pad_u32(values.len() as u32));
+	result.resize(32 * (words + 1), 0);
+
+	let packed = &mut result[32..];
+	for (i, value) in values.iter().enumerate() {
+		assert!(element_bits == 64 || value >> element_bits == 0, "value doesn't fit into packed element");
+		for bit in 0..element_bits {
+			if (value >> (element_bits - 1 - bit)) & 1 == 1 {
+				let position = i * element_bits + bit;
+				packed[position / 8] |= 0x80 >> (position % 8);
+			}
+		}
+	}
+
+	result
+}
+
+/// Unpacks elements of `element_bits` bits encoded by `encode_packed_array`
+///
+/// # Panics
+///
+/// If `element_bits` is not within `1..=64`.
+pub fn decode_packed_array(element_bits: usize, data: &[u8]) -> Result<Vec<u64>, Error> {
+	assert_element_bits(element_bits);
+
+	if data.len() < 32 {
+		return Err(Error::UnexpectedEnd { position: 32 });
+	}
+	let mut len_word: Hash = [0u8; 32];
+	len_word.copy_from_slice(&data[..32]);
+	let count = as_u32(&len_word)? as usize;
+
+	let packed = &data[32..];
+	// saturating, the bit length could overflow for a bogus count
+	let words = count.saturating_mul(element_bits).saturating_add(255) / 256;
+	if packed.len() < 32 * words {
+		return Err(Error::UnexpectedEnd { position: 32 * (words + 1) });
+	}
+	if packed.len() > 32 * words {
+		return Err(Error::TrailingData { remaining: packed.len() - 32 * words });
+	}
+
+	let bit = |position: usize| (packed[position / 8] >> (7 - position % 8)) & 1;
+
+	// bits of the last word past the last element should be zero
+	if (count * element_bits..words * 256).any(|position| bit(position) != 0) {
+		return Err(Error::InvalidPadding);
+	}
+
+	Ok((0..count).map(|i| {
+		(0..element_bits).fold(0u64, |acc, b| (acc << 1) | bit(i * element_bits + b) as u64)
+	}).collect())
+}
+
+#[cfg(test)]
+mod tests {
+	extern crate rustc_hex as hex;
+
+	use self::hex::FromHex;
+	use super::*;
+
+	#[test]
+	fn pack_uint8_array_into_one_word() {
+		let encoded = encode_packed_array(8, &[1, 2, 3, 0xff]);
+		let expected = ("".to_owned() +
+			"0000000000000000000000000000000000000000000000000000000000000004" +
+			"010203ff00000000000000000000000000000000000000000000000000000000").from_hex().unwrap();
+
+		assert_eq!(encoded, expected);
+		assert_eq!(decode_packed_array(8, &encoded), Ok(vec![1, 2, 3, 0xff]));
+	}
+
+	#[test]
+	fn pack_across_words() {
+		// 40 elements of 7 bits take 280 bits, spilling into the second word
+		let values: Vec<u64> = (0..40).map(|i| (i * 3) % 128).collect();
+		let encoded = encode_packed_array(7, &values);
+
+		assert_eq!(encoded.len(), 32 * 3);
+		assert_eq!(decode_packed_array(7, &encoded), Ok(values));
+
+		let wide = [u64::MAX, 1];
+		assert_eq!(decode_packed_array(64, &encode_packed_array(64, &wide)), Ok(wide.to_vec()));
+	}
+
+	#[test]
+	fn reject_malformed_packed_data() {
+		let mut encoded = encode_packed_array(8, &[1, 2, 3, 4]);
+
+		assert_eq!(decode_packed_array(8, &encoded[..32]), Err(Error::UnexpectedEnd { position: 64 }));
+
+		let mut bogus_count = pad_u32(u32::MAX).to_vec();
+		bogus_count.extend_from_slice(&[0u8; 32]);
+		assert_eq!(decode_packed_array(64, &bogus_count), Err(Error::UnexpectedEnd { position: 32 * (1 + 0xffffffff / 4 + 1) }));
+
+		encoded[63] = 1;
+		assert_eq!(decode_packed_array(8, &encoded), Err(Error::InvalidPadding));
+
+		encoded[63] = 0;
+		encoded.extend_from_slice(&[0u8; 32]);
+		assert_eq!(decode_packed_array(8, &encoded), Err(Error::TrailingData { remaining: 32 }));
+	}
+
+	#[test]
+	#[should_panic]
+	fn reject_value_overflowing_element() {
+		encode_packed_array(4, &[16]);
+	}
+}
@@ -1,7 +1,9 @@
 use lib::*;
+use super::util::Error;
+use super::ValueType;
 
 /// Param type subset generatable by WASM contract
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ParamType {
 	// Unsigned integer (mapped from u32)
 	U32,
@@ -15,6 +17,10 @@ pub enum ParamType {
 	Address,
 	// 256-bit unsigned integer (mapped from U256)
 	U256,
+	// Unsigned integer of the given bit width, multiple of 8 up to 256
+	Uint(usize),
+	// Signed integer of the given bit width, multiple of 8 up to 256
+	Int(usize),
 	// 256-bit hash (mapped from H256)
 	H256,
 	// Byte array (mapped from Vec<u8>)
@@ -25,9 +31,32 @@ pub enum ParamType {
 	Bool,
 	// String (mapped from String/str)
 	String,
+	// Fixed-length array of the given length
+	FixedArray(ArrayRef, usize),
+	// Fixed-length byte string of the given length, 1 to 32 bytes (mapped from [u8; N])
+	FixedBytes(usize),
+	// Tuple of params (solidity struct)
+	Tuple(Cow<'static, [ParamType]>),
 }
 
 impl ParamType {
+	/// Whether values of this type are encoded in the tail, with only an offset in the head
+	pub fn is_dynamic(&self) -> bool {
+		match *self {
+			ParamType::Bytes | ParamType::String | ParamType::Array(_) => true,
+			ParamType::FixedArray(ref p, _) => p.as_ref().is_dynamic(),
+			ParamType::Tuple(ref members) => members.iter().any(ParamType::is_dynamic),
+			_ => false,
+		}
+	}
+
+	/// Canonical type name, e.g. `uint256[2]`, the inverse of `from_str`
+	pub fn to_string_type(&self) -> String {
+		let mut s = String::new();
+		self.to_member(&mut s);
+		s
+	}
+
 	pub fn to_member(&self, s: &mut String) {
 		match *self {
 			ParamType::I32 => s.push_str("int32"),
@@ -36,12 +65,186 @@ impl ParamType {
 			ParamType::U64 => s.push_str("uint64"),
 			ParamType::Address => s.push_str("address"),
 			ParamType::U256 => s.push_str("uint256"),
+			ParamType::Uint(bits) => {
+				s.push_str("uint");
+				s.push_str(&bits.to_string());
+			},
+			ParamType::Int(bits) => {
+				s.push_str("int");
+				s.push_str(&bits.to_string());
+			},
 			ParamType::H256 => s.push_str("uint256"),
 			ParamType::Bytes => s.push_str("bytes"),
 			ParamType::Bool => s.push_str("bool"),
 			ParamType::String => s.push_str("string"),
+			ParamType::FixedBytes(len) => {
+				s.push_str("bytes");
+				s.push_str(&len.to_string());
+			},
 			ParamType::Array(ref p_n) => { p_n.as_ref().to_member(s); s.push_str("[]"); },
+			ParamType::FixedArray(ref p_n, len) => {
+				p_n.as_ref().to_member(s);
+				s.push('[');
+				s.push_str(&len.to_string());
+				s.push(']');
+			},
+			ParamType::Tuple(ref members) => {
+				s.push('(');
+				for (i, p) in members.iter().enumerate() {
+					if i != 0 { s.push(','); }
+					p.to_member(s);
+				}
+				s.push(')');
+			},
+		}
+	}
+}
+
+impl str::FromStr for ParamType {
+	type Err = Error;
+
+	/// Parses canonical type name, as produced by `to_member`
+	fn from_str(s: &str) -> Result<ParamType, Error> {
+		if s.ends_with(']') {
+			let open = s.rfind('[').ok_or(Error::InvalidParamType)?;
+			let inner: ParamType = s[..open].parse()?;
+			return match &s[open + 1..s.len() - 1] {
+				"" => Ok(ParamType::Array(inner.into())),
+				len => {
+					Ok(ParamType::FixedArray(inner.into(), decimal(len)?))
+				},
+			};
+		}
+
+		if s.starts_with('(') && s.ends_with(')') {
+			let members = split_members(&s[1..s.len() - 1])?
+				.into_iter()
+				.map(str::parse)
+				.collect::<Result<Vec<ParamType>, Error>>()?;
+			return Ok(ParamType::Tuple(members.into()));
+		}
+
+		Ok(match s {
+			"uint32" => ParamType::U32,
+			"uint64" => ParamType::U64,
+			"int32" => ParamType::I32,
+			"int64" => ParamType::I64,
+			"address" => ParamType::Address,
+			"uint256" | "uint" => ParamType::U256,
+			"int" => ParamType::Int(256),
+			"bytes" => ParamType::Bytes,
+			"bool" => ParamType::Bool,
+			"string" => ParamType::String,
+			_ if s.starts_with("uint") => ParamType::Uint(int_bits(&s[4..])?),
+			_ if s.starts_with("int") => ParamType::Int(int_bits(&s[3..])?),
+			_ if s.starts_with("bytes") => {
+				let len = decimal(&s[5..])?;
+				if len == 0 || len > 32 {
+					return Err(Error::InvalidParamType);
+				}
+				ParamType::FixedBytes(len)
+			},
+			_ => return Err(Error::InvalidParamType),
+		})
+	}
+}
+
+/// Parses bit width of sized integer types, a multiple of 8 up to 256
+fn int_bits(s: &str) -> Result<usize, Error> {
+	match decimal(s) {
+		Ok(bits) if bits != 0 && bits <= 256 && bits.is_multiple_of(8) => Ok(bits),
+		_ => Err(Error::InvalidParamType),
+	}
+}
+
+/// Parses a width or length written in plain decimal, without sign or leading zeros like Solidity
+fn decimal(s: &str) -> Result<usize, Error> {
+	if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) || (s.len() > 1 && s.starts_with('0')) {
+		return Err(Error::InvalidParamType);
+	}
+	s.parse().map_err(|_| Error::InvalidParamType)
+}
+
+/// Splits comma separated tuple members, ignoring commas of nested tuples
+fn split_members(s: &str) -> Result<Vec<&str>, Error> {
+	if s.is_empty() {
+		return Ok(Vec::new());
+	}
+
+	let mut members = Vec::new();
+	let mut depth = 0usize;
+	let mut start = 0;
+	for (i, c) in s.char_indices() {
+		match c {
+			'(' => depth += 1,
+			')' => depth = depth.checked_sub(1).ok_or(Error::InvalidParamType)?,
+			',' if depth == 0 => {
+				members.push(&s[start..i]);
+				start = i + 1;
+			},
+			_ => {},
+		}
+	}
+	if depth != 0 {
+		return Err(Error::InvalidParamType);
+	}
+	members.push(&s[start..]);
+	Ok(members)
+}
+
+/// Rust type passed as a single param, implemented with `#[derive(AbiType)]` for structs
+///
+/// Structs are encoded as tuples of their fields, in declaration order.
+pub trait AbiType: Into<ValueType> + From<ValueType> {
+	/// Param type of the values, a constant so that dispatch tables using it stay static
+	const PARAM_TYPE: ParamType;
+
+	/// ABI JSON of the fields, listed as `components` of the tuple param,
+	/// e.g. `{"name":"price","type":"uint256"},{"name":"maker","type":"address"}`
+	const COMPONENTS_JSON: &'static str;
+
+	fn param_type() -> ParamType {
+		Self::PARAM_TYPE
+	}
+}
+
+/// Length of `pieces` joined together, the size of the `const_concat` buffer
+pub const fn const_concat_len(pieces: &[&str]) -> usize {
+	let mut len = 0;
+	let mut i = 0;
+	while i < pieces.len() {
+		len += pieces[i].len();
+		i += 1;
+	}
+	len
+}
+
+/// Joins `pieces` in a constant, e.g. ABI JSON listing the `COMPONENTS_JSON` of `AbiType` structs
+///
+/// `N` should be `const_concat_len(pieces)`, read the result with `const_str`.
+pub const fn const_concat<const N: usize>(pieces: &[&str]) -> [u8; N] {
+	assert!(const_concat_len(pieces) == N, "buffer should be as long as the joined pieces");
+	let mut bytes = [0u8; N];
+	let mut len = 0;
+	let mut i = 0;
+	while i < pieces.len() {
+		let piece = pieces[i].as_bytes();
+		let mut j = 0;
+		while j < piece.len() {
+			bytes[len] = piece[j];
+			len += 1;
+			j += 1;
 		}
+		i += 1;
+	}
+	bytes
+}
+
+/// Bytes joined by `const_concat` as a string, they are whole strings one after another
+pub const fn const_str(bytes: &[u8]) -> &str {
+	match str::from_utf8(bytes) {
+		Ok(s) => s,
+		Err(_) => panic!("joined pieces should be valid utf-8"),
 	}
 }
 
@@ -60,8 +263,89 @@ impl ArrayRef {
 	}
 }
 
+impl PartialEq for ArrayRef {
+	fn eq(&self, other: &ArrayRef) -> bool {
+		self.as_ref() == other.as_ref()
+	}
+}
+
 impl From<ParamType> for ArrayRef {
 	fn from(p: ParamType) -> Self {
 		ArrayRef::Owned(Box::new(p))
 	}
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{ParamType, const_concat_len, const_concat, const_str};
+	use super::super::Error;
+
+	fn roundtrip(s: &str) {
+		let param: ParamType = s.parse().unwrap();
+		assert_eq!(param.to_string_type(), s);
+	}
+
+	#[test]
+	fn parse_param_types() {
+		roundtrip("uint32");
+		roundtrip("int64");
+		roundtrip("address[]");
+		roundtrip("uint256[2][]");
+		roundtrip("(uint256,bool)[2]");
+		roundtrip("(address,(string,bytes[]))");
+		roundtrip("()");
+		roundtrip("bytes4");
+		roundtrip("uint8");
+		roundtrip("int24[]");
+		roundtrip("(uint128,int256)");
+		roundtrip("bytes32[]");
+
+		assert_eq!("uint".parse::<ParamType>(), Ok(ParamType::U256));
+		assert_eq!("uint128".parse::<ParamType>(), Ok(ParamType::Uint(128)));
+		assert_eq!("int".parse::<ParamType>(), Ok(ParamType::Int(256)));
+	}
+
+	#[test]
+	fn parse_invalid_param_types() {
+		assert_eq!("uint7".parse::<ParamType>(), Err(Error::InvalidParamType));
+		assert_eq!("int0".parse::<ParamType>(), Err(Error::InvalidParamType));
+		assert_eq!("uint264".parse::<ParamType>(), Err(Error::InvalidParamType));
+		assert_eq!("bytes0".parse::<ParamType>(), Err(Error::InvalidParamType));
+		assert_eq!("bytes33".parse::<ParamType>(), Err(Error::InvalidParamType));
+		assert_eq!("uint256[x]".parse::<ParamType>(), Err(Error::InvalidParamType));
+		assert_eq!("uint+8".parse::<ParamType>(), Err(Error::InvalidParamType));
+		assert_eq!("int064".parse::<ParamType>(), Err(Error::InvalidParamType));
+		assert_eq!("bytes+4".parse::<ParamType>(), Err(Error::InvalidParamType));
+		assert_eq!("bytes04".parse::<ParamType>(), Err(Error::InvalidParamType));
+		assert_eq!("bytes00".parse::<ParamType>(), Err(Error::InvalidParamType));
+		assert_eq!("uint256[+2]".parse::<ParamType>(), Err(Error::InvalidParamType));
+		assert_eq!("uint256[02]".parse::<ParamType>(), Err(Error::InvalidParamType));
+		assert_eq!("(uint256,bool".parse::<ParamType>(), Err(Error::InvalidParamType));
+		assert_eq!("(uint256))(".parse::<ParamType>(), Err(Error::InvalidParamType));
+	}
+
+	#[test]
+	fn dynamic_types() {
+		let parse = |s: &str| s.parse::<ParamType>().unwrap();
+
+		assert!(!parse("uint256").is_dynamic());
+		assert!(!parse("bytes32").is_dynamic());
+		assert!(parse("bytes").is_dynamic());
+		assert!(parse("uint256[]").is_dynamic());
+		assert!(!parse("address[3]").is_dynamic());
+		assert!(parse("string[3]").is_dynamic());
+		assert!(parse("uint8[][2]").is_dynamic());
+		assert!(!ParamType::Tuple(vec![ParamType::U256, ParamType::FixedArray(ParamType::Bool.into(), 2)].into()).is_dynamic());
+		assert!(ParamType::Tuple(vec![ParamType::U256, ParamType::FixedArray(ParamType::String.into(), 2)].into()).is_dynamic());
+	}
+
+	#[test]
+	fn concat_in_constant() {
+		const PIECES: &'static [&'static str] = &[r#"{"name":"price","type":"uint"#, "256", "", r#""}"#];
+		const BYTES: [u8; const_concat_len(PIECES)] = const_concat(PIECES);
+		const JOINED: &'static str = const_str(&BYTES);
+
+		assert_eq!(JOINED, r#"{"name":"price","type":"uint256"}"#);
+		assert_eq!(const_concat_len(&[]), 0);
+	}
+}
@@ -0,0 +1,70 @@
+//! Solidity revert data classification
+
+use lib::*;
+use byteorder::{BigEndian, ByteOrder};
+use super::{ParamType, ValueType};
+use super::decode::decode;
+
+/// Selector of `Error(string)`, used by `revert("reason")` and failed `require`
+pub const ERROR_SELECTOR: u32 = 0x08c379a0;
+
+/// Selector of `Panic(uint256)`, used by failed `assert`, overflows, division by zero, etc.
+pub const PANIC_SELECTOR: u32 = 0x4e487b71;
+
+fn payload(data: &[u8], selector: u32) -> Option<&[u8]> {
+	if data.len() < 4 || BigEndian::read_u32(&data[0..4]) != selector {
+		return None;
+	}
+	Some(&data[4..])
+}
+
+/// Reason of `Error(string)` revert data, `None` if data is not an (ABI valid) error
+pub fn decode_revert_reason(data: &[u8]) -> Option<String> {
+	let payload = payload(data, ERROR_SELECTOR)?;
+	match decode(&[ParamType::String], payload).ok()?.pop() {
+		Some(ValueType::String(reason)) => Some(reason),
+		_ => None,
+	}
+}
+
+/// Code of `Panic(uint256)` revert data (e.g. `0x11` for arithmetic overflow),
+/// `None` if data is not a panic or the code doesn't fit into u64
+pub fn decode_panic_code(data: &[u8]) -> Option<u64> {
+	let payload = payload(data, PANIC_SELECTOR)?;
+	match decode(&[ParamType::U64], payload).ok()?.pop() {
+		Some(ValueType::U64(code)) => Some(code),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	extern crate rustc_hex as hex;
+
+	use self::hex::FromHex;
+	use super::{decode_revert_reason, decode_panic_code};
+
+	#[test]
+	fn error_string() {
+		let data = ("".to_owned() +
+			"08c379a0" +
+			"0000000000000000000000000000000000000000000000000000000000000020" +
+			"0000000000000000000000000000000000000000000000000000000000000012" +
+			"496e73756666696369656e742066756e64730000000000000000000000000000").from_hex().unwrap();
+
+		assert_eq!(decode_revert_reason(&data), Some("Insufficient funds".to_owned()));
+		assert_eq!(decode_panic_code(&data), None);
+	}
+
+	#[test]
+	fn panic_code() {
+		let data = ("".to_owned() +
+			"4e487b71" +
+			"0000000000000000000000000000000000000000000000000000000000000011").from_hex().unwrap();
+
+		assert_eq!(decode_panic_code(&data), Some(0x11));
+		assert_eq!(decode_revert_reason(&data), None);
+		assert_eq!(decode_panic_code(&data[..4]), None);
+		assert_eq!(decode_panic_code(&[]), None);
+	}
+}
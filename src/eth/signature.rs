@@ -8,6 +8,9 @@ use super::encode::encode;
 pub struct Signature {
     pub params: Cow<'static, [ParamType]>,
     pub result: Option<ParamType>,
+    /// Whether `result` is a `Tuple` of multiple return values, encoded like params instead of
+    /// as a single tuple (which is at an offset if dynamic)
+    pub multiple_results: bool,
 }
 
 impl Signature {
@@ -18,6 +21,7 @@ impl Signature {
         Signature {
             params: params.into(),
             result: result,
+            multiple_results: false,
         }
     }
 
@@ -27,6 +31,21 @@ impl Signature {
         Signature {
             params: params.into(),
             result: None,
+            multiple_results: false,
+        }
+    }
+
+    /// Signature returning several values, `results` become members of a `Tuple` result
+    pub fn new_multi<T>(params: T, results: Vec<ParamType>) -> Self
+        where T: Into<Cow<'static, [ParamType]>>
+    {
+        Signature {
+            params: params.into(),
+            multiple_results: !results.is_empty(),
+            result: match results.is_empty() {
+                true => None,
+                false => Some(ParamType::Tuple(results.into())),
+            },
         }
     }
 
@@ -35,7 +54,15 @@ impl Signature {
     }
 
     pub fn decode_result(&self, payload: &[u8]) -> Result<Option<ValueType>, Error> {
-        let mut result = decode(self.params.as_ref(), payload)?;
+        match self.result {
+            Some(ParamType::Tuple(ref types)) if self.multiple_results => {
+                return Ok(Some(ValueType::Tuple(decode(types, payload)?)));
+            },
+            _ => {},
+        }
+
+        let types = self.result.as_slice();
+        let mut result = decode(types, payload)?;
         match (&self.result, result.pop()) {
             (&Some(_), Some(val)) => {
                 Ok(Some(val))
@@ -51,14 +78,38 @@ impl Signature {
 
     pub fn encode_result(&self, result: Option<ValueType>) -> Result<Vec<u8>, Error> {
         match (result, &self.result) {
+            (Some(ValueType::Unit), &Some(_)) => Err(Error::ResultCantFit),
+            // multiple return values are laid out as a standalone payload, without an offset
+            (Some(ValueType::Tuple(values)), &Some(ParamType::Tuple(_))) if self.multiple_results => Ok(encode(&values)),
             (Some(val), &Some(_)) => {
                 Ok(encode(&[val]))
             },
-            (None, &None) => Ok(Vec::new()),
+            (None, &None) | (Some(ValueType::Unit), &None) => Ok(Vec::new()),
             _ => Err(Error::ResultCantFit)
         }
     }
 
+    /// Checks that `values` can be encoded as the params of this signature, reporting the first mismatch
+    pub fn matches(&self, values: &[ValueType]) -> Result<(), Error> {
+        for index in 0..cmp::max(self.params.len(), values.len()) {
+            let expected = self.params.get(index);
+            let value = values.get(index);
+            let matched = match (expected, value) {
+                (Some(expected), Some(value)) => value_matches(value, expected),
+                _ => false,
+            };
+
+            if !matched {
+                return Err(Error::ArgumentMismatch {
+                    index: index,
+                    expected: expected.cloned(),
+                    got: value.and_then(ValueType::param_type),
+                });
+            }
+        }
+        Ok(())
+    }
+
     pub fn params(&self) -> &[ParamType] {
         self.params.as_ref()
     }
@@ -66,4 +117,329 @@ impl Signature {
     pub fn result(&self) -> Option<&ParamType> {
         self.result.as_ref()
     }
+
+    /// Whether `other` encodes params and result the same way, e.g. across a contract upgrade
+    ///
+    /// Only the layout is compared; selectors, which also depend on method names, are not.
+    pub fn is_compatible_with(&self, other: &Signature) -> bool {
+        self.params == other.params && self.result == other.result && self.multiple_results == other.multiple_results
+    }
+
+    /// Size in bytes of the head of encoded params: whole encoding of static params, plus
+    /// 32 bytes for the offset of each dynamic one
+    ///
+    /// Encoded params are exactly this long if none is dynamic, tails of dynamic ones follow
+    /// the head otherwise.
+    pub fn static_size(&self) -> usize {
+        self.params.iter().map(|param| static_size(param).unwrap_or(32)).sum()
+    }
+
+    /// Number of 32-byte slots in the head of encoded params (see `static_size`)
+    ///
+    /// Same as the number of params, unless static tuples or fixed arrays are inlined in the head.
+    pub fn head_count(&self) -> usize {
+        self.static_size() / 32
+    }
+
+    /// Size in bytes of the encoded result, `None` if it's dynamic (so only known after the call)
+    ///
+    /// Void methods return no data, `Some(0)`.
+    pub fn return_size_hint(&self) -> Option<usize> {
+        match self.result {
+            Some(ref result) => static_size(result),
+            None => Some(0),
+        }
+    }
+}
+
+/// Size in bytes of the encoding of a static param, `None` for dynamic ones
+fn static_size(param: &ParamType) -> Option<usize> {
+    if param.is_dynamic() {
+        return None;
+    }
+
+    match *param {
+        ParamType::FixedArray(ref t, len) => static_size(t.as_ref()).map(|size| size * len),
+        ParamType::Tuple(ref members) => {
+            members.iter().map(static_size).try_fold(0, |acc, size| Some(acc + size?))
+        },
+        _ => Some(32),
+    }
+}
+
+fn value_matches(value: &ValueType, param: &ParamType) -> bool {
+    match (value, param) {
+        (&ValueType::U32(_), &ParamType::U32) |
+        (&ValueType::U64(_), &ParamType::U64) |
+        (&ValueType::I32(_), &ParamType::I32) |
+        (&ValueType::I64(_), &ParamType::I64) |
+        (&ValueType::Address(_), &ParamType::Address) |
+        // both are `uint256` words, encoded the same way
+        (&ValueType::U256(_), &ParamType::U256) |
+        (&ValueType::U256(_), &ParamType::H256) |
+        (&ValueType::H256(_), &ParamType::U256) |
+        (&ValueType::H256(_), &ParamType::H256) |
+        (&ValueType::Bytes(_), &ParamType::Bytes) |
+        (&ValueType::Bool(_), &ParamType::Bool) |
+        (&ValueType::String(_), &ParamType::String) => true,
+        (&ValueType::FixedBytes(ref bytes), &ParamType::FixedBytes(len)) => bytes.len() == len,
+        (&ValueType::Uint(_, bits), &ParamType::Uint(expected)) |
+        (&ValueType::Int(_, bits), &ParamType::Int(expected)) => bits == expected,
+        (&ValueType::Array(ref values), &ParamType::Array(ref t)) => {
+            values.iter().all(|value| value_matches(value, t.as_ref()))
+        },
+        (&ValueType::FixedArray(ref values), &ParamType::FixedArray(ref t, len)) => {
+            values.len() == len && values.iter().all(|value| value_matches(value, t.as_ref()))
+        },
+        (&ValueType::Tuple(ref values), &ParamType::Tuple(ref members)) => {
+            values.len() == members.len() && values.iter().zip(members.iter()).all(|(v, p)| value_matches(v, p))
+        },
+        _ => false,
+    }
+}
+
+impl str::FromStr for Signature {
+    type Err = Error;
+
+    /// Parses params of a canonical signature like `transfer(address,uint256)`, the name is
+    /// optional and ignored (parse a `NamedSignature` to keep it)
+    ///
+    /// Canonical signatures don't mention the return type, the result is always `None`.
+    fn from_str(s: &str) -> Result<Signature, Error> {
+        let open = s.find('(').ok_or(Error::InvalidSignature)?;
+        if !s[..open].chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$') {
+            return Err(Error::InvalidSignature);
+        }
+        if !s.ends_with(')') {
+            return Err(Error::InvalidSignature);
+        }
+
+        match s[open..].parse()? {
+            ParamType::Tuple(params) => Ok(Signature::new_void(params.into_owned())),
+            // e.g. `f(uint256)[]`
+            _ => Err(Error::InvalidSignature),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Signature;
+    use super::super::{ParamType, ValueType, Error};
+    use super::super::encode::encode;
+
+    fn result_cases() -> Vec<(ParamType, ValueType)> {
+        let mut u8_word = [0u8; 32];
+        u8_word[31] = 0xff;
+
+        vec![
+            (ParamType::U32, ValueType::U32(0xdead_beef)),
+            (ParamType::U64, ValueType::U64(u64::MAX)),
+            (ParamType::I32, ValueType::I32(i32::MIN)),
+            (ParamType::I64, ValueType::I64(-5)),
+            (ParamType::Address, ValueType::Address([0x11u8; 20])),
+            (ParamType::U256, ValueType::U256([0x22u8; 32])),
+            (ParamType::H256, ValueType::H256([0x33u8; 32])),
+            (ParamType::Uint(8), ValueType::Uint(u8_word, 8)),
+            (ParamType::Int(256), ValueType::Int([0xffu8; 32], 256)),
+            (ParamType::Bool, ValueType::Bool(true)),
+            (ParamType::FixedBytes(4), ValueType::FixedBytes(vec![0xa9, 0x05, 0x9c, 0xbb])),
+            (ParamType::Bytes, ValueType::Bytes(vec![1, 2, 3])),
+            (ParamType::String, ValueType::String("gavofyork".to_owned())),
+            (ParamType::Array(ParamType::U32.into()), ValueType::Array(vec![ValueType::U32(1), ValueType::U32(2)])),
+            (
+                ParamType::FixedArray(ParamType::String.into(), 2),
+                ValueType::FixedArray(vec![ValueType::String("a".to_owned()), ValueType::String("b".to_owned())]),
+            ),
+            (
+                ParamType::Tuple(vec![ParamType::Bool, ParamType::Bytes].into()),
+                ValueType::Tuple(vec![ValueType::Bool(false), ValueType::Bytes(vec![0xff])]),
+            ),
+        ]
+    }
+
+    #[test]
+    fn result_round_trip() {
+        // a dynamic param in front, offsets of the result must not depend on it
+        let params = vec![ParamType::String];
+
+        for ((kind, value), (_, expected)) in result_cases().into_iter().zip(result_cases()) {
+            let signature = Signature::new(params.clone(), Some(kind));
+            let encoded = signature.encode_result(Some(value)).unwrap();
+            assert_eq!(signature.decode_result(&encoded), Ok(Some(expected)));
+        }
+
+        // all of them at once as multiple return values
+        let (kinds, values): (Vec<_>, Vec<_>) = result_cases().into_iter().unzip();
+        let signature = Signature::new_multi(params, kinds);
+        let encoded = signature.encode_result(Some(ValueType::Tuple(values))).unwrap();
+        let expected = result_cases().into_iter().map(|(_, value)| value).collect();
+        assert_eq!(signature.decode_result(&encoded), Ok(Some(ValueType::Tuple(expected))));
+    }
+
+    #[test]
+    fn dynamic_result_offsets() {
+        let signature = Signature::new(vec![ParamType::Bytes, ParamType::U32], Some(ParamType::String));
+        let encoded = signature.encode_result(Some(ValueType::String("gavofyork".to_owned()))).unwrap();
+
+        // offset of the tail counts from the start of the result, right past its single head word
+        assert_eq!(encoded.len(), 96);
+        assert_eq!(encoded[31], 0x20);
+        assert_eq!(encoded[63], 9);
+        assert_eq!(&encoded[64..73], b"gavofyork");
+
+        // a single dynamic tuple is at an offset too, offsets of its members count from its start
+        let signature = Signature::new(Vec::new(), Some(ParamType::Tuple(vec![ParamType::Bool, ParamType::Bytes].into())));
+        let encoded = signature.encode_result(Some(ValueType::Tuple(vec![ValueType::Bool(true), ValueType::Bytes(vec![0xff])]))).unwrap();
+        let mut expected = vec![0u8; 160];
+        expected[31] = 0x20;
+        expected[63] = 1;
+        expected[95] = 0x40;
+        expected[127] = 1;
+        expected[128] = 0xff;
+        assert_eq!(encoded, expected);
+
+        // multiple return values are laid out like params, offsets count from the first head word
+        let signature = Signature::new_multi(Vec::new(), vec![ParamType::U32, ParamType::Bytes]);
+        let encoded = signature.encode_result(Some(ValueType::Tuple(vec![ValueType::U32(7), ValueType::Bytes(vec![0xff])]))).unwrap();
+        assert_eq!(encoded.len(), 128);
+        assert_eq!(encoded[63], 0x40);
+        assert_eq!(encoded[95], 1);
+        assert_eq!(encoded[96], 0xff);
+    }
+
+    #[test]
+    fn parse() {
+        let signature: Signature = "transfer(address,uint256)".parse().unwrap();
+        assert_eq!(signature.params(), &[ParamType::Address, ParamType::U256][..]);
+        assert_eq!(signature.result(), None);
+
+        let signature: Signature = "batch(bytes,address[],uint32[2][],(bool,string))".parse().unwrap();
+        assert_eq!(signature.params(), &[
+            ParamType::Bytes,
+            ParamType::Array(ParamType::Address.into()),
+            ParamType::Array(ParamType::FixedArray(ParamType::U32.into(), 2).into()),
+            ParamType::Tuple(vec![ParamType::Bool, ParamType::String].into()),
+        ][..]);
+
+        assert!("ping()".parse::<Signature>().unwrap().params().is_empty());
+        assert!("(uint64)".parse::<Signature>().is_ok());
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert_eq!("transfer(address,uint255)".parse::<Signature>().err(), Some(Error::InvalidParamType));
+        assert_eq!("transfer(address,)".parse::<Signature>().err(), Some(Error::InvalidParamType));
+        assert_eq!("transfer".parse::<Signature>().err(), Some(Error::InvalidSignature));
+        assert_eq!("transfer(address".parse::<Signature>().err(), Some(Error::InvalidSignature));
+        assert_eq!("transfer(address)[]".parse::<Signature>().err(), Some(Error::InvalidSignature));
+        assert_eq!("my transfer(address)".parse::<Signature>().err(), Some(Error::InvalidSignature));
+    }
+
+    #[test]
+    fn matching_arguments() {
+        let signature = Signature::new_void(vec![
+            ParamType::Address,
+            ParamType::U256,
+            ParamType::Array(ParamType::U32.into()),
+        ]);
+
+        assert_eq!(signature.matches(&[
+            ValueType::Address([0x11u8; 20]),
+            ValueType::U256([0u8; 32]),
+            ValueType::Array(vec![ValueType::U32(1), ValueType::U32(2)]),
+        ]), Ok(()));
+        assert_eq!(signature.matches(&[
+            ValueType::Address([0x11u8; 20]),
+            ValueType::U256([0u8; 32]),
+            ValueType::Array(vec![]),
+        ]), Ok(()));
+    }
+
+    #[test]
+    fn mismatched_arguments() {
+        let signature = Signature::new_void(vec![ParamType::Address, ParamType::U256]);
+
+        assert_eq!(
+            signature.matches(&[ValueType::Address([0x11u8; 20]), ValueType::Bool(true)]),
+            Err(Error::ArgumentMismatch { index: 1, expected: Some(ParamType::U256), got: Some(ParamType::Bool) })
+        );
+        assert_eq!(
+            signature.matches(&[ValueType::Address([0x11u8; 20])]),
+            Err(Error::ArgumentMismatch { index: 1, expected: Some(ParamType::U256), got: None })
+        );
+        assert_eq!(
+            signature.matches(&[ValueType::Address([0x11u8; 20]), ValueType::U256([0u8; 32]), ValueType::U32(1)]),
+            Err(Error::ArgumentMismatch { index: 2, expected: None, got: Some(ParamType::U32) })
+        );
+    }
+
+    #[test]
+    fn return_size_hint() {
+        let returning = |result| Signature::new(Vec::new(), Some(result)).return_size_hint();
+
+        assert_eq!(returning(ParamType::Bool), Some(32));
+        assert_eq!(returning(ParamType::String), None);
+        assert_eq!(returning(ParamType::Tuple(vec![ParamType::U256, ParamType::Bool].into())), Some(64));
+        assert_eq!(returning(ParamType::FixedArray(ParamType::Address.into(), 3)), Some(96));
+        assert_eq!(returning(ParamType::Tuple(vec![ParamType::U256, ParamType::Bytes].into())), None);
+        assert_eq!(Signature::new_void(Vec::new()).return_size_hint(), Some(0));
+    }
+
+    #[test]
+    fn head_size() {
+        let signature = Signature::new_void(vec![
+            ParamType::Address,
+            ParamType::String,
+            ParamType::Tuple(vec![ParamType::U256, ParamType::Bool].into()),
+            ParamType::FixedArray(ParamType::U32.into(), 3),
+            ParamType::Array(ParamType::U32.into()),
+        ]);
+        assert_eq!(signature.static_size(), 32 * 8);
+        assert_eq!(signature.head_count(), 8);
+
+        let values = vec![
+            ValueType::Address([0x11u8; 20]),
+            ValueType::String(String::new()),
+            ValueType::Tuple(vec![ValueType::U256([0u8; 32]), ValueType::Bool(true)]),
+            ValueType::FixedArray(vec![ValueType::U32(1), ValueType::U32(2), ValueType::U32(3)]),
+            ValueType::Array(Vec::new()),
+        ];
+        // the empty string and array take a length word each in the tail
+        assert_eq!(signature.encode_invoke(&values).len(), signature.static_size() + 2 * 32);
+
+        assert_eq!(Signature::new_void(Vec::new()).head_count(), 0);
+    }
+
+    #[test]
+    fn multiple_results() {
+        let signature = Signature::new_multi(Vec::new(), vec![ParamType::U256, ParamType::String]);
+        let values = vec![ValueType::U256([0x11u8; 32]), ValueType::String("gavofyork".to_owned())];
+
+        // same as encoding the values as params, unlike a single tuple, which would be at an offset
+        let payload = signature.encode_result(Some(ValueType::Tuple(values))).unwrap();
+        let expected = encode(&[ValueType::U256([0x11u8; 32]), ValueType::String("gavofyork".to_owned())]);
+        assert_eq!(payload, expected);
+        assert_eq!(
+            signature.decode_result(&payload),
+            Ok(Some(ValueType::Tuple(vec![ValueType::U256([0x11u8; 32]), ValueType::String("gavofyork".to_owned())])))
+        );
+        assert!(Signature::new_multi(Vec::new(), Vec::new()).result().is_none());
+    }
+
+    #[test]
+    fn compatible_signatures() {
+        use super::super::{NamedSignature, HashSignature};
+
+        let old = Signature::new(vec![ParamType::Address, ParamType::U256], Some(ParamType::Bool));
+        assert!(old.is_compatible_with(&Signature::new(vec![ParamType::Address, ParamType::U256], Some(ParamType::Bool))));
+        assert!(!old.is_compatible_with(&Signature::new(vec![ParamType::Address, ParamType::U64], Some(ParamType::Bool))));
+        assert!(!old.is_compatible_with(&Signature::new(vec![ParamType::Address, ParamType::U256], None)));
+
+        // same layout under another name is compatible, the selector tells them apart
+        let transfer = HashSignature::from(NamedSignature::new("transfer", old.clone()));
+        let send = HashSignature::from(NamedSignature::new("send", old.clone()));
+        assert!(transfer.signature().is_compatible_with(send.signature()));
+        assert!(transfer.hash() != send.hash());
+    }
 }
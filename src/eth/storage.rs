@@ -0,0 +1,62 @@
+//! Solidity storage layout helpers
+
+use lib::*;
+use super::ValueType;
+use super::encode::encode;
+use super::util::{keccak256, Hash};
+
+/// Storage slot of `mapping[key]` for a mapping declared at `slot`
+///
+/// Value type keys are ABI-encoded into a single word, while `bytes` and `string`
+/// keys are hashed unpadded, the same way Solidity lays out mappings in storage.
+pub fn mapping_slot(slot: Hash, key: &ValueType) -> Hash {
+	let mut preimage = match *key {
+		ValueType::Bytes(ref bytes) => bytes.clone(),
+		ValueType::String(ref s) => s.as_bytes().to_vec(),
+		_ => encode(slice::from_ref(key)),
+	};
+	preimage.extend_from_slice(&slot);
+	keccak256(&preimage).into()
+}
+
+/// Storage slot of `mapping[outer][inner]` for a two-level mapping declared at `slot`
+pub fn nested_mapping_slot(slot: Hash, outer: &ValueType, inner: &ValueType) -> Hash {
+	mapping_slot(mapping_slot(slot, outer), inner)
+}
+
+#[cfg(test)]
+mod tests {
+	extern crate rustc_hex as hex;
+
+	use self::hex::FromHex;
+	use super::{mapping_slot, nested_mapping_slot};
+	use super::super::ValueType;
+	use super::super::util::{pad_u32, Hash};
+
+	fn hash(s: &str) -> Hash {
+		let mut result = [0u8; 32];
+		result.copy_from_slice(&s.from_hex().unwrap());
+		result
+	}
+
+	#[test]
+	fn address_key() {
+		let slot = mapping_slot(pad_u32(0), &ValueType::Address([0u8; 20]));
+		assert_eq!(slot, hash("ad3228b676f7d3cd4284a5443f17f1962b36e491b30a40b2405849e597ba5fb5"));
+
+		let slot = mapping_slot(pad_u32(1), &ValueType::Address([0x11u8; 20]));
+		assert_eq!(slot, hash("8eec1c9afb183a84aac7003cf8e730bfb6385f6e43761d6425fba4265de3a9eb"));
+	}
+
+	#[test]
+	fn string_key() {
+		let slot = mapping_slot(pad_u32(2), &ValueType::String("abc".to_owned()));
+		assert_eq!(slot, hash("31f76c90c4bd232b01bb0bd40689518175171c0bb64d053d77a6e90319d96718"));
+	}
+
+	#[test]
+	fn nested_key() {
+		let slot = nested_mapping_slot(pad_u32(3), &ValueType::Address([0x11u8; 20]), &ValueType::U32(5));
+		assert_eq!(slot, hash("9bb71377f6843c8cd36bc18da43449102f1975cd0358f61eb7e54a68e4eec479"));
+	}
+}
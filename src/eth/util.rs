@@ -1,18 +1,95 @@
 use lib::*;
+use tiny_keccak::Keccak;
+use parity_hash::H256;
+use super::ParamType;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum Error {
 	UnknownSignature,
 	NoLengthForSignature,
 	NoFallback,
 	ResultCantFit,
-	UnexpectedEnd,
+	/// Payload ends before `position`, the byte offset up to which a value had to be read
+	UnexpectedEnd { position: usize },
 	InvalidPadding,
+	/// Integer word has bits set outside of the range of its `width` bits wide type
+	IntegerOverflow { width: usize },
+	/// Payload length is not a multiple of 32, `expected` is the length rounded up to the next word
+	LengthMismatch { expected: usize, got: usize },
 	InvalidUtf8,
+	OverlappingRegions,
+	/// Offset of a dynamic value exceeds the addressable range (doesn't fit into u32)
+	OffsetTooLarge,
+	/// Payload decodes fine but is not the canonical encoding of its values
+	NonCanonical,
+	TrailingData { remaining: usize },
+	/// Value at `index` doesn't match the signature (`None` for a missing/extra param, or a value without param type)
+	ArgumentMismatch { index: usize, expected: Option<ParamType>, got: Option<ParamType> },
+	/// Signature has more top-level params than the decoder allows
+	TooManyParams { count: usize, max: usize },
+	/// Type name is malformed or not supported by this crate
+	InvalidParamType,
+	/// ABI JSON is malformed or misses required fields
+	InvalidAbiJson,
+	/// Log topics don't match the event: other signature topic or number of indexed params
+	InvalidTopics,
+	/// Value (or one of its elements) is not of the variant the conversion expects
+	InvalidConversion,
+	/// Hex string has an odd length or a character other than a hex digit
+	InvalidHex,
+	/// Signature string is not of the canonical `name(type,type)` form
+	InvalidSignature,
+	/// Table already has a signature with selector `hash`
+	DuplicateSelector { hash: u32 },
 }
 
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Error::UnknownSignature => write!(f, "unknown method signature"),
+			Error::NoLengthForSignature => write!(f, "payload is too short to hold a method signature"),
+			Error::NoFallback => write!(f, "no fallback method"),
+			Error::ResultCantFit => write!(f, "result does not match the method return type"),
+			Error::UnexpectedEnd { position } => write!(f, "payload ends before byte {}", position),
+			Error::InvalidPadding => write!(f, "invalid padding"),
+			Error::IntegerOverflow { width } => write!(f, "integer exceeds the range of its {}-bit type", width),
+			Error::LengthMismatch { expected, got } => {
+				write!(f, "payload length {} is not a multiple of 32, expected {}", got, expected)
+			},
+			Error::InvalidUtf8 => write!(f, "string is not valid UTF-8"),
+			Error::OverlappingRegions => write!(f, "tails of dynamic values overlap"),
+			Error::OffsetTooLarge => write!(f, "offset of a dynamic value is out of range"),
+			Error::NonCanonical => write!(f, "payload is not canonically encoded"),
+			Error::TrailingData { remaining } => write!(f, "{} bytes left after the last value", remaining),
+			Error::ArgumentMismatch { index, ref expected, ref got } => {
+				write!(f, "argument {} mismatch: expected {:?}, got {:?}", index, expected, got)
+			},
+			Error::TooManyParams { count, max } => write!(f, "{} params exceed the limit of {}", count, max),
+			Error::InvalidParamType => write!(f, "invalid or unsupported param type"),
+			Error::InvalidAbiJson => write!(f, "invalid ABI JSON"),
+			Error::InvalidTopics => write!(f, "log topics do not match the event"),
+			Error::InvalidConversion => write!(f, "value can't be converted to the requested type"),
+			Error::InvalidHex => write!(f, "invalid hex string"),
+			Error::InvalidSignature => write!(f, "invalid signature string"),
+			Error::DuplicateSelector { hash } => write!(f, "selector 0x{:08x} is already in the table", hash),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for Error {}
+
 pub type Hash = [u8; 32];
 
+/// Keccak-256 digest of the given data, as used for selectors, event topics and storage keys
+pub fn keccak256(data: &[u8]) -> H256 {
+	let mut keccak = Keccak::new_keccak256();
+	let mut res = H256::zero();
+	keccak.update(data);
+	keccak.finalize(res.as_mut());
+	res
+}
+
 /// Converts u32 to right aligned array of 32 bytes.
 pub fn pad_u32(value: u32) -> Hash {
 	let mut padded = [0u8; 32];
@@ -37,41 +114,29 @@ pub fn pad_u64(value: u64) -> Hash {
 	padded
 }
 
+/// Converts i128 to right aligned array of 32 bytes, sign extended to the full word.
+///
+/// Signed values of any width up to 128 bits are encoded through this, so that negative
+/// ones are the same words as their `int256` counterparts.
+pub fn pad_i128(value: i128) -> Hash {
+	let mut padded = if value < 0 { [0xffu8; 32] } else { [0u8; 32] };
+	padded[16..].copy_from_slice(&value.to_be_bytes());
+	padded
+}
+
 /// Converts i64 to right aligned array of 32 bytes.
 pub fn pad_i64(value: i64) -> Hash {
-	if value >= 0 {
-		return pad_u64(value as u64);
-	}
-
-	let mut padded = [0xffu8; 32];
-	padded[24] = (value >> 56) as u8;
-	padded[25] = (value >> 48) as u8;
-	padded[26] = (value >> 40) as u8;
-	padded[27] = (value >> 32) as u8;
-	padded[28] = (value >> 24) as u8;
-	padded[29] = (value >> 16) as u8;
-	padded[30] = (value >> 8) as u8;
-	padded[31] = value as u8;
-	padded
+	pad_i128(value as i128)
 }
 
 /// Converts i32 to right aligned array of 32 bytes.
 pub fn pad_i32(value: i32) -> Hash {
-	if value >= 0 {
-		return pad_u32(value as u32);
-	}
-
-	let mut padded = [0xffu8; 32];
-	padded[28] = (value >> 24) as u8;
-	padded[29] = (value >> 16) as u8;
-	padded[30] = (value >> 8) as u8;
-	padded[31] = value as u8;
-	padded
+	pad_i128(value as i128)
 }
 
 pub fn as_u32(slice: &Hash) -> Result<u32, Error> {
 	if !slice[..28].iter().all(|x| *x == 0) {
-		return Err(Error::InvalidPadding);
+		return Err(Error::IntegerOverflow { width: 32 });
 	}
 
 	let result = ((slice[28] as u32) << 24) +
@@ -82,30 +147,18 @@ pub fn as_u32(slice: &Hash) -> Result<u32, Error> {
 	Ok(result)
 }
 
+/// Reads a two's complement word as `i32`, all bits above the lowest 32 should be copies of its sign bit
 pub fn as_i32(slice: &Hash) -> Result<i32, Error> {
-	let is_negative = slice[0] & 0x80 != 0;
-
-	if !is_negative {
-		return Ok(as_u32(slice)? as i32);
-	}
-
-	// only negative path here
-
-	if !slice[1..28].iter().all(|x| *x == 0xff) {
-		return Err(Error::InvalidPadding);
-	}
-
-	let result = ((slice[28] as u32) << 24) +
-		((slice[29] as u32) << 16) +
-		((slice[30] as u32) << 8) +
-		(slice[31] as u32);
+	check_int(slice, 32)?;
 
-	Ok(-(result as i32))
+	let mut low = [0u8; 4];
+	low.copy_from_slice(&slice[28..]);
+	Ok(i32::from_be_bytes(low))
 }
 
 pub fn as_u64(slice: &Hash) -> Result<u64, Error> {
 	if !slice[..24].iter().all(|x| *x == 0) {
-		return Err(Error::InvalidPadding);
+		return Err(Error::IntegerOverflow { width: 64 });
 	}
 
 	let result =
@@ -121,30 +174,43 @@ pub fn as_u64(slice: &Hash) -> Result<u64, Error> {
 	Ok(result)
 }
 
+/// Reads a two's complement word as `i64`, all bits above the lowest 64 should be copies of its sign bit
 pub fn as_i64(slice: &Hash) -> Result<i64, Error> {
-	let is_negative = slice[0] & 0x80 != 0;
+	check_int(slice, 64)?;
 
-	if !is_negative {
-		return Ok(as_u64(slice)? as i64);
-	}
-
-	// only negative path here
+	let mut low = [0u8; 8];
+	low.copy_from_slice(&slice[24..]);
+	Ok(i64::from_be_bytes(low))
+}
 
-	if !slice[1..28].iter().all(|x| *x == 0xff) {
-		return Err(Error::InvalidPadding);
+/// Checks that `slice` holds a `uint<bits>`: every bit above the lowest `bits` is zero
+pub fn check_uint(slice: &Hash, bits: usize) -> Result<(), Error> {
+	let padding = padding_len(bits)?;
+	if !slice[..padding].iter().all(|x| *x == 0) {
+		return Err(Error::IntegerOverflow { width: bits });
 	}
+	Ok(())
+}
 
-	let result =
-		((slice[24] as u64) << 56) +
-		((slice[25] as u64) << 48) +
-		((slice[26] as u64) << 40) +
-		((slice[27] as u64) << 32) +
-		((slice[28] as u64) << 24) +
-		((slice[29] as u64) << 16) +
-		((slice[30] as u64) << 8) +
-		 (slice[31] as u64);
+/// Checks that `slice` holds an `int<bits>`: every bit above the lowest `bits` is a copy of the sign bit
+pub fn check_int(slice: &Hash, bits: usize) -> Result<(), Error> {
+	let padding = padding_len(bits)?;
+	if padding == 0 {
+		return Ok(());
+	}
+	let extension = if slice[padding] & 0x80 != 0 { 0xff } else { 0 };
+	if !slice[..padding].iter().all(|x| *x == extension) {
+		return Err(Error::IntegerOverflow { width: bits });
+	}
+	Ok(())
+}
 
-	Ok(-(result as i64))
+/// Number of leading padding bytes of a `bits` wide integer word
+fn padding_len(bits: usize) -> Result<usize, Error> {
+	if bits == 0 || bits > 256 || !bits.is_multiple_of(8) {
+		return Err(Error::InvalidParamType);
+	}
+	Ok(32 - bits / 8)
 }
 
 pub fn as_bool(slice: &Hash) -> Result<bool, Error> {
@@ -154,3 +220,76 @@ pub fn as_bool(slice: &Hash) -> Result<bool, Error> {
 
 	Ok(slice[31] == 1)
 }
+
+/// Hex encoding of call data and words, without the `rustc-hex` dependency (so it's available without `std`)
+pub mod hex {
+	use lib::*;
+	use super::Error;
+
+	const DIGITS: &'static [u8; 16] = b"0123456789abcdef";
+
+	/// Lowercase hex digits of `data`, without `0x` prefix
+	pub fn to_hex(data: &[u8]) -> String {
+		let mut hex = String::with_capacity(data.len() * 2);
+		for byte in data {
+			hex.push(DIGITS[(byte >> 4) as usize] as char);
+			hex.push(DIGITS[(byte & 0x0f) as usize] as char);
+		}
+		hex
+	}
+
+	/// Bytes of hex string `s`, with or without `0x` prefix, digits of either case
+	pub fn from_hex(s: &str) -> Result<Vec<u8>, Error> {
+		let digits = s.strip_prefix("0x").unwrap_or(s).as_bytes();
+		if digits.len() % 2 == 1 {
+			return Err(Error::InvalidHex);
+		}
+
+		digits.chunks(2)
+			.map(|pair| Ok((digit(pair[0])? << 4) | digit(pair[1])?))
+			.collect()
+	}
+
+	fn digit(c: u8) -> Result<u8, Error> {
+		match c {
+			b'0'..=b'9' => Ok(c - b'0'),
+			b'a'..=b'f' => Ok(c - b'a' + 10),
+			b'A'..=b'F' => Ok(c - b'A' + 10),
+			_ => Err(Error::InvalidHex),
+		}
+	}
+
+	#[cfg(test)]
+	mod tests {
+		use super::{to_hex, from_hex};
+		use super::super::Error;
+
+		#[test]
+		fn round_trip() {
+			let data = [0x00u8, 0x01, 0xa9, 0x05, 0x9c, 0xbb, 0xff];
+			assert_eq!(to_hex(&data), "0001a9059cbbff");
+			assert_eq!(from_hex("0001a9059cbbff"), Ok(data.to_vec()));
+			assert_eq!(from_hex("0x0001A9059CBBFF"), Ok(data.to_vec()));
+			assert_eq!(to_hex(&[]), "");
+			assert_eq!(from_hex("0x"), Ok(Vec::new()));
+		}
+
+		#[test]
+		fn invalid_hex() {
+			assert_eq!(from_hex("abc"), Err(Error::InvalidHex));
+			assert_eq!(from_hex("0xzz"), Err(Error::InvalidHex));
+			assert_eq!(from_hex("0x 1"), Err(Error::InvalidHex));
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{keccak256, hex};
+
+	#[test]
+	fn keccak256_known_digests() {
+		assert_eq!(hex::to_hex(keccak256(b"").as_ref()), "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470");
+		assert_eq!(hex::to_hex(&keccak256(b"transfer(address,uint256)")[0..4]), "a9059cbb");
+	}
+}
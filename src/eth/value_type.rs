@@ -1,12 +1,18 @@
 //! Typed value module
 
 use lib::*;
-use bigint::U256;
+use bigint::{U256, FromDecStrErr};
 use parity_hash::H256;
 use parity_hash::Address;
+use super::{ParamType, Error};
+use super::util::hex::to_hex;
 
 /// Typed value
-#[derive(Debug, PartialEq)]
+///
+/// Formatted with `Display` the way solidity tooling shows values: addresses, hashes and byte
+/// strings as `0x` prefixed hex, integers in decimal, arrays and tuples in brackets and
+/// parentheses. `Debug` prints the same with the variant names around.
+#[derive(PartialEq, Eq, Hash)]
 pub enum ValueType {
     U32(u32),
     U64(u64),
@@ -19,6 +25,436 @@ pub enum ValueType {
     Array(Vec<ValueType>),
     Bool(bool),
     String(String),
+    /// No value, used as the return of void methods (encodes to zero bytes)
+    Unit,
+    /// Tuple of values (mapped to a solidity struct)
+    Tuple(Vec<ValueType>),
+    /// Fixed-length array of values
+    FixedArray(Vec<ValueType>),
+    /// Fixed-length byte string of at most 32 bytes (left aligned in its word)
+    FixedBytes(Vec<u8>),
+    /// Unsigned integer word of the given bit width
+    Uint([u8; 32], usize),
+    /// Signed integer word (sign extended to 256 bits) of the given bit width
+    Int([u8; 32], usize),
+}
+
+/// Error parsing a numeric value from a string
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    /// Empty input or a character that is not a digit of the used radix
+    InvalidCharacter,
+    /// Value does not fit into 256 bits
+    Overflow,
+}
+
+/// Error of arithmetic on `ValueType::U256` values
+#[derive(Debug, PartialEq)]
+pub enum MathError {
+    /// Result exceeds 256 bits
+    Overflow,
+    /// Result is negative
+    Underflow,
+    /// Operand is not a `ValueType::U256`
+    InvalidOperand,
+}
+
+/// Error converting a `ValueType` into a Rust type with `TryFromValue`
+#[derive(Debug, PartialEq)]
+pub enum ValueError {
+    /// Value is of another type, e.g. `U32` for a `bool`
+    TypeMismatch,
+    /// Byte string or fixed array of another length
+    LengthMismatch { expected: usize, got: usize },
+}
+
+impl From<ValueError> for Error {
+    fn from(_: ValueError) -> Error {
+        Error::InvalidConversion
+    }
+}
+
+/// Fallible counterpart of the `From<ValueType>` conversions
+///
+/// Generated dispatch code converts arguments with `From`, which panics on a mismatch: the
+/// decoder already checked them against the signature, which is derived from the very same
+/// Rust types. Values which don't come with such a guarantee, like data returned by another
+/// contract, should be converted with this trait instead.
+pub trait TryFromValue: Sized {
+    fn try_from_value(value: ValueType) -> Result<Self, ValueError>;
+}
+
+macro_rules! impl_try_from_value {
+    ($($ty:ty => $variant:ident),+) => {
+        $(
+            impl TryFromValue for $ty {
+                fn try_from_value(value: ValueType) -> Result<Self, ValueError> {
+                    match value {
+                        ValueType::$variant(v) => Ok(v.into()),
+                        _ => Err(ValueError::TypeMismatch),
+                    }
+                }
+            }
+        )+
+    }
+}
+
+impl_try_from_value!(u32 => U32, u64 => U64, i32 => I32, i64 => I64, bool => Bool, String => String);
+impl_try_from_value!(Vec<u8> => Bytes, U256 => U256, H256 => H256, Address => Address);
+
+#[cfg(target_pointer_width = "32")]
+impl TryFromValue for usize {
+    fn try_from_value(value: ValueType) -> Result<Self, ValueError> {
+        u32::try_from_value(value).map(|v| v as usize)
+    }
+}
+
+#[cfg(target_pointer_width = "32")]
+impl TryFromValue for isize {
+    fn try_from_value(value: ValueType) -> Result<Self, ValueError> {
+        i32::try_from_value(value).map(|v| v as isize)
+    }
+}
+
+impl TryFromValue for () {
+    fn try_from_value(value: ValueType) -> Result<Self, ValueError> {
+        match value {
+            ValueType::Unit => Ok(()),
+            _ => Err(ValueError::TypeMismatch),
+        }
+    }
+}
+
+impl TryFromValue for [u8; 32] {
+    fn try_from_value(value: ValueType) -> Result<Self, ValueError> {
+        match value {
+            ValueType::U256(v) | ValueType::H256(v) => Ok(v),
+            ValueType::FixedBytes(ref v) => fixed_bytes(v),
+            _ => Err(ValueError::TypeMismatch),
+        }
+    }
+}
+
+fn fixed_bytes<T: Default + AsMut<[u8]>>(bytes: &[u8]) -> Result<T, ValueError> {
+    let mut result = T::default();
+    let expected = result.as_mut().len();
+    if bytes.len() != expected {
+        return Err(ValueError::LengthMismatch { expected: expected, got: bytes.len() });
+    }
+    result.as_mut().copy_from_slice(bytes);
+    Ok(result)
+}
+
+impl<T: TryFromValue> TryFromValue for Vec<T> {
+    fn try_from_value(value: ValueType) -> Result<Self, ValueError> {
+        match value {
+            ValueType::Array(values) => values.into_iter().map(T::try_from_value).collect(),
+            _ => Err(ValueError::TypeMismatch),
+        }
+    }
+}
+
+impl<T: TryFromValue, const N: usize> TryFromValue for [T; N] {
+    fn try_from_value(value: ValueType) -> Result<Self, ValueError> {
+        let values = match value {
+            ValueType::FixedArray(values) => values,
+            _ => return Err(ValueError::TypeMismatch),
+        };
+        let got = values.len();
+        let values: Vec<T> = values.into_iter().map(T::try_from_value).collect::<Result<_, _>>()?;
+        convert::TryInto::try_into(values).map_err(|_| ValueError::LengthMismatch { expected: N, got: got })
+    }
+}
+
+impl ValueType {
+    /// Abi type of the value
+    ///
+    /// Returns `None` for `Unit`, which has no abi type, and for empty arrays, whose element
+    /// type can't be inferred.
+    pub fn param_type(&self) -> Option<ParamType> {
+        Some(match *self {
+            ValueType::U32(_) => ParamType::U32,
+            ValueType::U64(_) => ParamType::U64,
+            ValueType::I32(_) => ParamType::I32,
+            ValueType::I64(_) => ParamType::I64,
+            ValueType::Address(_) => ParamType::Address,
+            ValueType::U256(_) => ParamType::U256,
+            ValueType::H256(_) => ParamType::H256,
+            ValueType::Bytes(_) => ParamType::Bytes,
+            ValueType::Array(ref values) => {
+                ParamType::Array(values.first()?.param_type()?.into())
+            },
+            ValueType::Bool(_) => ParamType::Bool,
+            ValueType::String(_) => ParamType::String,
+            ValueType::FixedArray(ref values) => {
+                ParamType::FixedArray(values.first()?.param_type()?.into(), values.len())
+            },
+            ValueType::Tuple(ref values) => {
+                let members = values.iter().map(ValueType::param_type).collect::<Option<Vec<_>>>()?;
+                ParamType::Tuple(members.into())
+            },
+            ValueType::FixedBytes(ref bytes) => ParamType::FixedBytes(bytes.len()),
+            ValueType::Uint(_, bits) => ParamType::Uint(bits),
+            ValueType::Int(_, bits) => ParamType::Int(bits),
+            ValueType::Unit => return None,
+        })
+    }
+
+    /// Builds `ValueType::Bytes` from a borrowed slice
+    ///
+    /// The slice is copied into a newly allocated vector.
+    pub fn bytes_from_slice(bytes: &[u8]) -> ValueType {
+        ValueType::Bytes(bytes.to_vec())
+    }
+
+    /// Builds `ValueType::FixedBytes` from a borrowed slice, `None` if it's longer than 32 bytes
+    ///
+    /// The slice is copied into a newly allocated vector.
+    pub fn fixed_bytes_from_slice(bytes: &[u8]) -> Option<ValueType> {
+        if bytes.len() > 32 {
+            return None;
+        }
+        Some(ValueType::FixedBytes(bytes.to_vec()))
+    }
+
+    pub fn as_u32(&self) -> Option<u32> {
+        match *self {
+            ValueType::U32(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            ValueType::U64(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_i32(&self) -> Option<i32> {
+        match *self {
+            ValueType::I32(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            ValueType::I64(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            ValueType::Bool(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Value of a `U256` or `Uint` word
+    pub fn as_u256(&self) -> Option<U256> {
+        match *self {
+            ValueType::U256(ref word) | ValueType::Uint(ref word, _) => Some(U256::from(&word[..])),
+            _ => None,
+        }
+    }
+
+    pub fn as_h256(&self) -> Option<H256> {
+        match *self {
+            ValueType::H256(hash) => Some(H256::from(hash)),
+            _ => None,
+        }
+    }
+
+    pub fn as_address(&self) -> Option<Address> {
+        match *self {
+            ValueType::Address(address) => Some(Address::from(address)),
+            _ => None,
+        }
+    }
+
+    /// Contents of `Bytes` and `FixedBytes`
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match *self {
+            ValueType::Bytes(ref bytes) | ValueType::FixedBytes(ref bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            ValueType::String(ref string) => Some(string),
+            _ => None,
+        }
+    }
+
+    /// Elements of `Array` and `FixedArray`
+    pub fn as_array(&self) -> Option<&[ValueType]> {
+        match *self {
+            ValueType::Array(ref values) | ValueType::FixedArray(ref values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Checked sum of two `ValueType::U256` values
+    pub fn u256_add(&self, other: &ValueType) -> Result<ValueType, MathError> {
+        let (a, b) = u256_operands(self, other)?;
+        match a.overflowing_add(b) {
+            (_, true) => Err(MathError::Overflow),
+            (sum, false) => Ok(ValueType::U256(sum.into())),
+        }
+    }
+
+    /// Checked difference of two `ValueType::U256` values
+    pub fn u256_sub(&self, other: &ValueType) -> Result<ValueType, MathError> {
+        let (a, b) = u256_operands(self, other)?;
+        match a.overflowing_sub(b) {
+            (_, true) => Err(MathError::Underflow),
+            (difference, false) => Ok(ValueType::U256(difference.into())),
+        }
+    }
+
+    /// Whether the value is encoded in the tail, with only an offset in the head
+    pub fn is_dynamic(&self) -> bool {
+        match *self {
+            ValueType::Bytes(_) | ValueType::String(_) | ValueType::Array(_) => true,
+            ValueType::Tuple(ref values) | ValueType::FixedArray(ref values) => {
+                values.iter().any(ValueType::is_dynamic)
+            },
+            _ => false,
+        }
+    }
+
+    /// Builds `ValueType::U256` from a decimal or `0x`-prefixed hex string
+    pub fn u256_from_str(s: &str) -> Result<ValueType, ParseError> {
+        let mut result = [0u8; 32];
+
+        if s.starts_with("0x") || s.starts_with("0X") {
+            let digits = s[2..].trim_start_matches('0');
+            if s.len() == 2 {
+                return Err(ParseError::InvalidCharacter);
+            }
+            if digits.len() > 64 {
+                return Err(ParseError::Overflow);
+            }
+
+            // fill nibbles from the least significant end
+            for (i, c) in digits.bytes().rev().enumerate() {
+                let nibble = match c {
+                    b'0'..=b'9' => c - b'0',
+                    b'a'..=b'f' => c - b'a' + 10,
+                    b'A'..=b'F' => c - b'A' + 10,
+                    _ => return Err(ParseError::InvalidCharacter),
+                };
+                result[31 - i / 2] |= nibble << (4 * (i % 2));
+            }
+        } else {
+            if s.is_empty() {
+                return Err(ParseError::InvalidCharacter);
+            }
+
+            let value = U256::from_dec_str(s).map_err(|e| match e {
+                FromDecStrErr::InvalidCharacter => ParseError::InvalidCharacter,
+                FromDecStrErr::InvalidLength => ParseError::Overflow,
+            })?;
+            value.to_big_endian(&mut result);
+        }
+
+        Ok(ValueType::U256(result))
+    }
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        format_value(self, f, false)
+    }
+}
+
+impl fmt::Debug for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        format_value(self, f, true)
+    }
+}
+
+/// Writes `value` for `Display`, or wrapped in its variant name for `Debug` (recursively, as
+/// elements of arrays and tuples are)
+fn format_value(value: &ValueType, f: &mut fmt::Formatter, debug: bool) -> fmt::Result {
+    let name = match *value {
+        ValueType::U32(_) => "U32",
+        ValueType::U64(_) => "U64",
+        ValueType::I32(_) => "I32",
+        ValueType::I64(_) => "I64",
+        ValueType::Address(_) => "Address",
+        ValueType::U256(_) => "U256",
+        ValueType::H256(_) => "H256",
+        ValueType::Bytes(_) => "Bytes",
+        ValueType::Array(_) => "Array",
+        ValueType::Bool(_) => "Bool",
+        ValueType::String(_) => "String",
+        ValueType::Unit => return write!(f, "{}", if debug { "Unit" } else { "()" }),
+        ValueType::Tuple(_) => "Tuple",
+        ValueType::FixedArray(_) => "FixedArray",
+        ValueType::FixedBytes(_) => "FixedBytes",
+        ValueType::Uint(..) => "Uint",
+        ValueType::Int(..) => "Int",
+    };
+    if debug {
+        write!(f, "{}(", name)?;
+    }
+
+    match *value {
+        ValueType::U32(v) => write!(f, "{}", v)?,
+        ValueType::U64(v) => write!(f, "{}", v)?,
+        ValueType::I32(v) => write!(f, "{}", v)?,
+        ValueType::I64(v) => write!(f, "{}", v)?,
+        ValueType::Address(ref bytes) => write!(f, "0x{}", to_hex(bytes))?,
+        ValueType::H256(ref bytes) => write!(f, "0x{}", to_hex(bytes))?,
+        ValueType::Bytes(ref bytes) | ValueType::FixedBytes(ref bytes) => write!(f, "0x{}", to_hex(bytes))?,
+        ValueType::U256(ref word) | ValueType::Uint(ref word, _) => write!(f, "{}", U256::from(&word[..]))?,
+        ValueType::Int(ref word, _) => {
+            let value = U256::from(&word[..]);
+            match word[0] & 0x80 != 0 {
+                true => write!(f, "-{}", (!value).overflowing_add(U256::one()).0)?,
+                false => write!(f, "{}", value)?,
+            }
+        },
+        ValueType::Bool(v) => write!(f, "{}", v)?,
+        ValueType::String(ref v) => write!(f, "{:?}", v)?,
+        ValueType::Array(ref values) | ValueType::FixedArray(ref values) => format_list(values, f, debug, "[", "]")?,
+        ValueType::Tuple(ref values) => format_list(values, f, debug, "(", ")")?,
+        ValueType::Unit => unreachable!("returned above"),
+    }
+
+    match *value {
+        ValueType::Uint(_, bits) | ValueType::Int(_, bits) if debug => write!(f, ", {})", bits),
+        _ if debug => write!(f, ")"),
+        _ => Ok(()),
+    }
+}
+
+fn format_list(values: &[ValueType], f: &mut fmt::Formatter, debug: bool, open: &str, close: &str) -> fmt::Result {
+    write!(f, "{}", open)?;
+    for (i, value) in values.iter().enumerate() {
+        if i != 0 {
+            write!(f, ", ")?;
+        }
+        format_value(value, f, debug)?;
+    }
+    write!(f, "{}", close)
+}
+
+fn u256_operands(a: &ValueType, b: &ValueType) -> Result<(U256, U256), MathError> {
+    match (a, b) {
+        (&ValueType::U256(ref a), &ValueType::U256(ref b)) => Ok((U256::from(&a[..]), U256::from(&b[..]))),
+        _ => Err(MathError::InvalidOperand),
+    }
+}
+
+impl From<()> for ValueType {
+    fn from(_: ()) -> Self {
+        ValueType::Unit
+    }
 }
 
 impl From<bool> for ValueType {
@@ -33,9 +469,49 @@ impl From<u32> for ValueType {
     }
 }
 
+impl From<u64> for ValueType {
+    fn from(val: u64) -> Self {
+        ValueType::U64(val)
+    }
+}
+
+impl From<i32> for ValueType {
+    fn from(val: i32) -> Self {
+        ValueType::I32(val)
+    }
+}
+
+impl From<i64> for ValueType {
+    fn from(val: i64) -> Self {
+        ValueType::I64(val)
+    }
+}
+
+impl From<String> for ValueType {
+    fn from(val: String) -> Self {
+        ValueType::String(val)
+    }
+}
+
+// `usize`/`isize` are only mapped on 32-bit targets (wasm32), where they fit into the
+// `uint32`/`int32` abi types used for them by the derive macro
+#[cfg(target_pointer_width = "32")]
+impl From<usize> for ValueType {
+    fn from(val: usize) -> Self {
+        ValueType::U32(val as u32)
+    }
+}
+
+#[cfg(target_pointer_width = "32")]
+impl From<isize> for ValueType {
+    fn from(val: isize) -> Self {
+        ValueType::I32(val as i32)
+    }
+}
+
 impl From<U256> for ValueType {
     fn from(val: U256) -> Self {
-        ValueType::H256(val.into())
+        ValueType::U256(val.into())
     }
 }
 
@@ -55,6 +531,62 @@ impl From<ValueType> for u32 {
     }
 }
 
+impl From<ValueType> for u64 {
+    fn from(val: ValueType) -> Self {
+        match val {
+            ValueType::U64(v) => v,
+            _ => panic!("invalid abi generated for u64 argument"),
+        }
+    }
+}
+
+impl From<ValueType> for i32 {
+    fn from(val: ValueType) -> Self {
+        match val {
+            ValueType::I32(v) => v,
+            _ => panic!("invalid abi generated for i32 argument"),
+        }
+    }
+}
+
+impl From<ValueType> for i64 {
+    fn from(val: ValueType) -> Self {
+        match val {
+            ValueType::I64(v) => v,
+            _ => panic!("invalid abi generated for i64 argument"),
+        }
+    }
+}
+
+#[cfg(target_pointer_width = "32")]
+impl From<ValueType> for usize {
+    fn from(val: ValueType) -> Self {
+        match val {
+            ValueType::U32(v) => v as usize,
+            _ => panic!("invalid abi generated for usize argument"),
+        }
+    }
+}
+
+#[cfg(target_pointer_width = "32")]
+impl From<ValueType> for isize {
+    fn from(val: ValueType) -> Self {
+        match val {
+            ValueType::I32(v) => v as isize,
+            _ => panic!("invalid abi generated for isize argument"),
+        }
+    }
+}
+
+impl From<ValueType> for () {
+    fn from(val: ValueType) -> Self {
+        match val {
+            ValueType::Unit => (),
+            _ => panic!("invalid abi generated for unit return"),
+        }
+    }
+}
+
 impl From<ValueType> for bool {
     fn from(val: ValueType) -> Self {
         match val {
@@ -82,11 +614,90 @@ impl Into<Vec<u8>> for ValueType {
     }
 }
 
+/// Elements of a `bytes[]` value, failing with `Error::InvalidConversion` for anything else
+impl convert::TryFrom<ValueType> for Vec<Vec<u8>> {
+    type Error = Error;
+
+    fn try_from(val: ValueType) -> Result<Self, Error> {
+        Ok(TryFromValue::try_from_value(val)?)
+    }
+}
+
+/// `bytes32`, also accepting the words of the same size
 impl From<ValueType> for [u8; 32] {
     fn from(val: ValueType) -> Self {
         match val {
             ValueType::U256(v) | ValueType::H256(v) => v,
-            _ => panic!("invalid abi generated for bool argument"),
+            ValueType::FixedBytes(ref v) if v.len() == 32 => {
+                let mut result = [0u8; 32];
+                result.copy_from_slice(v);
+                result
+            },
+            _ => panic!("invalid abi generated for [u8; 32] argument"),
+        }
+    }
+}
+
+// `[u8; 32]` is implemented apart, as it also converts from words
+macro_rules! impl_fixed_bytes {
+    ($($len:expr),+) => {
+        $(
+            impl From<[u8; $len]> for ValueType {
+                fn from(val: [u8; $len]) -> Self {
+                    ValueType::FixedBytes(val.to_vec())
+                }
+            }
+
+            impl TryFromValue for [u8; $len] {
+                fn try_from_value(value: ValueType) -> Result<Self, ValueError> {
+                    match value {
+                        ValueType::FixedBytes(ref v) => fixed_bytes(v),
+                        _ => Err(ValueError::TypeMismatch),
+                    }
+                }
+            }
+
+            impl From<ValueType> for [u8; $len] {
+                fn from(val: ValueType) -> Self {
+                    match val {
+                        ValueType::FixedBytes(ref v) if v.len() == $len => {
+                            let mut result = [0u8; $len];
+                            result.copy_from_slice(v);
+                            result
+                        },
+                        _ => panic!(concat!("invalid abi generated for [u8; ", stringify!($len), "] argument")),
+                    }
+                }
+            }
+        )+
+    }
+}
+
+impl_fixed_bytes!(1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16);
+impl_fixed_bytes!(17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31);
+
+impl From<[u8; 32]> for ValueType {
+    fn from(val: [u8; 32]) -> Self {
+        ValueType::FixedBytes(val.to_vec())
+    }
+}
+
+// `[u8; N]` are byte strings, handled above
+impl<T: Into<ValueType>, const N: usize> From<[T; N]> for ValueType {
+    fn from(val: [T; N]) -> Self {
+        ValueType::FixedArray(iter::IntoIterator::into_iter(val).map(Into::into).collect())
+    }
+}
+
+impl<T: From<ValueType>, const N: usize> From<ValueType> for [T; N] {
+    fn from(val: ValueType) -> Self {
+        let values: Vec<T> = match val {
+            ValueType::FixedArray(v) => v.into_iter().map(From::from).collect(),
+            _ => panic!("invalid abi generated for [T; N] argument"),
+        };
+        match convert::TryInto::try_into(values) {
+            Ok(array) => array,
+            Err(_) => panic!("invalid abi generated for [T; N] argument: length mismatch"),
         }
     }
 }
@@ -130,8 +741,407 @@ impl<T: Into<ValueType>> From<Vec<T>> for ValueType {
     }
 }
 
+impl<'a> From<&'a [u8]> for ValueType {
+    fn from(val: &'a [u8]) -> ValueType {
+        ValueType::Bytes(val.to_vec())
+    }
+}
+
+impl<'a> From<&'a str> for ValueType {
+    fn from(val: &'a str) -> ValueType {
+        ValueType::String(val.to_owned())
+    }
+}
+
+impl From<ValueType> for String {
+    fn from(val: ValueType) -> String {
+        match val {
+            ValueType::String(s) => s,
+            _ => panic!("invalid abi generated for String argument"),
+        }
+    }
+}
+
 impl From<Vec<u8>> for ValueType {
     fn from(val: Vec<u8>) -> ValueType {
         ValueType::Bytes(val)
     }
-}
\ No newline at end of file
+}
+
+macro_rules! impl_from_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: Into<ValueType>),+> From<($($name,)+)> for ValueType {
+            #[allow(non_snake_case)]
+            fn from(val: ($($name,)+)) -> ValueType {
+                let ($($name,)+) = val;
+                ValueType::Tuple(vec![$($name.into()),+])
+            }
+        }
+    }
+}
+
+macro_rules! impl_tuple_from {
+    ($($name:ident),+) => {
+        impl<$($name: From<ValueType>),+> From<ValueType> for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn from(val: ValueType) -> Self {
+                match val {
+                    ValueType::Tuple(values) => {
+                        let mut values = values.into_iter();
+                        $(let $name = values.next().expect("invalid abi generated for tuple return").into();)+
+                        ($($name,)+)
+                    },
+                    _ => panic!("invalid abi generated for tuple return"),
+                }
+            }
+        }
+    }
+}
+
+impl_from_tuple!(A);
+impl_from_tuple!(A, B);
+impl_from_tuple!(A, B, C);
+impl_from_tuple!(A, B, C, D);
+impl_from_tuple!(A, B, C, D, E);
+impl_from_tuple!(A, B, C, D, E, F);
+impl_from_tuple!(A, B, C, D, E, F, G);
+impl_from_tuple!(A, B, C, D, E, F, G, H);
+impl_from_tuple!(A, B, C, D, E, F, G, H, I);
+impl_from_tuple!(A, B, C, D, E, F, G, H, I, J);
+impl_from_tuple!(A, B, C, D, E, F, G, H, I, J, K);
+impl_from_tuple!(A, B, C, D, E, F, G, H, I, J, K, L);
+
+impl_tuple_from!(A);
+impl_tuple_from!(A, B);
+impl_tuple_from!(A, B, C);
+impl_tuple_from!(A, B, C, D);
+impl_tuple_from!(A, B, C, D, E);
+impl_tuple_from!(A, B, C, D, E, F);
+impl_tuple_from!(A, B, C, D, E, F, G);
+impl_tuple_from!(A, B, C, D, E, F, G, H);
+impl_tuple_from!(A, B, C, D, E, F, G, H, I);
+impl_tuple_from!(A, B, C, D, E, F, G, H, I, J);
+impl_tuple_from!(A, B, C, D, E, F, G, H, I, J, K);
+impl_tuple_from!(A, B, C, D, E, F, G, H, I, J, K, L);
+
+#[cfg(test)]
+mod tests {
+    use super::{ValueType, ParseError, MathError, ValueError, TryFromValue};
+    use bigint::U256;
+    use parity_hash::Address;
+
+    #[test]
+    fn typed_accessors() {
+        let values = [
+            ValueType::from(U256::from(69)),
+            ValueType::from(Address::from([0x11u8; 20])),
+            ValueType::Bytes(vec![1, 2, 3]),
+            ValueType::from("gavofyork"),
+            ValueType::Array(vec![ValueType::U32(7), ValueType::Bool(true)]),
+        ];
+
+        assert_eq!(values[0].as_u256(), Some(U256::from(69)));
+        assert!(values[1].as_address() == Some(Address::from([0x11u8; 20])));
+        assert_eq!(values[2].as_bytes(), Some(&[1u8, 2, 3][..]));
+        assert_eq!(values[3].as_str(), Some("gavofyork"));
+
+        let elements = values[4].as_array().unwrap();
+        assert_eq!(elements[0].as_u32(), Some(7));
+        assert_eq!(elements[1].as_bool(), Some(true));
+
+        // no conversion between variants
+        assert_eq!(values[0].as_u32(), None);
+        assert_eq!(values[1].as_u256(), None);
+        assert!(values[0].as_h256().is_none());
+        assert_eq!(ValueType::U32(7).as_u64(), None);
+        assert_eq!(ValueType::I64(-5).as_i64(), Some(-5));
+        assert_eq!(ValueType::I64(-5).as_i32(), None);
+    }
+
+    #[test]
+    fn display() {
+        let mut minus_one = [0xffu8; 32];
+        assert_eq!(ValueType::Int(minus_one, 8).to_string(), "-1");
+        minus_one[31] = 0x00;
+        assert_eq!(ValueType::Int(minus_one, 16).to_string(), "-256");
+
+        let value = ValueType::Tuple(vec![
+            ValueType::Address([0x11u8; 20]),
+            ValueType::from(U256::from(1000)),
+            ValueType::Array(vec![ValueType::Bytes(vec![0xa9, 0x05]), ValueType::FixedBytes(vec![0x9c])]),
+            ValueType::String("gavofyork".to_owned()),
+            ValueType::Bool(true),
+            ValueType::I64(-5),
+        ]);
+        assert_eq!(
+            value.to_string(),
+            "(0x1111111111111111111111111111111111111111, 1000, [0xa905, 0x9c], \"gavofyork\", true, -5)"
+        );
+        assert_eq!(ValueType::Unit.to_string(), "()");
+    }
+
+    #[test]
+    fn debug() {
+        let value = ValueType::Array(vec![ValueType::H256([0u8; 32]), ValueType::Uint([0u8; 32], 8)]);
+
+        assert_eq!(
+            format!("{:?}", value),
+            "Array([H256(0x0000000000000000000000000000000000000000000000000000000000000000), Uint(0, 8)])"
+        );
+        assert_eq!(format!("{:?}", ValueType::Unit), "Unit");
+    }
+
+    #[test]
+    fn bytes_from_borrowed_slice() {
+        let buffer = [0x12u8, 0x34, 0x56];
+        let borrowed: &[u8] = &buffer[..2];
+
+        assert_eq!(ValueType::bytes_from_slice(borrowed), ValueType::Bytes(vec![0x12, 0x34]));
+        assert_eq!(ValueType::fixed_bytes_from_slice(borrowed), Some(ValueType::FixedBytes(vec![0x12, 0x34])));
+        assert_eq!(ValueType::fixed_bytes_from_slice(&[0u8; 33]), None);
+    }
+
+    #[test]
+    fn fixed_bytes_conversions() {
+        let selector = ValueType::from([0xa9u8, 0x05, 0x9c, 0xbb]);
+
+        assert_eq!(selector, ValueType::FixedBytes(vec![0xa9, 0x05, 0x9c, 0xbb]));
+        assert_eq!(member(ValueType::FixedBytes(vec![0xa9, 0x05, 0x9c, 0xbb])), Some("bytes4".to_owned()));
+        assert_eq!(<[u8; 4]>::from(selector), [0xa9, 0x05, 0x9c, 0xbb]);
+        assert_eq!(<[u8; 32]>::from(ValueType::FixedBytes(vec![1; 32])), [1u8; 32]);
+        assert_eq!(member([1u8; 32].into()), Some("bytes32".to_owned()));
+        assert_eq!(<[u8; 32]>::try_from_value(ValueType::FixedBytes(vec![1; 4])), Err(ValueError::LengthMismatch { expected: 32, got: 4 }));
+    }
+
+    #[test]
+    #[should_panic]
+    fn fixed_bytes_length_mismatch() {
+        let _: [u8; 4] = ValueType::FixedBytes(vec![0xa9, 0x05]).into();
+    }
+
+    #[test]
+    fn scalar_conversions() {
+        assert_eq!(u64::from(ValueType::from(u64::MAX)), u64::MAX);
+        assert_eq!(i32::from(ValueType::from(-5i32)), -5);
+        assert_eq!(i64::from(ValueType::from(i64::MIN)), i64::MIN);
+        assert_eq!(String::from(ValueType::from("gavofyork".to_owned())), "gavofyork");
+    }
+
+    #[test]
+    #[should_panic]
+    fn scalar_conversion_mismatch() {
+        let _: i64 = ValueType::I32(-5).into();
+    }
+
+    #[test]
+    fn borrowed_conversions() {
+        assert_eq!(ValueType::from(&[1u8, 2, 3][..]), ValueType::Bytes(vec![1, 2, 3]));
+        assert_eq!(ValueType::from("gavofyork"), ValueType::String("gavofyork".to_owned()));
+        assert_eq!(String::from(ValueType::String("gavofyork".to_owned())), "gavofyork");
+    }
+
+    #[test]
+    fn fixed_array_conversions() {
+        let ids = ValueType::from([1u32, 2, 3]);
+
+        assert_eq!(ids, ValueType::FixedArray(vec![ValueType::U32(1), ValueType::U32(2), ValueType::U32(3)]));
+        assert_eq!(<[u32; 3]>::from(ids), [1, 2, 3]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn fixed_array_length_mismatch() {
+        let _: [u32; 3] = ValueType::FixedArray(vec![ValueType::U32(1)]).into();
+    }
+
+    #[test]
+    fn try_from_value() {
+        use super::{TryFromValue, ValueError};
+
+        assert_eq!(u64::try_from_value(ValueType::U64(7)), Ok(7));
+        assert_eq!(bool::try_from_value(ValueType::U32(1)), Err(ValueError::TypeMismatch));
+        assert_eq!(String::try_from_value(ValueType::String("gavofyork".to_owned())), Ok("gavofyork".to_owned()));
+        assert_eq!(
+            Vec::<u32>::try_from_value(ValueType::Array(vec![ValueType::U32(1), ValueType::U32(2)])),
+            Ok(vec![1, 2])
+        );
+        assert_eq!(
+            Vec::<u32>::try_from_value(ValueType::Array(vec![ValueType::U32(1), ValueType::Bool(true)])),
+            Err(ValueError::TypeMismatch)
+        );
+        assert_eq!(<[u8; 4]>::try_from_value(ValueType::FixedBytes(vec![1, 2, 3, 4])), Ok([1, 2, 3, 4]));
+        assert_eq!(
+            <[u8; 4]>::try_from_value(ValueType::FixedBytes(vec![1, 2])),
+            Err(ValueError::LengthMismatch { expected: 4, got: 2 })
+        );
+        assert_eq!(
+            <[u32; 3]>::try_from_value(ValueType::FixedArray(vec![ValueType::U32(1)])),
+            Err(ValueError::LengthMismatch { expected: 3, got: 1 })
+        );
+    }
+
+    #[test]
+    fn bytes_array_into_vec() {
+        use std::convert::TryFrom;
+        use super::super::{decode_values, encode_values, ParamType};
+
+        let encoded = encode_values(&[ValueType::Array(vec![
+            ValueType::Bytes(vec![0x12, 0x34]),
+            ValueType::Bytes(vec![]),
+            ValueType::Bytes(vec![0xff; 40]),
+        ])]);
+        let mut decoded = decode_values(&[ParamType::Array(ParamType::Bytes.into())], &encoded).unwrap();
+
+        assert_eq!(
+            Vec::<Vec<u8>>::try_from(decoded.pop().unwrap()),
+            Ok(vec![vec![0x12, 0x34], vec![], vec![0xff; 40]])
+        );
+    }
+
+    #[test]
+    fn mixed_array_into_bytes_vec() {
+        use std::convert::TryFrom;
+        use super::super::Error;
+
+        let value = ValueType::Array(vec![ValueType::Bytes(vec![0x12]), ValueType::String("ab".to_owned())]);
+        assert_eq!(Vec::<Vec<u8>>::try_from(value), Err(Error::InvalidConversion));
+        assert_eq!(Vec::<Vec<u8>>::try_from(ValueType::Bytes(vec![0x12])), Err(Error::InvalidConversion));
+    }
+
+    #[test]
+    fn dedup_in_hash_set() {
+        use std::collections::HashSet;
+
+        let mut set = HashSet::new();
+        set.insert(ValueType::Address([0x11u8; 20]));
+        set.insert(ValueType::Address([0x22u8; 20]));
+        set.insert(ValueType::Address([0x11u8; 20]));
+        set.insert(ValueType::Address([0x33u8; 20]));
+        set.insert(ValueType::Address([0x22u8; 20]));
+
+        assert_eq!(set.len(), 3);
+        assert!(set.contains(&ValueType::Address([0x33u8; 20])));
+    }
+
+    #[test]
+    fn tuple_from_rust_tuple() {
+        let amount = U256::from(1000);
+        let value = ValueType::from((amount, Address::from([0x11u8; 20]), true));
+
+        assert_eq!(value, ValueType::Tuple(vec![
+            ValueType::from(amount),
+            ValueType::Address([0x11u8; 20]),
+            ValueType::Bool(true),
+        ]));
+        assert!(!value.is_dynamic());
+        assert!(ValueType::from((1u32, vec![1u8, 2, 3])).is_dynamic());
+    }
+
+    #[test]
+    fn u256_round_trip() {
+        use super::super::{encode_values, decode_values, ParamType};
+
+        let amount = U256::from(1_000_000_000_000_000_000u64);
+        let value = ValueType::from(amount);
+        assert_eq!(value, ValueType::U256(amount.into()));
+
+        let encoded = encode_values(&[value]);
+        let mut decoded = decode_values(&[ParamType::U256], &encoded).unwrap();
+        assert_eq!(U256::from(decoded.pop().unwrap()), amount);
+    }
+
+    #[test]
+    fn word_arrays_round_trip() {
+        use parity_hash::H256;
+        use super::super::{encode_values, decode_values, ParamType};
+
+        let numbers = vec![U256::from(1), U256::from(1000), U256::max_value()];
+        let value = ValueType::from(numbers.clone());
+        assert_eq!(value, ValueType::Array(numbers.iter().map(|&n| ValueType::U256(n.into())).collect()));
+
+        let encoded = encode_values(&[value]);
+        let mut decoded = decode_values(&[ParamType::Array(ParamType::U256.into())], &encoded).unwrap();
+        let converted: Vec<U256> = decoded.pop().unwrap().into();
+        assert_eq!(converted, numbers);
+
+        let hashes = vec![H256::from([0x11u8; 32]), H256::from([0x22u8; 32])];
+        let converted: Vec<H256> = ValueType::from(hashes.clone()).into();
+        assert!(converted == hashes);
+    }
+
+    fn member(value: ValueType) -> Option<String> {
+        value.param_type().map(|p| {
+            let mut s = String::new();
+            p.to_member(&mut s);
+            s
+        })
+    }
+
+    #[test]
+    fn unit_param_type() {
+        assert!(ValueType::Unit.param_type().is_none());
+        assert!(ValueType::from(()).param_type().is_none());
+        assert_eq!(member(ValueType::Bool(true)), Some("bool".to_owned()));
+        assert_eq!(member(ValueType::Array(vec![ValueType::U32(1)])), Some("uint32[]".to_owned()));
+        assert!(ValueType::Array(vec![]).param_type().is_none());
+    }
+
+    #[test]
+    fn u256_from_decimal() {
+        let value = ValueType::u256_from_str("1000000000000000000").unwrap();
+        assert_eq!(U256::from(value), U256::from(1_000_000_000_000_000_000u64));
+    }
+
+    #[test]
+    fn u256_from_hex() {
+        let value = ValueType::u256_from_str("0xde0b6b3a7640000").unwrap();
+        assert_eq!(U256::from(value), U256::from(1_000_000_000_000_000_000u64));
+
+        let value = ValueType::u256_from_str("0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF").unwrap();
+        assert_eq!(value, ValueType::U256([0xff; 32]));
+    }
+
+    #[test]
+    fn u256_from_str_overflow() {
+        assert_eq!(
+            ValueType::u256_from_str("115792089237316195423570985008687907853269984665640564039457584007913129639936"),
+            Err(ParseError::Overflow)
+        );
+        assert_eq!(
+            ValueType::u256_from_str("0x10000000000000000000000000000000000000000000000000000000000000000"),
+            Err(ParseError::Overflow)
+        );
+    }
+
+    fn u256(value: u64) -> ValueType {
+        let mut word = [0u8; 32];
+        U256::from(value).to_big_endian(&mut word);
+        ValueType::U256(word)
+    }
+
+    #[test]
+    fn u256_checked_math() {
+        assert_eq!(u256(1000).u256_add(&u256(24)), Ok(u256(1024)));
+        assert_eq!(u256(1000).u256_sub(&u256(1000)), Ok(u256(0)));
+        assert_eq!(u256(1).u256_add(&ValueType::U32(1)), Err(MathError::InvalidOperand));
+    }
+
+    #[test]
+    fn u256_add_overflow() {
+        assert_eq!(ValueType::U256([0xff; 32]).u256_add(&u256(1)), Err(MathError::Overflow));
+        assert_eq!(ValueType::U256([0xff; 32]).u256_add(&u256(0)), Ok(ValueType::U256([0xff; 32])));
+    }
+
+    #[test]
+    fn u256_sub_underflow() {
+        assert_eq!(u256(5).u256_sub(&u256(6)), Err(MathError::Underflow));
+    }
+
+    #[test]
+    fn u256_from_str_invalid() {
+        assert_eq!(ValueType::u256_from_str(""), Err(ParseError::InvalidCharacter));
+        assert_eq!(ValueType::u256_from_str("0x"), Err(ParseError::InvalidCharacter));
+        assert_eq!(ValueType::u256_from_str("12a"), Err(ParseError::InvalidCharacter));
+        assert_eq!(ValueType::u256_from_str("0x12g"), Err(ParseError::InvalidCharacter));
+    }
+}
@@ -7,6 +7,8 @@ extern crate tiny_keccak;
 extern crate byteorder;
 extern crate bigint;
 extern crate parity_hash;
+#[cfg(feature = "serde")]
+extern crate serde_json;
 
 #[cfg(not(feature="std"))]
 #[macro_use]
@@ -14,6 +16,35 @@ extern crate alloc;
 
 pub mod eth;
 
+/// Types most contract crates need, importable with a single `use`
+///
+/// ```
+/// use pwasm_abi::prelude::*;
+///
+/// let signature = Signature::new(vec![ParamType::Address, ParamType::U256], Some(ParamType::Bool));
+/// assert_eq!(signature.params().len(), 2);
+/// assert_eq!(signature.result(), Some(&ParamType::Bool));
+/// ```
+pub mod prelude {
+	pub use eth::{ValueType, ParamType, Signature, Table, HashSignature, NamedSignature, Error};
+	pub use eth::{encode_values, decode_values};
+}
+
+/// Free encoding and decoding functions of the legacy (ethereum) ABI, for callers holding
+/// plain param type lists rather than `Signature`s
+///
+/// ```
+/// use pwasm_abi::legacy::{self, ParamType, ValueType};
+///
+/// let values = vec![ValueType::Address([0x11u8; 20]), ValueType::Uint([0x22u8; 32], 256)];
+/// let data = legacy::encode(&values);
+/// assert_eq!(legacy::decode(&[ParamType::Address, ParamType::Uint(256)], &data), Ok(values));
+/// ```
+pub mod legacy {
+	pub use eth::{ValueType, ParamType, Error};
+	pub use eth::{encode_values as encode, decode_values as decode, decode_strict};
+}
+
 mod lib {
 
 	mod core {
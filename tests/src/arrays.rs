@@ -0,0 +1,99 @@
+mod contract {
+	use pwasm_abi_derive::eth_abi;
+	use parity_hash::Address;
+	use bigint::U256;
+	use call;
+	use std::borrow::Cow;
+
+	#[eth_abi(Endpoint, Client)]
+	pub trait Registry {
+		fn holders(&mut self) -> Vec<Address>;
+		fn balances(&mut self, owners: Vec<Address>) -> Vec<U256>;
+	}
+
+	#[derive(Default)]
+	pub struct Instance;
+
+	impl Registry for Instance {
+		fn holders(&mut self) -> Vec<Address> {
+			vec![[0x11u8; 20].into(), [0x22u8; 20].into()]
+		}
+
+		fn balances(&mut self, owners: Vec<Address>) -> Vec<U256> {
+			owners.iter().map(|owner| U256::from(owner[0]) * U256::from(1000)).collect()
+		}
+	}
+}
+
+use pwasm_abi::eth::{encode_values, decode_values, ParamType};
+use pwasm_abi::eth::hex::from_hex;
+use parity_hash::Address;
+use bigint::U256;
+use {LAST_CALL, NEXT_RESULT};
+
+fn owners() -> Vec<Address> {
+	vec![[0x11u8; 20].into(), [0x22u8; 20].into()]
+}
+
+fn balances_payload() -> Vec<u8> {
+	// balances([0x11..11, 0x22..22])
+	let mut payload = vec![0xec, 0x36, 0xc8, 0x49];
+	payload.extend_from_slice(&encode_values(&[owners().into()]));
+	payload
+}
+
+#[test]
+fn array_result_layout() {
+	let mut endpoint = contract::Endpoint::new(contract::Instance);
+	let result = endpoint.dispatch(&[0x81, 0x88, 0xf7, 0x1c]);
+
+	assert_eq!(result, from_hex(&("".to_owned() +
+		"0000000000000000000000000000000000000000000000000000000000000020" +
+		"0000000000000000000000000000000000000000000000000000000000000002" +
+		"0000000000000000000000001111111111111111111111111111111111111111" +
+		"0000000000000000000000002222222222222222222222222222222222222222")).unwrap());
+}
+
+#[test]
+fn array_result_round_trip() {
+	use self::contract::Registry;
+
+	let mut endpoint = contract::Endpoint::new(contract::Instance);
+	let result = endpoint.dispatch(&balances_payload());
+	assert_eq!(
+		decode_values(&[ParamType::Array(ParamType::U256.into())], &result).unwrap(),
+		vec![vec![U256::from(17000), U256::from(34000)].into()]
+	);
+
+	let mut client = contract::Client::new(Address::zero());
+	NEXT_RESULT.with(|r| *r.borrow_mut() = result);
+	assert_eq!(client.balances(owners()), vec![U256::from(17000), U256::from(34000)]);
+	LAST_CALL.with(|v| assert_eq!(*v.borrow(), balances_payload()));
+
+	NEXT_RESULT.with(|r| *r.borrow_mut() = endpoint.dispatch(&[0x81, 0x88, 0xf7, 0x1c]));
+	assert!(client.holders() == owners());
+}
+
+#[test]
+#[should_panic(expected = "abi dispatch failed")]
+fn array_result_over_capacity() {
+	use self::contract::Registry;
+
+	// 2 words of offset and length and 2 elements don't fit in 3 words
+	let mut client = contract::Client::new(Address::zero()).result_capacity(90);
+	NEXT_RESULT.with(|r| *r.borrow_mut() = encode_values(&[owners().into()]));
+	client.holders();
+}
+
+#[test]
+fn result_capacity_rounding_does_not_overflow() {
+	contract::Client::new(Address::zero()).result_capacity(usize::MAX);
+}
+
+#[test]
+fn array_result_abi_json() {
+	let abi: ::serde_json::Value = ::serde_json::from_str(contract::Endpoint::<contract::Instance>::ABI_JSON).unwrap();
+
+	assert_eq!(abi[0]["outputs"][0]["type"], "address[]");
+	assert_eq!(abi[1]["outputs"][0]["type"], "uint256[]");
+}
@@ -0,0 +1,57 @@
+mod contract {
+	use pwasm_abi_derive::eth_abi;
+	use parity_hash::Address;
+	use bigint::U256;
+	use call;
+	use std::borrow::Cow;
+
+	#[eth_abi(Endpoint, Client)]
+	pub trait Notes {
+		fn store(&mut self, data: &[u8], note: &str);
+	}
+
+	#[derive(Default)]
+	pub struct Instance {
+		pub stored: Option<(Vec<u8>, String)>,
+	}
+
+	impl Notes for Instance {
+		fn store(&mut self, data: &[u8], note: &str) {
+			self.stored = Some((data.to_vec(), note.to_owned()));
+		}
+	}
+}
+
+use pwasm_abi::eth::{encode_values, ValueType};
+use parity_hash::Address;
+use LAST_CALL;
+
+fn store_payload() -> Vec<u8> {
+	// store(0x010203, "gavofyork")
+	let mut payload = vec![0xee, 0x34, 0x0c, 0x74];
+	payload.extend_from_slice(&encode_values(&[
+		ValueType::Bytes(vec![1, 2, 3]),
+		ValueType::String("gavofyork".to_owned()),
+	]));
+	payload
+}
+
+#[test]
+fn dispatch_borrowed_params() {
+	let mut endpoint = contract::Endpoint::new(contract::Instance::default());
+	endpoint.dispatch(&store_payload());
+
+	let (data, note) = endpoint.instance().stored.clone().expect("store was not invoked");
+	assert_eq!(data, vec![1, 2, 3]);
+	assert_eq!(note, "gavofyork");
+}
+
+#[test]
+fn call_with_borrowed_params() {
+	use self::contract::Notes;
+
+	let mut client = contract::Client::new(Address::zero());
+	client.store(&[1, 2, 3], "gavofyork");
+
+	LAST_CALL.with(|v| assert_eq!(*v.borrow(), store_payload()));
+}
@@ -0,0 +1,129 @@
+mod contract {
+	use pwasm_abi_derive::eth_abi;
+	use parity_hash::Address;
+	use bigint::U256;
+	use call;
+	use std::borrow::Cow;
+
+	#[eth_abi(Endpoint, Client)]
+	pub trait Registry {
+		fn register(&mut self, hash: [u8; 32], owner: Address) -> bool;
+		fn count(&mut self, hashes: Vec<[u8; 32]>) -> u32;
+	}
+
+	#[derive(Default)]
+	pub struct Instance {
+		pub registered: Option<([u8; 32], Address)>,
+		pub counted: Vec<[u8; 32]>,
+	}
+
+	impl Registry for Instance {
+		fn register(&mut self, hash: [u8; 32], owner: Address) -> bool {
+			self.registered = Some((hash, owner));
+			true
+		}
+
+		fn count(&mut self, hashes: Vec<[u8; 32]>) -> u32 {
+			self.counted = hashes;
+			self.counted.len() as u32
+		}
+	}
+}
+
+mod tagged {
+	use pwasm_abi_derive::eth_abi;
+	use parity_hash::Address;
+	use bigint::U256;
+	use call;
+	use std::borrow::Cow;
+
+	#[eth_abi(Endpoint, Client)]
+	pub trait Tagged {
+		fn supports(&mut self, id: [u8; 4], tag: [u8; 32]) -> bool;
+	}
+
+	#[derive(Default)]
+	pub struct Instance {
+		pub tags: Vec<([u8; 4], [u8; 32])>,
+	}
+
+	impl Tagged for Instance {
+		fn supports(&mut self, id: [u8; 4], tag: [u8; 32]) -> bool {
+			self.tags.push((id, tag));
+			true
+		}
+	}
+}
+
+use parity_hash::Address;
+use {LAST_CALL, NEXT_RESULT};
+
+fn sample_hash(seed: u8) -> [u8; 32] {
+	let mut hash = [0u8; 32];
+	for (i, b) in hash.iter_mut().enumerate() {
+		*b = seed + i as u8;
+	}
+	hash
+}
+
+#[test]
+fn register_hash() {
+	// register(0x0102..20, 0x11..11)
+	let mut payload = vec![0xd2, 0x20, 0x57, 0xa9];
+	payload.extend_from_slice(&sample_hash(1));
+	payload.extend_from_slice(&[0u8; 12]);
+	payload.extend_from_slice(&[0x11u8; 20]);
+
+	let mut endpoint = contract::Endpoint::new(contract::Instance::default());
+	let result = endpoint.dispatch(&payload);
+
+	let (hash, owner) = endpoint.instance().registered.expect("register was not invoked");
+	assert_eq!(hash, sample_hash(1));
+	assert_eq!(&owner[..], &[0x11u8; 20][..]);
+	assert_eq!(result[31], 1);
+}
+
+#[test]
+fn hash_abi_json() {
+	let abi: ::serde_json::Value = ::serde_json::from_str(contract::Endpoint::<contract::Instance>::ABI_JSON).unwrap();
+
+	assert_eq!(abi[0]["inputs"][0]["type"], "bytes32");
+	assert_eq!(abi[1]["inputs"][0]["type"], "bytes32[]");
+}
+
+#[test]
+fn count_hashes() {
+	// count([0x0102..20, 0x8182..a0])
+	let mut payload = vec![0xdf, 0x69, 0xb1, 0x07];
+	payload.extend_from_slice(&::pwasm_abi::eth::encode_values(&[
+		vec![sample_hash(1), sample_hash(0x81)].into(),
+	]));
+
+	let mut endpoint = contract::Endpoint::new(contract::Instance::default());
+	let result = endpoint.dispatch(&payload);
+
+	assert_eq!(endpoint.instance().counted, vec![sample_hash(1), sample_hash(0x81)]);
+	assert_eq!(result[31], 2);
+}
+
+#[test]
+fn dispatch_bytes32() {
+	use self::tagged::Tagged;
+
+	// supports(0x01ffc9a7, 0x0102..20)
+	let mut payload = vec![0x2f, 0x06, 0xb1, 0x68];
+	payload.extend_from_slice(&[0x01, 0xff, 0xc9, 0xa7]);
+	payload.extend_from_slice(&[0u8; 28]);
+	payload.extend_from_slice(&sample_hash(1));
+
+	let mut endpoint = tagged::Endpoint::new(tagged::Instance::default());
+	let result = endpoint.dispatch(&payload);
+
+	assert_eq!(endpoint.instance().tags, vec![([0x01, 0xff, 0xc9, 0xa7], sample_hash(1))]);
+	assert_eq!(result[31], 1);
+
+	let mut client = tagged::Client::new(Address::zero());
+	NEXT_RESULT.with(|r| *r.borrow_mut() = result);
+	assert!(client.supports([0x01, 0xff, 0xc9, 0xa7], sample_hash(1)));
+	LAST_CALL.with(|v| assert_eq!(*v.borrow(), payload));
+}
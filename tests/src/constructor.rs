@@ -0,0 +1,64 @@
+mod contract {
+	use pwasm_abi_derive::eth_abi;
+	use parity_hash::Address;
+	use bigint::U256;
+	use call;
+	use std::borrow::Cow;
+
+	#[eth_abi(Endpoint, Client)]
+	pub trait Vault {
+		#[constructor]
+		fn init(&mut self, owner: Address, limit: U256);
+		fn limit(&mut self) -> U256;
+	}
+
+	#[derive(Default)]
+	pub struct Instance {
+		pub owner: Option<Address>,
+		pub limit: U256,
+	}
+
+	impl Vault for Instance {
+		fn init(&mut self, owner: Address, limit: U256) {
+			self.owner = Some(owner);
+			self.limit = limit;
+		}
+
+		fn limit(&mut self) -> U256 {
+			self.limit
+		}
+	}
+}
+
+use pwasm_abi::eth::{encode_values, ParamType, ValueType};
+use parity_hash::Address;
+use bigint::U256;
+
+#[test]
+fn marked_constructor_is_fallback() {
+	let mut endpoint = contract::Endpoint::new(contract::Instance::default());
+	assert_eq!(
+		endpoint.table().constructor_signature().map(|s| s.params()),
+		Some(&[ParamType::Address, ParamType::U256][..])
+	);
+
+	// init(0x11..11, 1000), without selector
+	let mut limit = [0u8; 32];
+	limit[30] = 0x03;
+	limit[31] = 0xe8;
+	endpoint.dispatch_ctor(&encode_values(&[ValueType::Address([0x11u8; 20]), ValueType::U256(limit)]));
+
+	assert_eq!(endpoint.instance().owner, Some(Address::from([0x11u8; 20])));
+	assert_eq!(endpoint.instance().limit, U256::from(1000));
+}
+
+#[test]
+fn marked_constructor_in_abi_json() {
+	use serde_json::{self, Value};
+
+	let abi: Value = serde_json::from_str(contract::Endpoint::<contract::Instance>::ABI_JSON).unwrap();
+	let ctor = abi.as_array().unwrap().iter().find(|e| e["type"] == "constructor").expect("constructor is in the abi");
+
+	assert_eq!(ctor["inputs"][0]["name"], "owner");
+	assert_eq!(ctor["inputs"][1]["type"], "uint256");
+}
@@ -20,7 +20,7 @@ mod contract {
 		fn ctor(&mut self, total_supply: U256);
 		fn balanceOf(&mut self, _owner: Address) -> U256;
 		fn transfer(&mut self, _to: Address, _amount: U256) -> bool;
-		fn totalSupply(&mut self) -> U256;
+		fn totalSupply(&self) -> U256;
 	}
 
 	#[derive(Default)]
@@ -42,7 +42,7 @@ mod contract {
 			false
 		}
 
-		fn totalSupply(&mut self) -> U256 {
+		fn totalSupply(&self) -> U256 {
 			self.total_supply
 		}
 	}
@@ -73,11 +73,78 @@ fn ctor() {
 	assert_eq!(endpoint.instance().total_supply, U256::from(1) << 248);
 }
 
+#[test]
+fn constructor_signature() {
+	use pwasm_abi::eth::ParamType;
+
+	let endpoint = contract::Endpoint::new(contract::Instance::default());
+	let table = endpoint.table();
+	let signature = table.constructor_signature().expect("ctor is declared");
+
+	match signature.params() {
+		&[ParamType::U256] => {},
+		params => panic!("unexpected constructor params: {:?}", params),
+	}
+	assert!(signature.result().is_none());
+	assert!(table.inner.iter().all(|hs| !table.is_constructor_selector(hs.hash())));
+}
+
+#[test]
+fn abi_json() {
+	use serde_json::{self, Value};
+
+	let abi: Value = serde_json::from_str(contract::Endpoint::<contract::Instance>::ABI_JSON)
+		.expect("ABI_JSON should be valid json");
+	let entries = abi.as_array().expect("abi is a list of entries");
+
+	let transfer = entries.iter().find(|e| e["name"] == "transfer").expect("transfer is in the abi");
+	assert_eq!(transfer["type"], "function");
+	assert_eq!(transfer["inputs"][0]["name"], "_to");
+	assert_eq!(transfer["inputs"][0]["type"], "address");
+	assert_eq!(transfer["inputs"][1]["type"], "uint256");
+	assert_eq!(transfer["outputs"][0]["type"], "bool");
+	assert_eq!(transfer["stateMutability"], "nonpayable");
+
+	let total_supply = entries.iter().find(|e| e["name"] == "totalSupply").expect("totalSupply is in the abi");
+	assert_eq!(total_supply["stateMutability"], "view");
+
+	let ctor = entries.iter().find(|e| e["type"] == "constructor").expect("constructor is in the abi");
+	assert_eq!(ctor["inputs"][0]["type"], "uint256");
+}
+
 #[test]
 fn call() {
 	contract::Client::new(Address::zero()).value(U256::from(100));
 }
 
+#[test]
+fn no_output_is_not_zero() {
+	use self::contract::TokenContract;
+	use pwasm_abi::eth::Error;
+	use NEXT_RESULT;
+
+	let mut client = contract::Client::new(Address::zero());
+	NEXT_RESULT.with(|r| r.borrow_mut().clear());
+
+	assert_eq!(client.try_balanceOf(Address::zero()), Err(Error::UnexpectedEnd { position: 32 }));
+	assert!(::std::panic::catch_unwind(move || client.balanceOf(Address::zero())).is_err(), "balanceOf should not return 0");
+}
+
+#[test]
+fn client_decodes_result() {
+	use self::contract::TokenContract;
+	use NEXT_RESULT;
+
+	let mut balance = [0u8; 32];
+	balance[30] = 0x03;
+	balance[31] = 0xe8;
+	NEXT_RESULT.with(|r| *r.borrow_mut() = balance.to_vec());
+
+	let mut client = contract::Client::new(Address::zero());
+	assert_eq!(client.balanceOf(Address::zero()), U256::from(1000));
+	assert_eq!(client.totalSupply(), U256::from(1000));
+}
+
 #[test]
 #[should_panic]
 fn ctor_empty() {
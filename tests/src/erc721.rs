@@ -0,0 +1,121 @@
+use hex::FromHex;
+use pwasm_abi::eth::{NamedSignature, HashSignature, Signature, ParamType, ValueType, Table};
+
+fn signatures() -> Vec<NamedSignature> {
+	vec![
+		NamedSignature::new("ownerOf", Signature::new(vec![ParamType::U256], Some(ParamType::Address))),
+		NamedSignature::new("tokenURI", Signature::new(vec![ParamType::U256], Some(ParamType::String))),
+		NamedSignature::new("safeTransferFrom", Signature::new_void(
+			vec![ParamType::Address, ParamType::Address, ParamType::U256, ParamType::Bytes]
+		)),
+	]
+}
+
+fn table() -> Table {
+	let mut table = Table::default();
+	for signature in signatures() {
+		table.push(signature);
+	}
+	table
+}
+
+fn uint(v: u8) -> ValueType {
+	let mut word = [0u8; 32];
+	word[31] = v;
+	ValueType::U256(word)
+}
+
+fn hex(s: &str) -> Vec<u8> {
+	s.from_hex().unwrap()
+}
+
+/// Decodes `calldata` via the table, checks the arguments and that they encode back to the same bytes
+fn round_trip_call(calldata: &[u8], expected: Vec<ValueType>) {
+	let table = table();
+	let (method_id, args) = table.decode_input(calldata).expect("calldata should decode");
+	assert_eq!(args, expected);
+
+	let signature = table.hash_signature(method_id).unwrap().signature();
+	assert_eq!(signature.encode_invoke(&args), &calldata[4..]);
+}
+
+/// Checks that `value` returned by method `method_id` encodes to `payload` and decodes back
+fn round_trip_result(method_id: u32, value: ValueType, payload: &[u8]) {
+	let table = table();
+	let signature = table.hash_signature(method_id).unwrap().signature();
+
+	let decoded = signature.decode_result(payload).expect("result should decode");
+	assert_eq!(decoded.as_ref(), Some(&value));
+	assert_eq!(signature.encode_result(Some(value)).unwrap(), payload);
+}
+
+#[test]
+fn selectors() {
+	let selectors: Vec<u32> = signatures().into_iter().map(|s| HashSignature::from(s).hash()).collect();
+	assert_eq!(selectors, vec![0x6352211e, 0xc87b56dd, 0xb88d4fde]);
+}
+
+#[test]
+fn owner_of() {
+	round_trip_call(&hex(&("".to_owned() +
+		"6352211e" +
+		"000000000000000000000000000000000000000000000000000000000000002a")), vec![uint(42)]);
+
+	round_trip_result(
+		0x6352211e,
+		ValueType::Address([0x11u8; 20]),
+		&hex("0000000000000000000000001111111111111111111111111111111111111111"),
+	);
+}
+
+#[test]
+fn token_uri() {
+	round_trip_call(&hex(&("".to_owned() +
+		"c87b56dd" +
+		"0000000000000000000000000000000000000000000000000000000000000001")), vec![uint(1)]);
+
+	// dynamic result is prefixed with its offset, as if it was the only param
+	round_trip_result(
+		0xc87b56dd,
+		ValueType::String("ipfs://bafybeigdyrzt/1.json".to_owned()),
+		&hex(&("".to_owned() +
+			"0000000000000000000000000000000000000000000000000000000000000020" +
+			"000000000000000000000000000000000000000000000000000000000000001b" +
+			"697066733a2f2f62616679626569676479727a742f312e6a736f6e0000000000")),
+	);
+}
+
+#[test]
+fn safe_transfer_from_with_data() {
+	// three static heads, then the offset of the trailing bytes pointing past all four heads
+	round_trip_call(&hex(&("".to_owned() +
+		"b88d4fde" +
+		"0000000000000000000000001111111111111111111111111111111111111111" +
+		"0000000000000000000000002222222222222222222222222222222222222222" +
+		"0000000000000000000000000000000000000000000000000000000000000007" +
+		"0000000000000000000000000000000000000000000000000000000000000080" +
+		"0000000000000000000000000000000000000000000000000000000000000024" +
+		"deadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeef" +
+		"0102030400000000000000000000000000000000000000000000000000000000")), vec![
+		ValueType::Address([0x11u8; 20]),
+		ValueType::Address([0x22u8; 20]),
+		uint(7),
+		ValueType::Bytes(hex("deadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeef01020304")),
+	]);
+}
+
+#[test]
+fn safe_transfer_from_empty_data() {
+	round_trip_call(&hex(&("".to_owned() +
+		"b88d4fde" +
+		"0000000000000000000000001111111111111111111111111111111111111111" +
+		"0000000000000000000000002222222222222222222222222222222222222222" +
+		"0000000000000000000000000000000000000000000000000000000000000007" +
+		"0000000000000000000000000000000000000000000000000000000000000080" +
+		"0000000000000000000000000000000000000000000000000000000000000000")), vec![
+		ValueType::Address([0x11u8; 20]),
+		ValueType::Address([0x22u8; 20]),
+		uint(7),
+		ValueType::Bytes(vec![]),
+	]);
+}
@@ -0,0 +1,58 @@
+use std::cell::RefCell;
+use std::borrow::Cow;
+
+use pwasm_abi::eth::{Event, EventParam, ParamType, ValueType};
+use pwasm_abi_derive::eth_abi;
+use parity_hash::{Address, H256};
+use bigint::U256;
+use hex::FromHex;
+use call;
+
+thread_local!(static LOGS: RefCell<Vec<(Vec<H256>, Vec<u8>)>> = RefCell::new(Vec::new()));
+
+fn log(topics: &[H256], data: &[u8]) {
+	LOGS.with(|l| l.borrow_mut().push((topics.to_vec(), data.to_vec())));
+}
+
+#[eth_abi(RegistryEndpoint, RegistryClient)]
+pub trait Registry {
+	fn register(&mut self, name: Vec<u8>, owner: Address);
+
+	#[event]
+	fn registered(&mut self, indexed_name: Vec<u8>, indexed_active: bool, owner: Address);
+}
+
+struct Instance;
+
+impl Registry for Instance {
+	fn register(&mut self, name: Vec<u8>, owner: Address) {
+		self.registered(name, true, owner);
+	}
+}
+
+#[test]
+fn emit_indexed_dynamic_param() {
+	Instance.register(b"gavofyork".to_vec(), [0x11u8; 20].into());
+
+	let (topics, data) = LOGS.with(|l| l.borrow_mut().pop()).expect("event was not emitted");
+	let topics: Vec<[u8; 32]> = topics.iter().map(|topic| {
+		let mut word = [0u8; 32];
+		word.copy_from_slice(&topic[..]);
+		word
+	}).collect();
+
+	let event = Event::new("registered", vec![
+		EventParam::new(ParamType::Bytes, true),
+		EventParam::new(ParamType::Bool, true),
+		EventParam::new(ParamType::Address, false),
+	]);
+	// keccak256("gavofyork")
+	let mut name_hash = [0u8; 32];
+	name_hash.copy_from_slice(&"e0859ceea0a2fd2474deef2b2183f10f4c741ebba702e9a07d337522c0af55fb".from_hex().unwrap());
+
+	assert_eq!(event.decode_log(&topics, &data).unwrap(), vec![
+		ValueType::H256(name_hash),
+		ValueType::Bool(true),
+		ValueType::Address([0x11u8; 20]),
+	]);
+}
@@ -0,0 +1,79 @@
+mod contract {
+	use pwasm_abi_derive::eth_abi;
+	use parity_hash::Address;
+	use bigint::U256;
+	use call;
+	use std::borrow::Cow;
+
+	#[eth_abi(Endpoint, Client)]
+	pub trait Wallet {
+		fn deposit(&mut self, amount: u32);
+		#[fallback]
+		fn receive(&mut self);
+	}
+
+	#[derive(Default)]
+	pub struct Instance {
+		pub deposited: u32,
+		pub received: u32,
+	}
+
+	impl Wallet for Instance {
+		fn deposit(&mut self, amount: u32) {
+			self.deposited += amount;
+		}
+
+		fn receive(&mut self) {
+			self.received += 1;
+		}
+	}
+}
+
+mod no_fallback {
+	use pwasm_abi_derive::eth_abi;
+	use parity_hash::Address;
+	use bigint::U256;
+	use call;
+	use std::borrow::Cow;
+
+	#[eth_abi(Endpoint, Client)]
+	pub trait Counter {
+		fn bump(&mut self);
+	}
+
+	#[derive(Default)]
+	pub struct Instance;
+
+	impl Counter for Instance {
+		fn bump(&mut self) {}
+	}
+}
+
+#[test]
+fn unknown_selector_invokes_fallback() {
+	let mut endpoint = contract::Endpoint::new(contract::Instance::default());
+
+	assert_eq!(endpoint.dispatch(&[0xde, 0xad, 0xbe, 0xef]), Vec::<u8>::new());
+	assert_eq!(endpoint.dispatch(&[]), Vec::<u8>::new());
+
+	assert_eq!(endpoint.instance().received, 2);
+	assert_eq!(endpoint.instance().deposited, 0);
+}
+
+#[test]
+fn fallback_is_not_in_table() {
+	use pwasm_abi::eth::compute_selector;
+
+	let endpoint = contract::Endpoint::new(contract::Instance::default());
+
+	assert!(endpoint.table().hash_signature(compute_selector("deposit(uint32)")).is_ok());
+	assert!(endpoint.table().hash_signature(compute_selector("receive()")).is_err());
+	assert!(contract::Endpoint::<contract::Instance>::ABI_JSON.contains(r#"{"type":"fallback","stateMutability":"nonpayable"}"#));
+}
+
+#[test]
+#[should_panic(expected = "unknown method selector")]
+fn unknown_selector_without_fallback_reverts() {
+	let mut endpoint = no_fallback::Endpoint::new(no_fallback::Instance);
+	endpoint.dispatch(&[0xde, 0xad, 0xbe, 0xef]);
+}
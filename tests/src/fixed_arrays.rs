@@ -0,0 +1,62 @@
+mod contract {
+	use pwasm_abi_derive::eth_abi;
+	use parity_hash::Address;
+	use bigint::U256;
+	use call;
+	use std::borrow::Cow;
+
+	#[eth_abi(Endpoint, Client)]
+	pub trait Ids {
+		fn set_ids(&mut self, ids: [u32; 3]) -> u32;
+		fn tag(&mut self, tag: [u8; 4], owners: [Address; 2]);
+	}
+
+	#[derive(Default)]
+	pub struct Instance {
+		pub ids: [u32; 3],
+		pub tagged: Option<([u8; 4], [Address; 2])>,
+	}
+
+	impl Ids for Instance {
+		fn set_ids(&mut self, ids: [u32; 3]) -> u32 {
+			self.ids = ids;
+			ids.iter().sum()
+		}
+
+		fn tag(&mut self, tag: [u8; 4], owners: [Address; 2]) {
+			self.tagged = Some((tag, owners));
+		}
+	}
+}
+
+use pwasm_abi::eth::{encode_values, ValueType};
+
+#[test]
+fn fixed_array_of_ints() {
+	// set_ids([1, 2, 3])
+	let mut payload = vec![0x9c, 0xd5, 0x6d, 0x26];
+	payload.extend_from_slice(&encode_values(&[[1u32, 2, 3].into()]));
+
+	let mut endpoint = contract::Endpoint::new(contract::Instance::default());
+	let result = endpoint.dispatch(&payload);
+
+	assert_eq!(endpoint.instance().ids, [1, 2, 3]);
+	assert_eq!(result[31], 6);
+}
+
+#[test]
+fn fixed_bytes_and_address_array() {
+	// tag(0xdeadbeef, [0x11..11, 0x22..22])
+	let mut payload = vec![0xaa, 0x57, 0x64, 0xbf];
+	payload.extend_from_slice(&encode_values(&[
+		ValueType::FixedBytes(vec![0xde, 0xad, 0xbe, 0xef]),
+		ValueType::FixedArray(vec![ValueType::Address([0x11u8; 20]), ValueType::Address([0x22u8; 20])]),
+	]));
+
+	let mut endpoint = contract::Endpoint::new(contract::Instance::default());
+	endpoint.dispatch(&payload);
+
+	let (tag, owners) = endpoint.instance().tagged.expect("tag was not invoked");
+	assert_eq!(tag, [0xde, 0xad, 0xbe, 0xef]);
+	assert_eq!(&owners[1][..], &[0x22u8; 20][..]);
+}
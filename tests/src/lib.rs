@@ -23,8 +23,32 @@ extern crate pwasm_abi;
 extern crate parity_hash;
 extern crate pwasm_abi_derive;
 extern crate bigint;
-
+extern crate serde_json;
+extern crate rustc_hex as hex;
+#[cfg(feature = "ethabi")]
+extern crate ethabi;
+
+mod arrays;
+mod borrowed;
+mod bytes32;
+mod constructor;
 mod erc20;
+mod erc721;
+mod events;
+mod fallback;
+mod fixed_arrays;
+mod multi_return;
+mod nonreentrant;
+mod optional;
+mod scalars;
+mod selectors;
+mod strict;
+mod structs;
+mod trace;
+#[cfg(feature = "ethabi")]
+mod ethabi_diff;
+#[cfg(target_pointer_width = "32")]
+mod wasm32;
 
 use pwasm_abi_derive::eth_abi;
 
@@ -70,10 +94,20 @@ const PAYLOAD_SAMPLE_3: &[u8] = &[
 #[cfg(test)]
 thread_local!(pub static LAST_CALL: RefCell<Vec<u8>> = RefCell::new(Vec::new()));
 
+// output of the next call, written to the result buffer as far as it fits
+#[cfg(test)]
+thread_local!(pub static NEXT_RESULT: RefCell<Vec<u8>> = RefCell::new(Vec::new()));
+
+// returns the length of the whole output, like `RETURNDATASIZE`, even if it didn't fit
 #[cfg(test)]
-fn call(_address: &Address, _value: U256, input: &[u8], _result: &mut [u8]) -> Result<(), ()> {
+fn call(_address: &Address, _value: U256, input: &[u8], result: &mut [u8]) -> Result<usize, ()> {
 	LAST_CALL.with(|v| { *v.borrow_mut() = input.to_vec(); });
-	Ok(())
+	NEXT_RESULT.with(|r| {
+		let output = r.borrow();
+		let len = ::std::cmp::min(result.len(), output.len());
+		result[..len].copy_from_slice(&output[..len]);
+		Ok(output.len())
+	})
 }
 
 #[cfg(test)]
@@ -108,7 +142,7 @@ fn baz_dispatch() {
 	let mut endpoint = Endpoint::new(TestContractInstance::default());
 	let result = endpoint.dispatch(PAYLOAD_SAMPLE_1);
 
-	assert_eq!(result, Vec::new());
+	assert_eq!(result, Vec::<u8>::new());
 
 	assert!(endpoint.inner.called, "`baz` method was not invoked");
 	assert!(!endpoint.inner.called_wrong, "wrong method was invoked");
@@ -147,7 +181,7 @@ fn sam_dispatch() {
 	let mut endpoint = Endpoint::new(TestContractInstance::default());
 	let result = endpoint.dispatch(PAYLOAD_SAMPLE_2);
 
-	assert_eq!(result, Vec::new());
+	assert_eq!(result, Vec::<u8>::new());
 
 	assert!(endpoint.inner.called, "`sam` method was not invoked");
 	assert!(!endpoint.inner.called_wrong, "wrong method was invoked");
@@ -194,4 +228,31 @@ fn baz_call() {
 		let val: &[u8] = &v.borrow()[..];
 		assert_eq!(val, PAYLOAD_SAMPLE_1);
 	});
-}
\ No newline at end of file
+}
+
+#[test]
+fn sorted_table() {
+	let client = Client::new(Address::zero());
+	let hashes: Vec<u32> = client.table.inner.iter().map(|hs| hs.hash()).collect();
+
+	assert!(client.table.is_sorted());
+	assert_eq!(hashes.len(), 4);
+	assert!(hashes.windows(2).all(|w| w[0] < w[1]), "generated table is not sorted: {:?}", hashes);
+}
+
+#[test]
+fn static_table() {
+	let table = Client::new(Address::zero()).table;
+
+	match table.inner {
+		Cow::Borrowed(_) => {},
+		Cow::Owned(_) => panic!("generated table should borrow its signatures"),
+	}
+	assert_eq!(table.constructor_signature().map(|s| s.params().len()), Some(1));
+
+	let result = table.dispatch(PAYLOAD_SAMPLE_3, |method_id, mut args| {
+		assert_eq!(method_id, 0x5ddab4d4);
+		args.pop()
+	}).expect("boo is in the table");
+	assert_eq!(&result[28..32], &[0x00, 0x00, 0x00, 0x45]);
+}
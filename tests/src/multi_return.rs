@@ -0,0 +1,55 @@
+use std::borrow::Cow;
+
+use pwasm_abi::eth::ValueType;
+use pwasm_abi_derive::eth_abi;
+use parity_hash::Address;
+use bigint::U256;
+use serde_json;
+use call;
+
+#[eth_abi(PairEndpoint, PairClient)]
+pub trait Pair {
+	fn reserve(&mut self) -> (U256, Address);
+}
+
+struct Instance;
+
+impl Pair for Instance {
+	fn reserve(&mut self) -> (U256, Address) {
+		(U256::from(1000), Address::from([0x11u8; 20]))
+	}
+}
+
+#[test]
+fn tuple_return_round_trip() {
+	// reserve()
+	let mut endpoint = PairEndpoint::new(Instance);
+	let result = endpoint.dispatch(&[0xcd, 0x32, 0x93, 0xde]);
+
+	// two static words, no offset in front
+	let mut expected = vec![0u8; 64];
+	expected[30] = 0x03;
+	expected[31] = 0xe8;
+	expected[44..].copy_from_slice(&[0x11u8; 20]);
+	assert_eq!(result, expected);
+
+	let signature = endpoint.table().hash_signature(0xcd3293de).unwrap().signature();
+	let decoded = signature.decode_result(&result).unwrap().expect("reserve returns values");
+	let mut amount = [0u8; 32];
+	U256::from(1000).to_big_endian(&mut amount);
+	assert_eq!(decoded, ValueType::Tuple(vec![ValueType::U256(amount), ValueType::Address([0x11u8; 20])]));
+
+	let (reserve, owner): (U256, Address) = decoded.into();
+	assert_eq!(reserve, U256::from(1000));
+	assert_eq!(owner, Address::from([0x11u8; 20]));
+}
+
+#[test]
+fn tuple_return_abi_json() {
+	let abi: serde_json::Value = serde_json::from_str(PairEndpoint::<Instance>::ABI_JSON).unwrap();
+
+	assert_eq!(abi[0]["outputs"], serde_json::json!([
+		{"name": "", "type": "uint256"},
+		{"name": "", "type": "address"},
+	]));
+}
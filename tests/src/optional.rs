@@ -0,0 +1,112 @@
+mod contract {
+	use pwasm_abi_derive::eth_abi;
+	use parity_hash::Address;
+	use bigint::U256;
+	use call;
+	use std::borrow::Cow;
+
+	#[eth_abi(Endpoint, Client)]
+	pub trait Registry {
+		fn allowances(&mut self, spender: Address) -> Option<Vec<U256>>;
+		fn memo(&mut self) -> Option<String>;
+		fn allowance(&mut self, spender: Address) -> Option<U256>;
+	}
+
+	#[derive(Default)]
+	pub struct Instance {
+		pub memo: Option<String>,
+	}
+
+	impl Registry for Instance {
+		fn allowances(&mut self, spender: Address) -> Option<Vec<U256>> {
+			match spender {
+				spender if spender == Address::zero() => None,
+				spender if spender == Address::from([0x11u8; 20]) => Some(Vec::new()),
+				_ => Some(vec![U256::from(1000)]),
+			}
+		}
+
+		fn memo(&mut self) -> Option<String> {
+			self.memo.clone()
+		}
+
+		fn allowance(&mut self, spender: Address) -> Option<U256> {
+			match spender == Address::zero() {
+				true => None,
+				false => Some(U256::from(1000)),
+			}
+		}
+	}
+}
+
+use pwasm_abi::eth::encode_values;
+use parity_hash::Address;
+use bigint::U256;
+use NEXT_RESULT;
+
+#[test]
+fn none_dispatches_as_no_output() {
+	let mut endpoint = contract::Endpoint::new(contract::Instance::default());
+
+	// allowances(0x00..00)
+	let mut payload = vec![0x2b, 0x60, 0x3c, 0x71];
+	payload.extend_from_slice(&[0u8; 32]);
+	assert!(endpoint.dispatch(&payload).is_empty());
+
+	// allowances(0x11..11), an empty array is still output
+	payload[16..].copy_from_slice(&[0x11u8; 20]);
+	assert_eq!(endpoint.dispatch(&payload), encode_values(&[Vec::<U256>::new().into()]));
+
+	// allowances(0x22..22)
+	payload[16..].copy_from_slice(&[0x22u8; 20]);
+	assert_eq!(endpoint.dispatch(&payload), encode_values(&[vec![U256::from(1000)].into()]));
+
+	// memo()
+	assert!(endpoint.dispatch(&[0x58, 0xc3, 0xb8, 0x70]).is_empty());
+
+	// allowance(0x00..00), a fixed-size result has no output for `None` as well
+	let mut payload = vec![0x3e, 0x5b, 0xea, 0xb9];
+	payload.extend_from_slice(&[0u8; 32]);
+	assert!(endpoint.dispatch(&payload).is_empty());
+
+	// allowance(0x11..11)
+	payload[16..].copy_from_slice(&[0x11u8; 20]);
+	assert_eq!(endpoint.dispatch(&payload), encode_values(&[U256::from(1000).into()]));
+}
+
+#[test]
+fn client_maps_empty_output_to_none() {
+	use self::contract::Registry;
+
+	let mut client = contract::Client::new(Address::zero());
+
+	NEXT_RESULT.with(|r| r.borrow_mut().clear());
+	assert_eq!(client.memo(), None);
+	assert_eq!(client.allowances(Address::zero()), None);
+	assert_eq!(client.allowance(Address::zero()), None);
+
+	NEXT_RESULT.with(|r| *r.borrow_mut() = encode_values(&["to do".to_owned().into()]));
+	assert_eq!(client.memo(), Some("to do".to_owned()));
+
+	NEXT_RESULT.with(|r| *r.borrow_mut() = encode_values(&[Vec::<U256>::new().into()]));
+	assert_eq!(client.allowances(Address::zero()), Some(Vec::new()));
+
+	NEXT_RESULT.with(|r| *r.borrow_mut() = encode_values(&[vec![U256::from(1000)].into()]));
+	assert_eq!(client.allowances(Address::zero()), Some(vec![U256::from(1000)]));
+
+	NEXT_RESULT.with(|r| *r.borrow_mut() = encode_values(&[U256::from(1000).into()]));
+	assert_eq!(client.allowance(Address::zero()), Some(U256::from(1000)));
+
+	// zero is output, unlike no output at all
+	NEXT_RESULT.with(|r| *r.borrow_mut() = encode_values(&[U256::zero().into()]));
+	assert_eq!(client.allowance(Address::zero()), Some(U256::zero()));
+}
+
+#[test]
+fn optional_result_abi_json() {
+	let abi: ::serde_json::Value = ::serde_json::from_str(contract::Endpoint::<contract::Instance>::ABI_JSON).unwrap();
+
+	assert_eq!(abi[0]["outputs"][0]["type"], "uint256[]");
+	assert_eq!(abi[1]["outputs"][0]["type"], "string");
+	assert_eq!(abi[2]["outputs"][0]["type"], "uint256");
+}
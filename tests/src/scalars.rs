@@ -0,0 +1,67 @@
+mod contract {
+	use pwasm_abi_derive::eth_abi;
+	use parity_hash::Address;
+	use bigint::U256;
+	use call;
+	use std::borrow::Cow;
+
+	#[eth_abi(Endpoint, Client)]
+	pub trait Scalars {
+		fn shift(&mut self, x: i64, by: u64, label: String) -> i64;
+		fn sum(&mut self, a: i32, b: u64, c: i64) -> i64;
+	}
+
+	#[derive(Default)]
+	pub struct Instance {
+		pub label: String,
+	}
+
+	impl Scalars for Instance {
+		fn shift(&mut self, x: i64, by: u64, label: String) -> i64 {
+			self.label = label;
+			x + by as i64
+		}
+
+		fn sum(&mut self, a: i32, b: u64, c: i64) -> i64 {
+			a as i64 + b as i64 + c
+		}
+	}
+}
+
+use pwasm_abi::eth::{encode_values, ValueType};
+
+#[test]
+fn wide_integers_and_string() {
+	// shift(40, 2, "gavofyork")
+	let mut payload = vec![0xd8, 0x0b, 0x5b, 0x7f];
+	payload.extend_from_slice(&encode_values(&[40i64.into(), 2u64.into(), "gavofyork".to_owned().into()]));
+
+	let mut endpoint = contract::Endpoint::new(contract::Instance::default());
+	let result = endpoint.dispatch(&payload);
+
+	assert_eq!(endpoint.instance().label, "gavofyork");
+	assert_eq!(result, encode_values(&[ValueType::I64(42)]));
+}
+
+#[test]
+fn minimum_signed_values() {
+	// shift(i64::MIN, 0, "")
+	let mut payload = vec![0xd8, 0x0b, 0x5b, 0x7f];
+	payload.extend_from_slice(&encode_values(&[i64::MIN.into(), 0u64.into(), String::new().into()]));
+
+	let mut endpoint = contract::Endpoint::new(contract::Instance::default());
+	let result = endpoint.dispatch(&payload);
+
+	assert_eq!(&result[..24], &[0xffu8; 24][..]);
+	assert_eq!(result, encode_values(&[ValueType::I64(i64::MIN)]));
+}
+
+#[test]
+fn params_keep_their_width_and_sign() {
+	// sum(-1, 2^40, -2^40), which fails to decode as the uint32 params the table had for these
+	let mut payload = vec![0xde, 0x00, 0x1e, 0x72];
+	payload.extend_from_slice(&encode_values(&[(-1i32).into(), (1u64 << 40).into(), (-(1i64 << 40)).into()]));
+
+	let mut endpoint = contract::Endpoint::new(contract::Instance::default());
+	assert_eq!(endpoint.dispatch(&payload), encode_values(&[ValueType::I64(-1)]));
+}
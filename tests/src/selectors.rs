@@ -0,0 +1,134 @@
+mod contract {
+	use pwasm_abi_derive::eth_abi;
+	use parity_hash::Address;
+	use bigint::U256;
+	use call;
+	use std::borrow::Cow;
+
+	#[eth_abi(Endpoint, Client)]
+	pub trait Token {
+		// dispatched as `transfer(address,uint256)`
+		#[abi(name = "transfer", signature = 0xa9059cbb)]
+		fn send(&mut self, to: Address, amount: U256) -> bool;
+
+		#[abi(name = "balanceOf")]
+		fn balance_of(&mut self, owner: Address) -> U256;
+
+		fn total_supply(&mut self) -> U256;
+
+		// no name has to hash to a forced selector
+		#[abi(signature = 0x12345678)]
+		fn mint(&mut self, amount: U256) -> U256;
+	}
+
+	#[derive(Default)]
+	pub struct Instance {
+		pub sent: Option<(Address, U256)>,
+	}
+
+	impl Token for Instance {
+		fn send(&mut self, to: Address, amount: U256) -> bool {
+			self.sent = Some((to, amount));
+			true
+		}
+
+		fn balance_of(&mut self, _owner: Address) -> U256 {
+			U256::from(1000)
+		}
+
+		fn total_supply(&mut self) -> U256 {
+			U256::from(1000000)
+		}
+
+		fn mint(&mut self, amount: U256) -> U256 {
+			amount + U256::from(1000000)
+		}
+	}
+}
+
+use pwasm_abi::eth::{encode_values, compute_selector};
+use parity_hash::Address;
+use bigint::U256;
+use {LAST_CALL, NEXT_RESULT};
+
+fn transfer_payload() -> Vec<u8> {
+	// transfer(0x11..11, 1000)
+	let mut payload = vec![0xa9, 0x05, 0x9c, 0xbb];
+	payload.extend_from_slice(&encode_values(&[Address::from([0x11u8; 20]).into(), U256::from(1000).into()]));
+	payload
+}
+
+#[test]
+fn dispatch_forced_selector() {
+	let mut endpoint = contract::Endpoint::new(contract::Instance::default());
+	let result = endpoint.dispatch(&transfer_payload());
+
+	let (to, amount) = endpoint.instance().sent.expect("send was not invoked");
+	assert!(to == Address::from([0x11u8; 20]));
+	assert_eq!(amount, U256::from(1000));
+	assert_eq!(result[31], 1);
+}
+
+#[test]
+#[should_panic(expected = "unknown method selector")]
+fn computed_selector_is_replaced() {
+	let mut payload = compute_selector("send(address,uint256)").to_be_bytes().to_vec();
+	payload.extend_from_slice(&transfer_payload()[4..]);
+
+	contract::Endpoint::new(contract::Instance::default()).dispatch(&payload);
+}
+
+#[test]
+fn call_forced_selector() {
+	use self::contract::Token;
+
+	let mut client = contract::Client::new(Address::zero());
+	NEXT_RESULT.with(|r| *r.borrow_mut() = encode_values(&[true.into()]));
+	assert!(client.send([0x11u8; 20].into(), U256::from(1000)));
+	LAST_CALL.with(|v| assert_eq!(*v.borrow(), transfer_payload()));
+
+	// the selector follows the ABI name, without a forced one as well
+	let mut endpoint = contract::Endpoint::new(contract::Instance::default());
+	let mut payload = compute_selector("balanceOf(address)").to_be_bytes().to_vec();
+	payload.extend_from_slice(&[0u8; 32]);
+	assert_eq!(endpoint.dispatch(&payload), encode_values(&[U256::from(1000).into()]));
+
+	// methods without the attributes keep the Rust name
+	let payload = compute_selector("total_supply()").to_be_bytes().to_vec();
+	assert_eq!(endpoint.dispatch(&payload), encode_values(&[U256::from(1000000).into()]));
+}
+
+#[test]
+fn selector_without_known_name() {
+	use self::contract::Token;
+
+	let mut payload = vec![0x12, 0x34, 0x56, 0x78];
+	payload.extend_from_slice(&encode_values(&[U256::from(5).into()]));
+
+	let mut endpoint = contract::Endpoint::new(contract::Instance::default());
+	assert_eq!(endpoint.dispatch(&payload), encode_values(&[U256::from(1000005).into()]));
+
+	let mut client = contract::Client::new(Address::zero());
+	NEXT_RESULT.with(|r| *r.borrow_mut() = encode_values(&[U256::from(1000005).into()]));
+	assert_eq!(client.mint(U256::from(5)), U256::from(1000005));
+	LAST_CALL.with(|v| assert_eq!(*v.borrow(), payload));
+}
+
+#[test]
+fn abi_json_uses_abi_names() {
+	let abi: ::serde_json::Value = ::serde_json::from_str(contract::Endpoint::<contract::Instance>::ABI_JSON).unwrap();
+
+	assert_eq!(abi[0]["name"], "transfer");
+	assert_eq!(abi[1]["name"], "balanceOf");
+	assert_eq!(abi[2]["name"], "total_supply");
+}
+
+#[test]
+fn abi_json_lists_forced_selectors() {
+	let abi: ::serde_json::Value = ::serde_json::from_str(contract::Endpoint::<contract::Instance>::ABI_JSON).unwrap();
+
+	assert_eq!(abi[0]["selector"], "0xa9059cbb");
+	assert!(abi[1].get("selector").is_none());
+	assert_eq!(abi[3]["name"], "mint");
+	assert_eq!(abi[3]["selector"], "0x12345678");
+}
@@ -0,0 +1,81 @@
+mod contract {
+	use pwasm_abi_derive::{eth_abi, AbiType};
+	use parity_hash::Address;
+	use bigint::U256;
+	use call;
+	use std::borrow::Cow;
+
+	#[derive(AbiType, Debug, PartialEq)]
+	pub struct Bid {
+		pub amount: U256,
+		pub sealed: bool,
+	}
+
+	#[eth_abi(Endpoint, Client, strict, structs(Bid))]
+	pub trait Auction {
+		fn transfer(&mut self, to: Address, amount: U256) -> bool;
+		fn submit(&mut self, bid: Bid, memo: &str);
+	}
+
+	#[derive(Default)]
+	pub struct Instance {
+		pub bids: Vec<(Bid, String)>,
+	}
+
+	impl Auction for Instance {
+		fn transfer(&mut self, _to: Address, _amount: U256) -> bool {
+			true
+		}
+
+		fn submit(&mut self, bid: Bid, memo: &str) {
+			self.bids.push((bid, memo.to_owned()));
+		}
+	}
+}
+
+use pwasm_abi::eth::{encode_values, Error, TryFromValue, ValueError, ValueType};
+use bigint::U256;
+use self::contract::Bid;
+
+fn submit_payload() -> Vec<u8> {
+	// submit((1000, true), "sealed")
+	let mut payload = vec![0x21, 0x78, 0x01, 0xd9];
+	let bid = Bid { amount: U256::from(1000), sealed: true };
+	payload.extend_from_slice(&encode_values(&[bid.into(), "sealed".into()]));
+	payload
+}
+
+#[test]
+fn try_dispatch_valid_call() {
+	let mut endpoint = contract::Endpoint::new(contract::Instance::default());
+
+	assert_eq!(endpoint.try_dispatch(&submit_payload()), Ok(Vec::new()));
+	assert_eq!(endpoint.instance().bids, vec![(Bid { amount: U256::from(1000), sealed: true }, "sealed".to_owned())]);
+
+	// transfer(0x00..00, 0)
+	let mut payload = vec![0xa9, 0x05, 0x9c, 0xbb];
+	payload.extend_from_slice(&[0u8; 64]);
+	assert_eq!(endpoint.try_dispatch(&payload), Ok(encode_values(&[true.into()])));
+}
+
+#[test]
+fn try_dispatch_reports_errors() {
+	let mut endpoint = contract::Endpoint::new(contract::Instance::default());
+
+	let mut truncated = submit_payload();
+	truncated.truncate(4 + 64);
+	assert_eq!(endpoint.try_dispatch(&truncated), Err(Error::UnexpectedEnd { position: 96 }));
+	assert_eq!(endpoint.try_dispatch(&[0x12, 0x34, 0x56, 0x78]), Err(Error::UnknownSignature));
+	assert_eq!(endpoint.try_dispatch(&[0x12]), Err(Error::NoLengthForSignature));
+	assert!(endpoint.instance().bids.is_empty());
+}
+
+#[test]
+fn struct_checked_conversion() {
+	let bid = ValueType::Tuple(vec![U256::from(7).into(), true.into()]);
+	assert_eq!(Bid::try_from_value(bid), Ok(Bid { amount: U256::from(7), sealed: true }));
+
+	assert_eq!(Bid::try_from_value(ValueType::U32(7)), Err(ValueError::TypeMismatch));
+	assert_eq!(Bid::try_from_value(ValueType::Tuple(vec![true.into()])), Err(ValueError::LengthMismatch { expected: 2, got: 1 }));
+	assert_eq!(Bid::try_from_value(ValueType::Tuple(vec![true.into(), true.into()])), Err(ValueError::TypeMismatch));
+}
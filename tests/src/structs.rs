@@ -0,0 +1,180 @@
+mod contract {
+	use pwasm_abi_derive::{eth_abi, AbiType};
+	use parity_hash::Address;
+	use bigint::U256;
+	use call;
+	use std::borrow::Cow;
+
+	#[derive(AbiType, Debug, Clone, PartialEq)]
+	pub struct Order {
+		pub price: U256,
+		pub maker: Address,
+	}
+
+	#[derive(AbiType, Debug, Clone, PartialEq)]
+	#[abi(structs(Order))]
+	pub struct Fill(pub Order, pub u64);
+
+	#[derive(AbiType, Debug, Clone, PartialEq)]
+	pub struct Receipt {
+		pub ok: bool,
+		pub data: Vec<u8>,
+	}
+
+	#[eth_abi(Endpoint, Client, structs(Order, Fill, Receipt))]
+	pub trait Exchange {
+		fn submit(&mut self, order: Order, urgent: bool);
+		fn fill(&mut self, fills: Vec<Fill>) -> u32;
+		fn best(&mut self) -> Order;
+		fn receipt(&mut self) -> Receipt;
+		fn amend(&mut self, amendment: (Order, u32), limit: (u32, bool));
+	}
+
+	#[derive(Default)]
+	pub struct Instance {
+		pub submitted: Option<(Order, bool)>,
+		pub filled: Vec<Fill>,
+	}
+
+	impl Exchange for Instance {
+		fn submit(&mut self, order: Order, urgent: bool) {
+			self.submitted = Some((order, urgent));
+		}
+
+		fn fill(&mut self, fills: Vec<Fill>) -> u32 {
+			self.filled = fills;
+			self.filled.len() as u32
+		}
+
+		fn best(&mut self) -> Order {
+			self.submitted.clone().expect("no order submitted").0
+		}
+
+		fn receipt(&mut self) -> Receipt {
+			Receipt { ok: true, data: vec![1, 2, 3] }
+		}
+
+		fn amend(&mut self, _amendment: (Order, u32), _limit: (u32, bool)) {
+		}
+	}
+}
+
+use pwasm_abi::eth::{encode_values, decode_values, AbiType, ParamType};
+use serde_json::json;
+use parity_hash::Address;
+use bigint::U256;
+use self::contract::{Order, Fill, Receipt};
+use {LAST_CALL, NEXT_RESULT};
+
+fn order(price: u64, maker: u8) -> Order {
+	Order { price: U256::from(price), maker: [maker; 20].into() }
+}
+
+fn submit_payload() -> Vec<u8> {
+	// submit((1000, 0x11..11), true)
+	let mut payload = vec![0xd2, 0xaf, 0x30, 0x07];
+	payload.extend_from_slice(&encode_values(&[order(1000, 0x11).into(), true.into()]));
+	payload
+}
+
+#[test]
+fn struct_param_type() {
+	assert_eq!(Order::param_type(), ParamType::Tuple(vec![ParamType::U256, ParamType::Address].into()));
+	assert_eq!(Fill::param_type(), ParamType::Tuple(vec![Order::param_type(), ParamType::U64].into()));
+
+	let encoded = encode_values(&[order(1000, 0x11).into()]);
+	let mut decoded = decode_values(&[Order::param_type()], &encoded).unwrap();
+	assert_eq!(Order::from(decoded.pop().unwrap()), order(1000, 0x11));
+}
+
+#[test]
+fn dispatch_struct_param() {
+	let mut endpoint = contract::Endpoint::new(contract::Instance::default());
+	endpoint.dispatch(&submit_payload());
+
+	assert_eq!(endpoint.instance().submitted, Some((order(1000, 0x11), true)));
+
+	let result = endpoint.dispatch(&[0xd0, 0x94, 0x0a, 0x5d]);
+	assert_eq!(result, encode_values(&[order(1000, 0x11).into()]));
+}
+
+#[test]
+fn dispatch_struct_array() {
+	let fills = vec![Fill(order(1000, 0x11), 5), Fill(order(2000, 0x22), 7)];
+
+	// fill([((1000, 0x11..11), 5), ((2000, 0x22..22), 7)])
+	let mut payload = vec![0xb7, 0x6f, 0x54, 0x73];
+	payload.extend_from_slice(&encode_values(&[fills.clone().into()]));
+
+	let mut endpoint = contract::Endpoint::new(contract::Instance::default());
+	let result = endpoint.dispatch(&payload);
+
+	assert_eq!(endpoint.instance().filled, fills);
+	assert_eq!(result[31], 2);
+}
+
+#[test]
+fn call_with_struct() {
+	use self::contract::Exchange;
+
+	let mut client = contract::Client::new(Address::zero());
+	client.submit(order(1000, 0x11), true);
+	LAST_CALL.with(|v| assert_eq!(*v.borrow(), submit_payload()));
+
+	NEXT_RESULT.with(|r| *r.borrow_mut() = encode_values(&[order(2000, 0x22).into()]));
+	assert_eq!(client.best(), order(2000, 0x22));
+}
+
+#[test]
+fn struct_abi_json() {
+	let abi: ::serde_json::Value = ::serde_json::from_str(contract::Endpoint::<contract::Instance>::ABI_JSON).unwrap();
+
+	let order = json!([
+		{"name": "price", "type": "uint256"},
+		{"name": "maker", "type": "address"},
+	]);
+	assert_eq!(abi[0]["inputs"][0], json!({"name": "order", "type": "tuple", "components": order}));
+	assert_eq!(abi[1]["inputs"][0], json!({"name": "fills", "type": "tuple[]", "components": [
+		{"name": "", "type": "tuple", "components": order},
+		{"name": "", "type": "uint64"},
+	]}));
+	assert_eq!(abi[2]["outputs"][0], json!({"name": "", "type": "tuple", "components": order}));
+
+	// plain tuples list their members as well, in and around structs
+	assert_eq!(abi[4]["inputs"], json!([
+		{"name": "amendment", "type": "tuple", "components": [
+			{"name": "", "type": "tuple", "components": order},
+			{"name": "", "type": "uint32"},
+		]},
+		{"name": "limit", "type": "tuple", "components": [
+			{"name": "", "type": "uint32"},
+			{"name": "", "type": "bool"},
+		]},
+	]));
+}
+
+#[test]
+fn struct_components_json() {
+	let components: ::serde_json::Value = ::serde_json::from_str(&format!("[{}]", Fill::COMPONENTS_JSON)).unwrap();
+
+	assert_eq!(components[0]["type"], "tuple");
+	assert_eq!(components[0]["components"][1], json!({"name": "maker", "type": "address"}));
+	assert_eq!(components[1], json!({"name": "", "type": "uint64"}));
+}
+
+#[test]
+fn dynamic_struct_result() {
+	use self::contract::Exchange;
+
+	let receipt = Receipt { ok: true, data: vec![1, 2, 3] };
+	let mut endpoint = contract::Endpoint::new(contract::Instance::default());
+	let result = endpoint.dispatch(&[0xe1, 0xe6, 0xb8, 0x98]);
+
+	// a single dynamic struct is at an offset, like any dynamic result
+	assert_eq!(result, encode_values(&[receipt.clone().into()]));
+	assert_eq!(result[31], 0x20);
+
+	let mut client = contract::Client::new(Address::zero());
+	NEXT_RESULT.with(|r| *r.borrow_mut() = result);
+	assert_eq!(client.receipt(), receipt);
+}
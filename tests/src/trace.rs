@@ -0,0 +1,53 @@
+use std::cell::RefCell;
+use std::borrow::Cow;
+
+use pwasm_abi::eth::{ValueType, encode_values};
+use pwasm_abi_derive::eth_abi;
+use parity_hash::Address;
+use bigint::U256;
+use call;
+
+// traced args are kept encoded, `ValueType` can't be cloned
+thread_local!(static TRACED: RefCell<Vec<(u32, Vec<u8>)>> = RefCell::new(Vec::new()));
+
+fn trace(method_id: u32, args: &[ValueType]) {
+	TRACED.with(|t| t.borrow_mut().push((method_id, encode_values(args))));
+}
+
+#[eth_abi(TokenEndpoint, TokenClient, trace(self::trace))]
+pub trait Token {
+	fn transfer(&mut self, to: Address, amount: U256) -> bool;
+}
+
+#[derive(Default)]
+struct Instance {
+	transferred: bool,
+}
+
+impl Token for Instance {
+	fn transfer(&mut self, _to: Address, _amount: U256) -> bool {
+		// the hook runs before the method
+		TRACED.with(|t| assert_eq!(t.borrow().len(), 1));
+		self.transferred = true;
+		true
+	}
+}
+
+#[test]
+fn trace_hook_sees_call() {
+	// transfer(0x11..11, 1000)
+	let mut payload = vec![0xa9, 0x05, 0x9c, 0xbb];
+	payload.extend_from_slice(&[0u8; 12]);
+	payload.extend_from_slice(&[0x11u8; 20]);
+	let mut amount = [0u8; 32];
+	amount[30] = 0x03;
+	amount[31] = 0xe8;
+	payload.extend_from_slice(&amount);
+
+	let mut endpoint = TokenEndpoint::new(Instance::default());
+	endpoint.dispatch(&payload);
+
+	assert!(endpoint.instance().transferred);
+	let expected_args = encode_values(&[ValueType::Address([0x11u8; 20]), ValueType::U256(amount)]);
+	TRACED.with(|t| assert_eq!(*t.borrow(), vec![(0xa9059cbb, expected_args)]));
+}
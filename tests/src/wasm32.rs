@@ -0,0 +1,36 @@
+mod contract {
+	use pwasm_abi_derive::eth_abi;
+	use parity_hash::Address;
+	use bigint::U256;
+	use call;
+	use std::borrow::Cow;
+
+	#[eth_abi(Endpoint, Client)]
+	pub trait IndexContract {
+		fn offset(&mut self, index: usize, delta: isize) -> usize;
+	}
+
+	#[derive(Default)]
+	pub struct Instance;
+
+	impl IndexContract for Instance {
+		fn offset(&mut self, index: usize, delta: isize) -> usize {
+			(index as isize + delta) as usize
+		}
+	}
+}
+
+// offset(3, 2)
+const SAMPLE: &'static [u8] = &[
+	0x94, 0xa6, 0xf1, 0xef,
+	0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03,
+	0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02,
+];
+
+#[test]
+fn pointer_sized_params() {
+	let mut endpoint = contract::Endpoint::new(contract::Instance::default());
+	let result = endpoint.dispatch(SAMPLE);
+
+	assert_eq!(&result[28..32], &[0x00, 0x00, 0x00, 0x05]);
+}